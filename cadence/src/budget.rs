@@ -0,0 +1,289 @@
+use rust_decimal::Decimal;
+
+use crate::pin_alignment::{NetEnd, ParentNet};
+use crate::sdc::{Clock, Constraints, PortGroup, SdcError};
+
+/// How the constraints of a child block are derived from those of its parent
+#[derive(Debug, Clone)]
+pub struct BudgetPolicy {
+    /// Clock ports of the child and the parent clocks driving them. When empty, each parent
+    /// clock defined on a port drives the child port of the same name, if the child has one;
+    /// [`connected_clock_ports`] derives them from the nets of the parent instead.
+    pub clock_ports: Vec<(String, String)>,
+    /// Fraction of the clock period spent outside the block before its inputs
+    pub input_fraction: Decimal,
+    /// Fraction of the clock period spent outside the block after its outputs
+    pub output_fraction: Decimal,
+}
+
+impl Default for BudgetPolicy {
+    fn default() -> Self {
+        BudgetPolicy {
+            clock_ports: Vec::new(),
+            input_fraction: Decimal::new(4, 1),
+            output_fraction: Decimal::new(4, 1),
+        }
+    }
+}
+
+/// Returns the primary clock `name` is derived from, scaled to the frequency of `name`
+fn effective_clock(parent: &Constraints, name: &str) -> Option<Clock> {
+    if let Some(clock) = parent.get_clock(name) {
+        return Some(clock.clone());
+    }
+    let generated = parent.generated_clocks.iter().find(|c| c.name == name)?;
+    let master = parent.get_clock(&generated.master)?;
+    let divide_by = Decimal::from(generated.divide_by.unwrap_or(1));
+    let multiply_by = Decimal::from(generated.multiply_by.unwrap_or(1));
+    Some(Clock {
+        name: generated.name.clone(),
+        port: None,
+        period: master.period * divide_by / multiply_by,
+        waveform: None,
+        uncertainty: master.uncertainty,
+    })
+}
+
+/// Returns the ports of the child instances `instances` driven by the parent clocks defined on
+/// ports, paired with those clocks, by following the net of each clock port to the instance
+/// ports on it. A child port driven by different clocks in different instances keeps the
+/// first one.
+pub fn connected_clock_ports(
+    parent: &Constraints,
+    nets: &[ParentNet],
+    instances: &[&str],
+) -> Vec<(String, String)> {
+    let mut clock_ports: Vec<(String, String)> = Vec::new();
+    for clock in &parent.clocks {
+        let Some(port) = &clock.port else {
+            continue;
+        };
+        let driven = nets
+            .iter()
+            .filter(|net| &net.name == port)
+            .flat_map(|net| &net.ends);
+        for end in driven {
+            if let NetEnd::Instance { instance, port } = end
+                && instances.contains(&instance.as_str())
+                && !clock_ports.iter().any(|(p, _)| p == port)
+            {
+                clock_ports.push((port.clone(), clock.name.clone()));
+            }
+        }
+    }
+    clock_ports
+}
+
+/// Derives default constraints for a child block with ports `ports`: each clock port gets the
+/// period of the parent clock driving it, and the I/O of the block is budgeted against the
+/// first one. The result is checked against `ports`.
+pub fn budget_child(
+    parent: &Constraints,
+    policy: &BudgetPolicy,
+    ports: &[String],
+) -> Result<Constraints, Vec<SdcError>> {
+    let clock_ports = if policy.clock_ports.is_empty() {
+        parent
+            .clocks
+            .iter()
+            .filter_map(|c| Some((c.port.clone()?, c.name.clone())))
+            .filter(|(port, _)| ports.contains(port))
+            .collect()
+    } else {
+        policy.clock_ports.clone()
+    };
+
+    // Virtual clocks only time the I/O of a block, so the child keeps them as they are
+    let virtual_clocks: Vec<Clock> = parent
+        .clocks
+        .iter()
+        .filter(|c| c.port.is_none())
+        .cloned()
+        .collect();
+    // (child clock, parent clock) pairs
+    let renamed: Vec<(String, String)> = clock_ports
+        .iter()
+        .cloned()
+        .chain(
+            virtual_clocks
+                .iter()
+                .map(|c| (c.name.clone(), c.name.clone())),
+        )
+        .collect();
+
+    let mut child = Constraints::new();
+    for (port, parent_clock) in &clock_ports {
+        let clock = effective_clock(parent, parent_clock)
+            .ok_or_else(|| vec![SdcError::UnknownClock(parent_clock.clone())])?;
+        child.clock(Clock {
+            name: port.clone(),
+            port: Some(port.clone()),
+            ..clock
+        });
+    }
+    for clock in virtual_clocks {
+        child.clock(clock);
+    }
+
+    for groups in &parent.clock_groups {
+        let mapped: Vec<Vec<String>> = groups
+            .groups
+            .iter()
+            .map(|group| {
+                renamed
+                    .iter()
+                    .filter(|(_, parent_clock)| group.contains(parent_clock))
+                    .map(|(clock, _)| clock.clone())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|group| !group.is_empty())
+            .collect();
+        if !mapped.is_empty() && (mapped.len() > 1 || groups.groups.len() == 1) {
+            child.clock_group(groups.relation, mapped);
+        }
+    }
+
+    if let Some(clock) = child.clocks.first().cloned() {
+        let ports: Vec<String> = clock_ports.iter().map(|(port, _)| port.clone()).collect();
+        let inputs = if ports.is_empty() {
+            PortGroup::AllInputs
+        } else {
+            PortGroup::InputsExcept(ports)
+        };
+        child
+            .input_delay(&clock.name, clock.period * policy.input_fraction, inputs)
+            .output_delay(
+                &clock.name,
+                clock.period * policy.output_fraction,
+                PortGroup::AllOutputs,
+            );
+    }
+    for load in &parent.loads {
+        if load.ports == PortGroup::AllOutputs {
+            child.load(load.capacitance, PortGroup::AllOutputs);
+        }
+    }
+    child.validate(ports)?;
+    Ok(child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::innovus::Side;
+    use crate::sdc::{ClockRelation, GeneratedClock, PathPoints};
+
+    fn ports(names: &[&str]) -> Vec<String> {
+        names.iter().map(|&n| n.into()).collect()
+    }
+
+    #[test]
+    fn propagates_clocks_and_budgets_io() {
+        let mut parent = Constraints::single_clock("clk", Decimal::new(20, 1));
+        parent.generated_clock(GeneratedClock {
+            name: "slow".into(),
+            source: PathPoints::Ports(vec!["clk".into()]),
+            master: "clk".into(),
+            target: PathPoints::Pins(vec!["div/q".into()]),
+            divide_by: Some(4),
+            multiply_by: None,
+        });
+
+        let child = budget_child(&parent, &BudgetPolicy::default(), &ports(&["clk", "y"])).unwrap();
+        assert_eq!(child.get_clock("clk").unwrap().period, Decimal::new(20, 1));
+        assert_eq!(child.clock_groups[0].relation, ClockRelation::Asynchronous);
+        assert_eq!(child.input_delays[0].delay, Decimal::new(8, 1));
+        assert_eq!(
+            child.input_delays[0].ports,
+            PortGroup::InputsExcept(vec!["clk".into()])
+        );
+        assert_eq!(child.loads.len(), 1);
+
+        let policy = BudgetPolicy {
+            clock_ports: vec![("clk_in".into(), "slow".into())],
+            input_fraction: Decimal::new(25, 2),
+            output_fraction: Decimal::new(5, 1),
+        };
+        let child = budget_child(&parent, &policy, &ports(&["clk_in", "y"])).unwrap();
+        assert_eq!(
+            child.get_clock("clk_in").unwrap().period,
+            Decimal::new(8, 0)
+        );
+        assert_eq!(child.input_delays[0].delay, Decimal::new(2, 0));
+        assert_eq!(child.output_delays[0].delay, Decimal::new(4, 0));
+
+        let policy = BudgetPolicy {
+            clock_ports: vec![("clk".into(), "missing".into())],
+            ..Default::default()
+        };
+        assert_eq!(
+            budget_child(&parent, &policy, &ports(&["clk"])),
+            Err(vec![SdcError::UnknownClock("missing".into())])
+        );
+
+        // A clock port the child does not have is reported rather than constrained
+        let policy = BudgetPolicy {
+            clock_ports: vec![("clk_in".into(), "clk".into())],
+            ..Default::default()
+        };
+        let errors = budget_child(&parent, &policy, &ports(&["ck", "y"])).unwrap_err();
+        assert!(errors.contains(&SdcError::UnknownPort("clk_in".into())));
+
+        // Without a matching port the child is combinational
+        let child = budget_child(&parent, &BudgetPolicy::default(), &ports(&["a", "y"])).unwrap();
+        assert!(child.clocks.is_empty());
+    }
+
+    #[test]
+    fn maps_clocks_through_parent_nets() {
+        let mut parent = Constraints::single_clock("clk", Decimal::new(20, 1));
+        parent.clock(Clock::virtual_clock("vclk", Decimal::new(10, 0)));
+        let pin = |instance: &str, port: &str| NetEnd::Instance {
+            instance: instance.into(),
+            port: port.into(),
+        };
+        let nets = vec![
+            ParentNet::new(
+                "clk",
+                vec![
+                    NetEnd::Boundary(Side::Left),
+                    pin("u0", "ck"),
+                    pin("u1", "ck"),
+                    pin("ram", "clock"),
+                ],
+            ),
+            ParentNet::new("ck", vec![NetEnd::Boundary(Side::Top), pin("u0", "en")]),
+        ];
+
+        let clock_ports = connected_clock_ports(&parent, &nets, &["u0", "u1"]);
+        assert_eq!(clock_ports, [("ck".to_string(), "clk".to_string())]);
+
+        let policy = BudgetPolicy {
+            clock_ports,
+            ..Default::default()
+        };
+        let child = budget_child(&parent, &policy, &ports(&["ck", "en", "q"])).unwrap();
+        assert_eq!(child.clocks[0].port.as_deref(), Some("ck"));
+        assert_eq!(child.clocks[0].period, Decimal::new(20, 1));
+        assert_eq!(child.clocks[1].name, "vclk");
+        assert_eq!(
+            child.input_delays[0].ports,
+            PortGroup::InputsExcept(vec!["ck".into()])
+        );
+    }
+
+    #[test]
+    fn keeps_virtual_clocks_of_combinational_parents() {
+        let mut parent = Constraints::new();
+        parent
+            .clock(Clock::virtual_clock("vclk", Decimal::new(10, 0)))
+            .clock_group(ClockRelation::Asynchronous, vec![vec!["vclk".into()]]);
+
+        let child = budget_child(&parent, &BudgetPolicy::default(), &[]).unwrap();
+        assert_eq!(child.clocks, parent.clocks);
+        assert_eq!(child.clock_groups, parent.clock_groups);
+        assert_eq!(child.input_delays[0].delay, Decimal::new(4, 0));
+        assert_eq!(child.input_delays[0].ports, PortGroup::AllInputs);
+        assert_eq!(child.output_delays[0].clock, "vclk");
+    }
+}
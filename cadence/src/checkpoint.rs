@@ -0,0 +1,280 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::{Checkpoint, Substep};
+
+/// Prefix of the databases written after each checkpointed substep
+pub const CHECKPOINT_PREFIX: &str = "post_";
+
+/// A checkpoint database found in a work dir
+#[derive(Debug, Clone)]
+pub struct CheckpointInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointError {
+    /// The checkpoint database does not exist on disk
+    Missing(PathBuf),
+    /// The checkpoint name does not match any substep in the flow
+    UnknownSubstep(String),
+    /// The substep the checkpoint is named after does not write a checkpoint
+    NotCheckpointed(String),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Missing(path) => {
+                write!(f, "checkpoint database {} does not exist", path.display())
+            }
+            CheckpointError::UnknownSubstep(name) => {
+                write!(f, "checkpoint {name} does not match any substep")
+            }
+            CheckpointError::NotCheckpointed(name) => {
+                write!(f, "substep {name} does not write a checkpoint")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+/// Which checkpoints survive [`prune_checkpoints`]; the default keeps only the latest one
+#[derive(Debug, Clone)]
+pub struct PrunePolicy {
+    /// Number of most recently written checkpoints to keep
+    pub keep_last: usize,
+    /// Checkpoints kept regardless of age
+    pub keep_names: Vec<String>,
+}
+
+impl Default for PrunePolicy {
+    fn default() -> Self {
+        PrunePolicy {
+            keep_last: 1,
+            keep_names: Vec::new(),
+        }
+    }
+}
+
+fn disk_usage(path: &Path) -> io::Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += disk_usage(&entry?.path())?;
+    }
+    Ok(size)
+}
+
+/// Lists the `post_*` checkpoints in `work_dir`, oldest first
+pub fn list_checkpoints(work_dir: &Path) -> io::Result<Vec<CheckpointInfo>> {
+    let mut checkpoints = Vec::new();
+    if !work_dir.exists() {
+        return Ok(checkpoints);
+    }
+    for entry in fs::read_dir(work_dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(name) = file_name.strip_prefix(CHECKPOINT_PREFIX) else {
+            continue;
+        };
+        let metadata = fs::symlink_metadata(entry.path())?;
+        if metadata.file_type().is_symlink() {
+            continue;
+        }
+        checkpoints.push(CheckpointInfo {
+            name: name.to_string(),
+            path: entry.path(),
+            size: disk_usage(&entry.path())?,
+            modified: metadata.modified()?,
+        });
+    }
+    checkpoints.sort_by_key(|c| c.modified);
+    Ok(checkpoints)
+}
+
+/// Checks that `checkpoint` exists on disk and names one of `substeps` that writes a
+/// checkpoint
+pub fn validate_checkpoint(
+    checkpoint: &Checkpoint,
+    substeps: &[Substep],
+) -> Result<(), CheckpointError> {
+    let Some(substep) = substeps.iter().find(|s| s.name == checkpoint.name) else {
+        return Err(CheckpointError::UnknownSubstep(checkpoint.name.clone()));
+    };
+    if !substep.checkpoint {
+        return Err(CheckpointError::NotCheckpointed(checkpoint.name.clone()));
+    }
+    if !checkpoint.path.exists() {
+        return Err(CheckpointError::Missing(checkpoint.path.clone()));
+    }
+    Ok(())
+}
+
+/// Removes the checkpoints in `work_dir` not retained by `policy`, returning the removed paths
+pub fn prune_checkpoints(work_dir: &Path, policy: &PrunePolicy) -> io::Result<Vec<PathBuf>> {
+    let checkpoints = list_checkpoints(work_dir)?;
+    let keep_from = checkpoints.len().saturating_sub(policy.keep_last);
+    let mut removed = Vec::new();
+    for checkpoint in &checkpoints[..keep_from] {
+        if policy.keep_names.contains(&checkpoint.name) {
+            continue;
+        }
+        if checkpoint.path.is_dir() {
+            fs::remove_dir_all(&checkpoint.path)?;
+        } else {
+            fs::remove_file(&checkpoint.path)?;
+        }
+        removed.push(checkpoint.path.clone());
+    }
+    Ok(removed)
+}
+
+/// Finds the furthest `post_<substep>` checkpoint in `work_dir` that can be resumed from.
+///
+/// Checkpointed substeps are walked in order, skipping databases removed by
+/// [`prune_checkpoints`], and the chain stops at a database older than the one before it,
+/// since that one was left over from an earlier run with a different substep list.
+pub fn latest_checkpoint(work_dir: &Path, substeps: &[Substep]) -> Option<Checkpoint> {
+    let mut latest: Option<(Checkpoint, SystemTime)> = None;
+    for substep in substeps.iter().filter(|s| s.checkpoint) {
+        let path = work_dir.join(format!("{CHECKPOINT_PREFIX}{}", substep.name));
+        let Some(modified) = fs::metadata(&path).and_then(|m| m.modified()).ok() else {
+            continue;
+        };
+        if latest.as_ref().is_some_and(|(_, prev)| modified < *prev) {
+            break;
+        }
+        latest = Some((
+            Checkpoint {
+                name: substep.name.clone(),
+                path,
+            },
+            modified,
+        ));
+    }
+    latest.map(|(checkpoint, _)| checkpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn substep(name: &str) -> Substep {
+        Substep {
+            name: name.into(),
+            command: String::new(),
+            checkpoint: true,
+        }
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rivet-checkpoint-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_checkpoint(dir: &Path, name: &str) {
+        fs::write(dir.join(format!("post_{name}")), name).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    #[test]
+    fn lists_validates_and_prunes_checkpoints() {
+        let dir = scratch_dir("prune");
+        let substeps = [substep("a"), substep("b"), substep("c")];
+        for name in ["a", "b", "c"] {
+            write_checkpoint(&dir, name);
+        }
+        fs::write(dir.join("syn.tcl"), "").unwrap();
+
+        let names: Vec<String> = list_checkpoints(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, ["a", "b", "c"]);
+
+        let unknown = Checkpoint {
+            name: "d".into(),
+            path: dir.join("post_d"),
+        };
+        assert_eq!(
+            validate_checkpoint(&unknown, &substeps),
+            Err(CheckpointError::UnknownSubstep("d".into()))
+        );
+        let valid = Checkpoint {
+            name: "b".into(),
+            path: dir.join("post_b"),
+        };
+        assert_eq!(validate_checkpoint(&valid, &substeps), Ok(()));
+        let unmarked = [Substep {
+            checkpoint: false,
+            ..substep("b")
+        }];
+        assert_eq!(
+            validate_checkpoint(&valid, &unmarked),
+            Err(CheckpointError::NotCheckpointed("b".into()))
+        );
+
+        let removed = prune_checkpoints(
+            &dir,
+            &PrunePolicy {
+                keep_last: 1,
+                keep_names: vec!["a".into()],
+            },
+        )
+        .unwrap();
+        assert_eq!(removed, vec![dir.join("post_b")]);
+        assert!(dir.join("syn.tcl").exists());
+
+        let removed = prune_checkpoints(&dir, &PrunePolicy::default()).unwrap();
+        assert_eq!(removed, vec![dir.join("post_a")]);
+        assert!(dir.join("post_c").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn latest_checkpoint_stops_at_stale_databases() {
+        let dir = scratch_dir("latest");
+        let substeps = [substep("a"), substep("b"), substep("c")];
+        write_checkpoint(&dir, "c");
+        write_checkpoint(&dir, "a");
+        write_checkpoint(&dir, "b");
+
+        let latest = latest_checkpoint(&dir, &substeps).unwrap();
+        assert_eq!(latest.name, "b");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resumes_after_pruning() {
+        let dir = scratch_dir("resume");
+        let substeps = [substep("a"), substep("b"), substep("c"), substep("d")];
+        for name in ["a", "b", "c"] {
+            write_checkpoint(&dir, name);
+        }
+        prune_checkpoints(&dir, &PrunePolicy::default()).unwrap();
+        assert!(!dir.join("post_a").exists());
+
+        let latest = latest_checkpoint(&dir, &substeps).unwrap();
+        assert_eq!(latest.name, "c");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
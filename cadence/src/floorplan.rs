@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use crate::innovus::{Floorplan, Point};
+
+/// `length` as a decimal number of microns, or `None` if it is not finite or out of range
+fn checked_microns(length: f64) -> Option<Decimal> {
+    Decimal::try_from(length).ok().map(|d| d.round_dp(3))
+}
+
+pub(crate) fn microns(length: f64) -> Decimal {
+    checked_microns(length).expect("floorplan coordinate out of range")
+}
+
+/// Reads the width and height of every macro of a LEF file from its `SIZE` statement
+pub fn lef_macro_sizes(lef: &str) -> HashMap<String, Point> {
+    let mut sizes = HashMap::new();
+    let mut current = None;
+    for line in lef.lines() {
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["MACRO", name, ..] => current = Some(name.to_string()),
+            ["SIZE", width, "BY", height, ..] => {
+                let height = height.trim_end_matches(';');
+                if let (Some(name), Ok(width), Ok(height)) =
+                    (&current, width.parse(), height.parse())
+                {
+                    sizes.insert(name.clone(), (width, height));
+                }
+            }
+            ["END", name] if current.as_deref() == Some(name) => current = None,
+            _ => {}
+        }
+    }
+    sizes
+}
+
+/// Orientation of a placed instance, as given to `place_inst`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Orientation {
+    R0,
+    R90,
+    R180,
+    R270,
+    Mx,
+    My,
+    Mx90,
+    My90,
+}
+
+impl Orientation {
+    pub(crate) fn parse(orientation: &str) -> Option<Self> {
+        Some(match orientation.to_ascii_lowercase().as_str() {
+            "r0" => Orientation::R0,
+            "r90" => Orientation::R90,
+            "r180" => Orientation::R180,
+            "r270" => Orientation::R270,
+            "mx" => Orientation::Mx,
+            "my" => Orientation::My,
+            "mx90" => Orientation::Mx90,
+            "my90" => Orientation::My90,
+            _ => return None,
+        })
+    }
+
+    fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Orientation::R90 | Orientation::R270 | Orientation::Mx90 | Orientation::My90
+        )
+    }
+
+    /// Outline of a `width` by `height` cell once placed in this orientation
+    pub(crate) fn placed_size(self, width: Decimal, height: Decimal) -> Point {
+        if self.swaps_axes() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Maps a point relative to the lower left corner of the placed outline back into the
+    /// coordinates of a `width` by `height` cell
+    pub(crate) fn to_cell(self, (x, y): Point, width: Decimal, height: Decimal) -> Point {
+        match self {
+            Orientation::R0 => (x, y),
+            Orientation::R90 => (y, height - x),
+            Orientation::R180 => (width - x, height - y),
+            Orientation::R270 => (width - y, x),
+            Orientation::Mx => (x, height - y),
+            Orientation::My => (width - x, y),
+            Orientation::Mx90 => (y, x),
+            Orientation::My90 => (width - y, height - x),
+        }
+    }
+}
+
+/// Technology rules a [`Floorplan`] is checked against
+#[derive(Debug, Clone)]
+pub struct FloorplanRules {
+    /// Width of the placement site; macros are placed a whole number of sites from the core edge
+    pub site_width: Decimal,
+    /// Height of the placement rows; macros are placed a whole number of rows from the core edge
+    pub row_height: Decimal,
+    pub manufacturing_grid: Decimal,
+    /// Layers that halos, stackups and obstructions may name
+    pub layers: Vec<String>,
+    /// Width and height of the macro masters, from their LEF with [`lef_macro_sizes`] or
+    /// their die; macros of other masters are reported
+    pub macro_sizes: HashMap<String, Point>,
+}
+
+/// Lower left and upper right corners of a rectangle
+type Rect = (Point, Point);
+
+fn contains(outer: &Rect, inner: &Rect) -> bool {
+    outer.0.0 <= inner.0.0
+        && outer.0.1 <= inner.0.1
+        && inner.1.0 <= outer.1.0
+        && inner.1.1 <= outer.1.1
+}
+
+fn overlaps(a: &Rect, b: &Rect) -> bool {
+    a.0.0 < b.1.0 && b.0.0 < a.1.0 && a.0.1 < b.1.1 && b.0.1 < a.1.1
+}
+
+fn grow(rect: &Rect, by: Decimal) -> Rect {
+    (
+        (rect.0.0 - by, rect.0.1 - by),
+        (rect.1.0 + by, rect.1.1 + by),
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FloorplanProblem {
+    /// The core margins leave no room for the core inside the die
+    EmptyCore,
+    /// A coordinate or length of the die, a macro or an obstruction is not a finite number
+    InvalidNumber {
+        owner: String,
+    },
+    /// No size is known for the master of a macro, so its overlaps and halo are not checked
+    UnknownMacroSize {
+        instance: String,
+        master: String,
+    },
+    UnknownOrientation {
+        instance: String,
+        orientation: String,
+    },
+    /// A macro is not entirely inside the core
+    MacroOutsideCore {
+        instance: String,
+    },
+    /// A macro overlaps another one or lies within its place halo
+    MacrosOverlap {
+        first: String,
+        second: String,
+    },
+    OffManufacturingGrid {
+        instance: String,
+        location: Point,
+    },
+    /// A macro is not a whole number of sites and rows from the lower left corner of the core
+    OffPlacementGrid {
+        instance: String,
+        location: Point,
+    },
+    /// An obstruction is empty or not entirely inside the die
+    ObstructionOutsideDie {
+        name: String,
+    },
+    UnknownLayer {
+        owner: String,
+        layer: String,
+    },
+}
+
+impl fmt::Display for FloorplanProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FloorplanProblem::EmptyCore => write!(f, "core margins leave no core area"),
+            FloorplanProblem::InvalidNumber { owner } => {
+                write!(f, "{owner}: coordinate or size is not a finite number")
+            }
+            FloorplanProblem::UnknownMacroSize { instance, master } => {
+                write!(f, "{instance}: unknown size of macro `{master}`")
+            }
+            FloorplanProblem::UnknownOrientation {
+                instance,
+                orientation,
+            } => write!(f, "{instance}: unknown orientation `{orientation}`"),
+            FloorplanProblem::MacroOutsideCore { instance } => {
+                write!(f, "{instance}: macro extends outside the core")
+            }
+            FloorplanProblem::MacrosOverlap { first, second } => {
+                write!(f, "{first} and {second} overlap, including place halos")
+            }
+            FloorplanProblem::OffManufacturingGrid { instance, location } => write!(
+                f,
+                "{instance}: location ({}, {}) is off the manufacturing grid",
+                location.0, location.1
+            ),
+            FloorplanProblem::OffPlacementGrid { instance, location } => write!(
+                f,
+                "{instance}: location ({}, {}) is off the site and row grid",
+                location.0, location.1
+            ),
+            FloorplanProblem::ObstructionOutsideDie { name } => {
+                write!(f, "{name}: obstruction is empty or extends outside the die")
+            }
+            FloorplanProblem::UnknownLayer { owner, layer } => {
+                write!(f, "{owner}: unknown layer `{layer}`")
+            }
+        }
+    }
+}
+
+/// Every problem found in a [`Floorplan`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FloorplanErrors(pub Vec<FloorplanProblem>);
+
+impl fmt::Display for FloorplanErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.0 {
+            writeln!(f, "{problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FloorplanErrors {}
+
+/// Checks the geometry of `floorplan` before it is handed to Innovus: macros inside the core,
+/// on the grids and clear of each other's halos, obstructions inside the die and only known
+/// layers. All problems are reported, not just the first.
+pub fn validate_floorplan(
+    floorplan: &Floorplan,
+    rules: &FloorplanRules,
+) -> Result<(), FloorplanErrors> {
+    let mut problems = Vec::new();
+    // Converts the lengths of `owner`, reporting it if any of them is not a finite number
+    let lengths = |owner: &str, values: &[f64], problems: &mut Vec<FloorplanProblem>| {
+        let lengths: Option<Vec<Decimal>> = values.iter().map(|&v| checked_microns(v)).collect();
+        if lengths.is_none() {
+            problems.push(FloorplanProblem::InvalidNumber {
+                owner: owner.into(),
+            });
+        }
+        lengths
+    };
+    let top = &floorplan.top;
+    let Some(&[width, height, left, bottom, right, margin_top]) = lengths(
+        "die",
+        &[
+            top.width, top.height, top.left, top.bottom, top.right, top.top,
+        ],
+        &mut problems,
+    )
+    .as_deref() else {
+        return Err(FloorplanErrors(problems));
+    };
+    let die: Rect = ((Decimal::ZERO, Decimal::ZERO), (width, height));
+    let core: Rect = ((left, bottom), (width - right, height - margin_top));
+    if core.0.0 >= core.1.0 || core.0.1 >= core.1.1 {
+        problems.push(FloorplanProblem::EmptyCore);
+    }
+    let check_layer = |owner: &str, layer: &String, problems: &mut Vec<FloorplanProblem>| {
+        if !rules.layers.contains(layer) {
+            problems.push(FloorplanProblem::UnknownLayer {
+                owner: owner.into(),
+                layer: layer.clone(),
+            });
+        }
+    };
+
+    // (instance, outline, outline grown by the place halo) of the macros of known size
+    let mut outlines: Vec<(&str, Rect, Rect)> = Vec::new();
+    for m in &floorplan.hard_macros {
+        for layer in m.stackup.iter().chain([&m.top_layer]) {
+            check_layer(&m.name, layer, &mut problems);
+        }
+        let Some(&[x, y, place_halo]) =
+            lengths(&m.name, &[m.x, m.y, m.place_halo_size], &mut problems).as_deref()
+        else {
+            continue;
+        };
+        let origin = (x, y);
+        if !(origin.0 % rules.manufacturing_grid).is_zero()
+            || !(origin.1 % rules.manufacturing_grid).is_zero()
+        {
+            problems.push(FloorplanProblem::OffManufacturingGrid {
+                instance: m.name.clone(),
+                location: origin,
+            });
+        } else if !((origin.0 - core.0.0) % rules.site_width).is_zero()
+            || !((origin.1 - core.0.1) % rules.row_height).is_zero()
+        {
+            problems.push(FloorplanProblem::OffPlacementGrid {
+                instance: m.name.clone(),
+                location: origin,
+            });
+        }
+        let Some(orientation) = Orientation::parse(&m.orientation) else {
+            problems.push(FloorplanProblem::UnknownOrientation {
+                instance: m.name.clone(),
+                orientation: m.orientation.clone(),
+            });
+            continue;
+        };
+        let Some(&(w, h)) = rules.macro_sizes.get(&m.master) else {
+            if !contains(&core, &(origin, origin)) {
+                problems.push(FloorplanProblem::MacroOutsideCore {
+                    instance: m.name.clone(),
+                });
+            }
+            problems.push(FloorplanProblem::UnknownMacroSize {
+                instance: m.name.clone(),
+                master: m.master.clone(),
+            });
+            continue;
+        };
+        let size = orientation.placed_size(w, h);
+        let outline = (origin, (origin.0 + size.0, origin.1 + size.1));
+        if !contains(&core, &outline) {
+            problems.push(FloorplanProblem::MacroOutsideCore {
+                instance: m.name.clone(),
+            });
+        }
+        outlines.push((&m.name, outline, grow(&outline, place_halo)));
+    }
+    for (i, (first, outline, halo)) in outlines.iter().enumerate() {
+        for (second, other, other_halo) in &outlines[i + 1..] {
+            if overlaps(halo, other) || overlaps(outline, other_halo) {
+                problems.push(FloorplanProblem::MacrosOverlap {
+                    first: first.to_string(),
+                    second: second.to_string(),
+                });
+            }
+        }
+    }
+
+    for obs in &floorplan.obstructs {
+        for layer in obs.obs_layers.iter().flatten() {
+            check_layer(&obs.name, layer, &mut problems);
+        }
+        let Some(&[x, y, width, height]) = lengths(
+            &obs.name,
+            &[obs.x, obs.y, obs.width, obs.height],
+            &mut problems,
+        )
+        .as_deref() else {
+            continue;
+        };
+        let area = ((x, y), (x + width, y + height));
+        if area.0.0 >= area.1.0 || area.0.1 >= area.1.1 || !contains(&die, &area) {
+            problems.push(FloorplanProblem::ObstructionOutsideDie {
+                name: obs.name.clone(),
+            });
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(FloorplanErrors(problems))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::innovus::{HardMacroConstraint, ObstructionConstraint, TopLevelConstraint};
+    use crate::test_util::macro_at;
+
+    #[test]
+    fn reports_every_problem() {
+        let rules = FloorplanRules {
+            site_width: Decimal::new(46, 2),
+            row_height: Decimal::new(272, 2),
+            manufacturing_grid: Decimal::new(5, 3),
+            layers: ["met1", "met2", "met3"].map(String::from).to_vec(),
+            macro_sizes: HashMap::from([("block".into(), (Decimal::from(20), Decimal::from(10)))]),
+        };
+        let mut floorplan = Floorplan {
+            top: TopLevelConstraint {
+                width: 100.0,
+                height: 100.0,
+                left: 0.0,
+                bottom: 0.0,
+                right: 0.0,
+                top: 0.0,
+            },
+            hard_macros: vec![
+                macro_at("block", "u0", 9.2, 10.88, "r0"),
+                macro_at("block", "u1", 9.2, 24.48, "mx"),
+            ],
+            obstructs: vec![],
+        };
+        assert_eq!(validate_floorplan(&floorplan, &rules), Ok(()));
+
+        floorplan.hard_macros.extend([
+            // rotated, 10 by 20, so it reaches past the top of the core
+            macro_at("block", "u2", 50.14, 81.6, "r90"),
+            // within the halo of u1
+            macro_at("block", "u3", 30.36, 24.48, "r0"),
+            macro_at("block", "u4", 70.001, 10.88, "r0"),
+            macro_at("block", "u5", 70.0, 10.88, "r45"),
+            HardMacroConstraint {
+                master: "sram".into(),
+                ..macro_at("block", "u6", 70.38, 40.8, "r0")
+            },
+            macro_at("block", "u7", f64::NAN, 10.88, "r0"),
+        ]);
+        floorplan.hard_macros[0].stackup.push("met9".into());
+        floorplan.obstructs.push(ObstructionConstraint {
+            x: 90.0,
+            y: 90.0,
+            width: 20.0,
+            height: 5.0,
+            obs_layers: Some(vec!["met2".into()]),
+            obs_types: vec!["Place".into()],
+            name: "keepout".into(),
+        });
+        let FloorplanErrors(problems) = validate_floorplan(&floorplan, &rules).unwrap_err();
+        let location = |x, y| (Decimal::new(x, 3), Decimal::new(y, 3));
+        assert_eq!(
+            problems,
+            vec![
+                FloorplanProblem::UnknownLayer {
+                    owner: "u0".into(),
+                    layer: "met9".into()
+                },
+                FloorplanProblem::MacroOutsideCore {
+                    instance: "u2".into()
+                },
+                FloorplanProblem::OffManufacturingGrid {
+                    instance: "u4".into(),
+                    location: location(70001, 10880)
+                },
+                FloorplanProblem::OffPlacementGrid {
+                    instance: "u5".into(),
+                    location: location(70000, 10880)
+                },
+                FloorplanProblem::UnknownOrientation {
+                    instance: "u5".into(),
+                    orientation: "r45".into()
+                },
+                FloorplanProblem::UnknownMacroSize {
+                    instance: "u6".into(),
+                    master: "sram".into()
+                },
+                FloorplanProblem::InvalidNumber { owner: "u7".into() },
+                FloorplanProblem::MacrosOverlap {
+                    first: "u1".into(),
+                    second: "u3".into()
+                },
+                FloorplanProblem::ObstructionOutsideDie {
+                    name: "keepout".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_macro_sizes_from_lef() {
+        let lef = "MACRO sram\n  CLASS BLOCK ;\n  SIZE 120.5 BY 80.24 ;\n  PIN clk\n  END clk\nEND sram\nMACRO empty\nEND empty\n";
+        assert_eq!(
+            lef_macro_sizes(lef),
+            HashMap::from([(
+                "sram".into(),
+                (Decimal::new(1205, 1), Decimal::new(8024, 2))
+            )])
+        );
+    }
+}
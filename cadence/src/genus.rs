@@ -1,27 +1,41 @@
 use regex::Regex;
 use std::fmt::Debug;
-use std::fmt::Write as FmtWrite;
 use std::io::Write;
+use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::sync::OnceLock;
 use std::{fs, io};
 
-use crate::{Checkpoint, MmmcConfig, MmmcCorner, SubmoduleInfo, Substep, mmmc};
+use crate::qor::{Qor, QorError, timing_summary_tcl};
+use crate::tcl::{Command, Script, list, quote};
+use crate::tool::{CadenceToolStep, ToolDescriptor};
+use crate::{MmmcConfig, MmmcCorner, SubmoduleInfo, Substep};
 use fs::File;
-use indoc::formatdoc;
+use indoc::{formatdoc, indoc};
 use rivet::Step;
 use std::sync::Arc;
 
+/// Describes how Genus is scripted and launched
+pub fn genus_tool() -> ToolDescriptor {
+    ToolDescriptor {
+        binary: "genus".into(),
+        script_name: "syn.tcl".into(),
+        log_name: "syn.log".into(),
+        script_flag: "-f".into(),
+        args: vec!["-no_gui".into(), "-batch".into()],
+        preamble: vec!["set_db super_thread_debug_directory super_thread_debug".into()],
+        write_db: "write_db -to_file".into(),
+        read_db: "read_db".into(),
+        exit: "quit".into(),
+    }
+}
+
 /// Defines the Genus synthesis step subflow
 #[derive(Debug, Clone)]
 pub struct GenusStep {
-    pub work_dir: PathBuf,
-    pub module: String,
-    pub substeps: Vec<Substep>,
-    pub pinned: bool,
-    pub start_checkpoint: Option<Checkpoint>,
-    pub endpoint: Option<String>,
-    pub dependencies: Vec<Arc<dyn Step>>,
+    pub step: CadenceToolStep,
+    /// QoR of the final netlist, available once a run writing the design has finished
+    pub qor: OnceLock<Qor>,
 }
 
 impl GenusStep {
@@ -32,162 +46,59 @@ impl GenusStep {
         pinned: bool,
         deps: Vec<Arc<dyn Step>>,
     ) -> Self {
-        let dir = work_dir.into();
-        let modul = module.into();
         GenusStep {
-            work_dir: dir,
-            module: modul,
-            substeps: steps,
-            pinned,
-            start_checkpoint: None,
-            endpoint: None,
-            dependencies: deps,
+            step: CadenceToolStep::new(genus_tool(), work_dir, module, steps, pinned, deps),
+            qor: OnceLock::new(),
         }
     }
 
-    /// Generates the tcl file for synthesis
-    fn make_tcl_file(&self, path: &Path, steps: Vec<Substep>) -> io::Result<()> {
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent).expect("failed to create syn.tcl parent directory");
-        }
-
-        let mut tcl_file =
-            File::create(path.join("syn.tcl")).expect("failed to create syn.tcl file");
-
-        File::create(path.join("rivet_error.log")).expect("failed to create par.tcl file");
-        writeln!(
-            tcl_file,
-            "set_db super_thread_debug_directory super_thread_debug"
-        )?;
-
-        if let Some(checkpoint) = &self.start_checkpoint {
-            writeln!(tcl_file, "read_db {}", checkpoint.path.display()).expect("Failed to write");
-        }
-
-        for step in steps.into_iter() {
-            println!("\n--> Parsing step: {}\n", step.name);
-
-            writeln!(tcl_file, "{}", step.command)?;
-            if step.checkpoint {
-                let checkpoint_file = self.work_dir.join(format!("post_{}", step.name.clone()));
-
-                writeln!(tcl_file, "write_db -to_file {}", checkpoint_file.display())?;
-            }
-        }
-        writeln!(tcl_file, "quit")?;
-
-        println!("\nFinished creating tcl file\n");
-        Ok(())
-    }
-
-    /// Inserts a custom command as a substep in the synthesis flow
-    pub fn add_hook(&mut self, name: &str, tcl: &str, after_substep: &str, checkpointed: bool) {
-        if let Some(index) = self.substeps.iter().position(|s| s.name == after_substep) {
-            self.substeps.insert(
-                index + 1,
-                Substep {
-                    name: name.to_string(),
-                    command: tcl.to_string(),
-                    checkpoint: checkpointed,
-                },
-            );
-        }
-    }
-
-    /// Replaces a specfic substep in the synthesis flow with a new command
-    pub fn replace_hook(
-        &mut self,
-        new_substep_name: &str,
-        tcl: &str,
-        replaced_substep_name: &str,
-        checkpointed: bool,
-    ) {
-        if let Some(index) = self
-            .substeps
-            .iter()
-            .position(|s| s.name == replaced_substep_name)
-        {
-            self.substeps[index] = Substep {
-                name: new_substep_name.to_string(),
-                command: tcl.to_string(),
-                checkpoint: checkpointed,
-            };
-        }
+    pub fn netlist(&self) -> PathBuf {
+        self.work_dir.join(format!("{}.mapped.v", self.module))
     }
 
-    /// Deletes a specfic substep in the synthesis flow
-    pub fn delete_hook(&mut self, deleted_substep_name: &str) {
-        if let Some(index) = self
-            .substeps
-            .iter()
-            .position(|s| s.name == deleted_substep_name)
-        {
-            self.substeps.remove(index);
-        }
+    /// Extracts the QoR from the final reports written by [`syn_write_design`]
+    pub fn read_qor(&self) -> Result<Qor, QorError> {
+        Qor::read(&self.work_dir.join("reports"), "final")
     }
+}
 
-    pub fn netlist(&self) -> PathBuf {
-        self.work_dir.join(format!("{}.mapped.v", self.module))
-    }
+impl Deref for GenusStep {
+    type Target = CadenceToolStep;
 
-    /// Assigns the starting checkpoint of the synthesis flow
-    pub fn add_checkpoint(&mut self, name: &str, checkpoint_path: PathBuf) {
-        self.start_checkpoint = Some(Checkpoint {
-            name: name.to_string(),
-            path: checkpoint_path,
-        });
+    fn deref(&self) -> &CadenceToolStep {
+        &self.step
     }
+}
 
-    pub fn add_endpoint(&mut self, name: &str) {
-        self.endpoint = Some(name.to_string());
+impl DerefMut for GenusStep {
+    fn deref_mut(&mut self) -> &mut CadenceToolStep {
+        &mut self.step
     }
 }
 
 impl Step for GenusStep {
     fn execute(&self) {
-        let mut substeps = self.substeps.clone();
-        if let Some(checkpoint) = &self.start_checkpoint {
-            let slice_index = self
-                .substeps
-                .iter()
-                .position(|s| s.name == checkpoint.name)
-                .expect("Failed to find checkpoint name");
-            substeps = self.substeps[(slice_index + 1)..].to_vec();
-        }
-        if let Some(endpoint_name) = &self.endpoint {
-            let slice_index = substeps
-                .iter()
-                .position(|s| s.name == *endpoint_name)
-                .expect("Failed to find endpoint name");
-            substeps = substeps[..=slice_index].to_vec();
-        }
-
-        self.make_tcl_file(&self.work_dir, substeps)
-            .expect("Failed to create syn.tcl");
-
-        let status = Command::new("genus")
-            .args([
-                "-f",
-                self.work_dir.join("syn.tcl").to_str().unwrap(),
-                "-no_gui",
-                "-batch",
-            ])
-            .current_dir(self.work_dir.clone())
-            .status()
-            .expect("Failed to execute syn.tcl");
-
-        if !status.success() {
-            eprintln!("Failed to execute syn.tcl");
-            panic!("Stopped flow");
+        self.step.execute();
+        if self.substeps.get("write_design").is_some() {
+            // The tool run succeeded; a report that cannot be parsed only leaves the QoR unset
+            match self.read_qor() {
+                Ok(qor) => {
+                    let _ = self.qor.set(qor);
+                }
+                Err(e) => eprintln!(
+                    "Warning: failed to read QoR reports of {}: {e}",
+                    self.module
+                ),
+            }
         }
     }
 
     fn deps(&self) -> Vec<Arc<dyn Step>> {
-        self.dependencies.clone()
+        self.step.deps()
     }
 
     fn pinned(&self) -> bool {
-        self.pinned
+        self.step.pinned()
     }
 }
 
@@ -249,47 +160,32 @@ pub fn syn_read_design_files(files: DesignFiles<'_>) -> Substep {
     let mut sdc_file =
         File::create(work_dir.join("clock_pin_constraints.sdc")).expect("failed to create file");
     writeln!(sdc_file, "{}", sdc_content).expect("Failed to write");
-    let mmmc_tcl = mmmc(mmmc_conf);
+    let mmmc_tcl = mmmc_conf.to_tcl();
     let mmmc_tcl_path = work_dir.to_path_buf().join("mmmc.tcl");
     let _ = fs::write(&mmmc_tcl_path, mmmc_tcl);
 
-    let mut lefs_vec = vec![tlef.display().to_string(), pdk_lef.display().to_string()];
+    let mut lefs: Vec<PathBuf> = vec![tlef.to_path_buf(), pdk_lef.to_path_buf()];
 
     if let Some(submodule_lefs) = &submodules {
-        lefs_vec.extend(
-            submodule_lefs
-                .iter()
-                .map(|p| p.lef.to_string_lossy().to_string()),
-        );
+        lefs.extend(submodule_lefs.iter().map(|p| p.lef.clone()));
     }
 
-    lefs_vec.extend(hard_macros.iter().map(|p| p.display().to_string()));
-
-    let lefs: String = lefs_vec.join(" ");
+    lefs.extend(hard_macros.iter().cloned());
 
-    let mut command = formatdoc!(
-        r#"
-            read_mmmc {}
-            "#,
-        mmmc_tcl_path.display(),
-    );
-
-    let verilog_files: Vec<String> = verilog_paths
-        .iter()
-        .map(|p| p.display().to_string())
-        .collect();
+    let mut command = Script::new();
+    command.command(Command::new("read_mmmc").arg(&mmmc_tcl_path));
 
     if let Some(submodule_vec) = &submodules {
         for submodule in submodule_vec {
-            writeln!(
-                command,
-                "read_ilm -basename {}/mmmc/ilm_data/{}/{}_postRoute -module_name {}",
-                submodule.ilm.display(),
-                submodule.name,
-                submodule.name,
-                submodule.name,
-            )
-            .unwrap();
+            let basename = submodule.ilm.join(format!(
+                "mmmc/ilm_data/{}/{}_postRoute",
+                submodule.name, submodule.name
+            ));
+            command.command(
+                Command::new("read_ilm")
+                    .opt("basename", basename)
+                    .opt("module_name", &submodule.name),
+            );
         }
     }
     let submodule_names: Vec<String> = submodules
@@ -300,31 +196,21 @@ pub fn syn_read_design_files(files: DesignFiles<'_>) -> Substep {
     let mut final_verilog_files = Vec::new();
 
     if is_hierarchical {
-        for verilog in &verilog_files {
-            let new_path =
-                remove_hierarchical_submodules(Path::new(verilog), work_dir, &submodule_names)
-                    .expect("Failed to remove hierarchical submodules");
-            final_verilog_files.push(new_path.to_string_lossy().to_string());
+        for verilog in verilog_paths {
+            let new_path = remove_hierarchical_submodules(verilog, work_dir, &submodule_names)
+                .expect("Failed to remove hierarchical submodules");
+            final_verilog_files.push(new_path);
         }
     } else {
-        final_verilog_files = verilog_files;
+        final_verilog_files = verilog_paths.to_vec();
     }
 
-    let all_verilog = final_verilog_files.join(" ");
-
-    writeln!(
-        command,
-        r#"
-        read_physical -lef {{ {} }}
-        read_hdl -sv {{ {} }}
-        "#,
-        lefs, all_verilog,
-    )
-    .unwrap();
+    command.command(Command::new("read_physical").opt("lef", list(&lefs)));
+    command.command(Command::new("read_hdl").opt("sv", list(&final_verilog_files)));
 
     Substep {
         checkpoint: false,
-        command,
+        command: command.into(),
         name: "read_design_files".into(),
     }
 }
@@ -332,27 +218,27 @@ pub fn syn_read_design_files(files: DesignFiles<'_>) -> Substep {
 pub fn elaborate(module: &String) -> Substep {
     Substep {
         checkpoint: false,
-        command: format!("elaborate {}", module),
+        command: Command::new("elaborate").arg(module).to_string(),
         name: "elaborate".to_string(),
     }
 }
 
 pub fn syn_init_design(module: &String, submodules: Option<Vec<SubmoduleInfo>>) -> Substep {
-    let mut command = String::new();
+    let mut command = Script::new();
     if let Some(submodule_ilms) = &submodules {
         for ilm in submodule_ilms {
-            writeln!(
-                command,
-                "set_db module:{}/{} .preserve true",
-                module, ilm.name
-            )
-            .unwrap();
+            command.command(
+                Command::new("set_db")
+                    .arg(format!("module:{}/{}", module, ilm.name))
+                    .raw(".preserve")
+                    .arg(true),
+            );
         }
     }
-    writeln!(command, "init_design -top {}", module).unwrap();
+    command.command(Command::new("init_design").opt("top", module));
     Substep {
         checkpoint: false,
-        command,
+        command: command.into(),
         name: "init_design".to_string(),
     }
 }
@@ -363,7 +249,7 @@ pub fn power_intent(work_dir: &Path, power_spec: &String) -> Substep {
 
     let mut power_spec_file = File::create(&power_spec_file_path).expect("failed to create file");
     writeln!(power_spec_file, "{}", power_spec).expect("Failed to write");
-    let power_spec_file_string = power_spec_file_path.display();
+    let power_spec_file_string = quote(&power_spec_file_path.display().to_string());
     Substep {
         checkpoint: true,
         command: formatdoc!(
@@ -407,19 +293,9 @@ pub fn add_tieoffs(hi_cell: &str, lo_cell: &str) -> Substep {
 }
 
 pub fn syn_write_design(module: &str, sdc_corner: MmmcCorner, is_hierarchical: bool) -> Substep {
-    let module = module.to_owned();
-    let corner = sdc_corner.name.clone();
-    let corner_type = sdc_corner.corner_type.clone();
-
-    let write_hdl = if is_hierarchical {
-        format!("write_hdl -exclude_ilm > {module}_noilm.mapped.v")
-    } else {
-        format!("write_hdl > {module}.mapped.v")
-    };
-
-    Substep {
-        checkpoint: true,
-        command: formatdoc!(
+    let mut script = Script::new();
+    script
+        .raw(indoc!(
             r#"
             set write_cells_ir "./find_regs_cells.json"
             set write_cells_ir [open $write_cells_ir "w"]
@@ -429,13 +305,13 @@ pub fn syn_write_design(module: &str, sdc_corner: MmmcCorner, is_hierarchical: b
 
             set len [llength $refs]
 
-            for {{set i 0}} {{$i < [llength $refs]}} {{incr i}} {{
-                if {{$i == $len - 1}} {{
+            for {set i 0} {$i < [llength $refs]} {incr i} {
+                if {$i == $len - 1} {
                     puts $write_cells_ir "    \"[lindex $refs $i]\""
-                }} else {{
+                } else {
                     puts $write_cells_ir "    \"[lindex $refs $i]\","
-                }}
-            }}
+                }
+            }
 
             puts $write_cells_ir "\]"
             close $write_cells_ir
@@ -447,29 +323,68 @@ pub fn syn_write_design(module: &str, sdc_corner: MmmcCorner, is_hierarchical: b
 
             set len [llength $regs]
 
-            for {{set i 0}} {{$i < [llength $regs]}} {{incr i}} {{
-                #regsub -all {{/}} [lindex $regs $i] . myreg
+            for {set i 0} {$i < [llength $regs]} {incr i} {
+                #regsub -all {/} [lindex $regs $i] . myreg
                 set myreg [lindex $regs $i]
-                if {{$i == $len - 1}} {{
+                if {$i == $len - 1} {
                     puts $write_regs_ir "    \"$myreg\""
-                }} else {{
+                } else {
                     puts $write_regs_ir "    \"$myreg\","
-                }}
-            }}
+                }
+            }
 
             puts $write_regs_ir "\]"
 
             close $write_regs_ir
-            write_reports -directory reports -tag final
-            report_timing -unconstrained -max_paths 50 > reports/final_unconstrained.rpt
-
-            {write_hdl}
-            write_template -full -outfile {module}.mapped.scr
-            write_sdc -view {corner}.{corner_type}_view > {module}.mapped.sdc
-            write_sdf > {module}.mapped.sdf
-            write_design -gzip_files {module}
-        "#
-        ),
+            "#
+        ))
+        .command(
+            Command::new("write_reports")
+                .opt("directory", "reports")
+                .opt("tag", "final"),
+        )
+        .command(
+            Command::new("report_timing")
+                .flag("unconstrained")
+                .opt("max_paths", 50i64)
+                .raw(">")
+                .arg("reports/final_unconstrained.rpt"),
+        )
+        .raw(&timing_summary_tcl("reports", "final"));
+
+    let write_hdl = if is_hierarchical {
+        Command::new("write_hdl")
+            .flag("exclude_ilm")
+            .raw(">")
+            .arg(format!("{module}_noilm.mapped.v"))
+    } else {
+        Command::new("write_hdl")
+            .raw(">")
+            .arg(format!("{module}.mapped.v"))
+    };
+    script
+        .command(write_hdl)
+        .command(
+            Command::new("write_template")
+                .flag("full")
+                .opt("outfile", format!("{module}.mapped.scr")),
+        )
+        .command(
+            Command::new("write_sdc")
+                .opt("view", sdc_corner.view_name())
+                .raw(">")
+                .arg(format!("{module}.mapped.sdc")),
+        )
+        .command(
+            Command::new("write_sdf")
+                .raw(">")
+                .arg(format!("{module}.mapped.sdf")),
+        )
+        .command(Command::new("write_design").flag("gzip_files").arg(module));
+
+    Substep {
+        checkpoint: true,
+        command: script.to_string(),
         name: "write_design".into(),
     }
 }
@@ -1,29 +1,51 @@
+use std::fmt;
 use std::fmt::Debug;
-use std::fmt::Write as FmtWrite;
 use std::io::Write;
+use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::sync::OnceLock;
 use std::{fs, io};
 
-use crate::MmmcCorner;
-use crate::{Checkpoint, MmmcConfig, SubmoduleInfo, Substep, mmmc};
+use crate::floorplan::microns;
+use crate::power_grid::PowerGrid;
+use crate::qor::{Qor, QorError, timing_summary_tcl};
+use crate::sdc::SdcError;
+use crate::sdc_reader::{rewrite_sdc, scope_pins_hierarchically};
+use crate::tcl::{Command, Script, Word, list};
+use crate::tool::{CadenceToolStep, ToolDescriptor};
+use crate::{CornerRole, MmmcConfig, SubmoduleInfo, Substep};
 use fs::File;
-use indoc::formatdoc;
+use indoc::{formatdoc, indoc};
 use rivet::Step;
 use rust_decimal::Decimal;
 use std::sync::Arc;
 
+/// Describes how Innovus is scripted and launched; `synthesis` adds the `-synthesis` flag
+pub fn innovus_tool(synthesis: bool) -> ToolDescriptor {
+    let mut args = vec!["-stylus".into(), "-no_gui".into(), "-batch".into()];
+    if synthesis {
+        args.push("-synthesis".into());
+    }
+    ToolDescriptor {
+        binary: "innovus".into(),
+        script_name: "par.tcl".into(),
+        log_name: "par.log".into(),
+        script_flag: "-file".into(),
+        args,
+        preamble: vec![],
+        write_db: "write_db".into(),
+        read_db: "read_db".into(),
+        exit: "exit".into(),
+    }
+}
+
 /// Defines the Innovus place and route step subflow
 #[derive(Debug, Clone)]
 pub struct InnovusStep {
-    pub work_dir: PathBuf,
-    pub module: String,
-    pub substeps: Vec<Substep>,
-    pub pinned: bool,
-    pub start_checkpoint: Option<Checkpoint>,
-    pub endpoint: Option<String>,
-    pub dependencies: Vec<Arc<dyn Step>>,
+    pub step: CadenceToolStep,
     pub synthesis: bool,
+    /// QoR of the routed design, available once a run with [`par_write_reports`] has finished
+    pub qor: OnceLock<Qor>,
 }
 
 impl InnovusStep {
@@ -35,88 +57,17 @@ impl InnovusStep {
         deps: Vec<Arc<dyn Step>>,
         synthesis: bool,
     ) -> Self {
-        let dir = work_dir.into();
-        let modul = module.into();
         InnovusStep {
-            work_dir: dir,
-            module: modul,
-            substeps,
-            pinned,
-            start_checkpoint: None,
-            endpoint: None,
-            dependencies: deps,
+            step: CadenceToolStep::new(
+                innovus_tool(synthesis),
+                work_dir,
+                module,
+                substeps,
+                pinned,
+                deps,
+            ),
             synthesis,
-        }
-    }
-
-    /// Generates the tcl file for place and route
-    fn make_tcl_file(&self, path: &Path, substeps: Vec<Substep>) -> io::Result<()> {
-        let mut tcl_file =
-            File::create(path.join("par.tcl")).expect("failed to create par.tcl file");
-
-        File::create(path.join("rivet_error.log")).expect("failed to create par.tcl file");
-        if let Some(checkpoint) = &self.start_checkpoint {
-            writeln!(tcl_file, "read_db {}", checkpoint.path.display()).expect("Failed to write");
-        }
-
-        for step in substeps.into_iter() {
-            println!("\n--> Parsing step: {}\n", step.name);
-            writeln!(tcl_file, "{}", step.command)?;
-            if step.checkpoint {
-                let checkpoint_file = self.work_dir.join(format!("post_{}", step.name.clone()));
-
-                writeln!(tcl_file, "write_db {}", checkpoint_file.display())?;
-            }
-        }
-        writeln!(tcl_file, "exit")?;
-
-        println!("\nFinished creating tcl file\n");
-        Ok(())
-    }
-
-    /// Inserts a custom command as a substep in the par flow
-    pub fn add_hook(&mut self, name: &str, tcl: &str, after_substep: &str, checkpointed: bool) {
-        if let Some(index) = self.substeps.iter().position(|s| s.name == after_substep) {
-            self.substeps.insert(
-                index + 1,
-                Substep {
-                    name: name.to_string(),
-                    command: tcl.to_string(),
-                    checkpoint: checkpointed,
-                },
-            );
-        }
-    }
-
-    /// Replaces a specfic substep in the par flow with a new command
-    pub fn replace_hook(
-        &mut self,
-        new_substep_name: &str,
-        tcl: &str,
-        replaced_substep_name: &str,
-        checkpointed: bool,
-    ) {
-        if let Some(index) = self
-            .substeps
-            .iter()
-            .position(|s| s.name == replaced_substep_name)
-        {
-            self.substeps[index] = Substep {
-                name: new_substep_name.to_string(),
-                command: tcl.to_string(),
-                checkpoint: checkpointed,
-            };
-        }
-    }
-
-    /// Deletes a specfic substep in the par flow
-    pub fn delete_hook(&mut self, deleted_substep_name: &str) {
-        if let Some(index) = self
-            .substeps
-            .iter()
-            .position(|s| s.name == deleted_substep_name)
-        {
-            self.substeps.remove(index);
+            qor: OnceLock::new(),
         }
     }
 
@@ -136,111 +87,343 @@ impl InnovusStep {
         self.work_dir.join(format!("{}.gds", self.module))
     }
 
-    /// Assigns the starting checkpoint of the par flow
-    pub fn add_checkpoint(&mut self, name: &str, checkpoint_path: PathBuf) {
-        self.start_checkpoint = Some(Checkpoint {
-            name: name.to_string(),
-            path: checkpoint_path,
-        });
+    /// Extracts the QoR from the reports written by [`par_write_reports`]
+    pub fn read_qor(&self) -> Result<Qor, QorError> {
+        Qor::read(&self.work_dir.join("reports"), "final")
     }
 
-    pub fn add_endpoint(&mut self, name: &str) {
-        self.endpoint = Some(name.to_string());
+    /// Scopes the `get_pins` queries of the ILM constraints written by [`write_ilm`] to the
+    /// whole hierarchy, so that they still apply once the block is read into its parent
+    pub fn scope_ilm_constraints(&self) -> Result<Vec<PathBuf>, IlmError> {
+        let ilm_data = self
+            .ilm_path()
+            .join(format!("mmmc/ilm_data/{}", self.module));
+        let io = |path: &Path| {
+            let path = path.to_owned();
+            move |e: io::Error| IlmError::Io {
+                path,
+                message: e.to_string(),
+            }
+        };
+        let mut rewritten = Vec::new();
+        for entry in fs::read_dir(&ilm_data).map_err(io(&ilm_data))? {
+            let path = entry.map_err(io(&ilm_data))?.path();
+            if !path.to_string_lossy().ends_with(".core.sdc") {
+                continue;
+            }
+            let sdc = fs::read_to_string(&path).map_err(io(&path))?;
+            let scoped =
+                rewrite_sdc(&sdc, scope_pins_hierarchically).map_err(|error| IlmError::Sdc {
+                    path: path.clone(),
+                    error,
+                })?;
+            fs::write(&path, scoped).map_err(io(&path))?;
+            rewritten.push(path);
+        }
+        Ok(rewritten)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IlmError {
+    /// The ILM data or one of its constraint files could not be read or written
+    Io { path: PathBuf, message: String },
+    /// A constraint file of the ILM could not be rewritten
+    Sdc { path: PathBuf, error: SdcError },
+}
+
+impl fmt::Display for IlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IlmError::Io { path, message } => write!(f, "{}: {message}", path.display()),
+            IlmError::Sdc { path, error } => write!(f, "{}: {error}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for IlmError {}
+
+impl Deref for InnovusStep {
+    type Target = CadenceToolStep;
+
+    fn deref(&self) -> &CadenceToolStep {
+        &self.step
+    }
+}
+
+impl DerefMut for InnovusStep {
+    fn deref_mut(&mut self) -> &mut CadenceToolStep {
+        &mut self.step
     }
 }
 
 impl Step for InnovusStep {
     fn execute(&self) {
-        let mut substeps = self.substeps.clone();
-        if let Some(checkpoint) = &self.start_checkpoint {
-            let slice_index = self
-                .substeps
-                .iter()
-                .position(|s| s.name == checkpoint.name)
-                .expect("Failed to find checkpoint name");
-            substeps = self.substeps[(slice_index + 1)..].to_vec();
+        self.step.execute();
+        if self.substeps.get("write_ilm").is_some()
+            && let Err(e) = self.scope_ilm_constraints()
+        {
+            panic!("Failed to rewrite ILM constraints: {e}");
         }
-        if let Some(endpoint_name) = &self.endpoint {
-            let slice_index = substeps
-                .iter()
-                .position(|s| s.name == *endpoint_name)
-                .expect("Failed to find endpoint name");
-            substeps = substeps[..=slice_index].to_vec();
+        if self.substeps.get("write_reports").is_some() {
+            // The tool run succeeded; a report that cannot be parsed only leaves the QoR unset
+            match self.read_qor() {
+                Ok(qor) => {
+                    let _ = self.qor.set(qor);
+                }
+                Err(e) => eprintln!(
+                    "Warning: failed to read QoR reports of {}: {e}",
+                    self.module
+                ),
+            }
         }
+    }
+
+    fn deps(&self) -> Vec<Arc<dyn Step>> {
+        self.step.deps()
+    }
 
-        self.make_tcl_file(&self.work_dir, substeps)
-            .expect("Failed to create par.tcl");
+    fn pinned(&self) -> bool {
+        self.step.pinned()
+    }
+}
 
-        let tcl_file = self.work_dir.join("par.tcl");
+/// Which ports a [`PinPlacement`] applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinSelector {
+    All,
+    Names(Vec<String>),
+    /// Ports matching a glob pattern such as `data_in*`
+    Pattern(String),
+}
 
-        let mut args = vec![
-            "-file",
-            tcl_file.to_str().unwrap(),
-            "-stylus",
-            "-no_gui",
-            "-batch",
-        ];
-        if self.synthesis {
-            args.push("-synthesis");
+impl PinSelector {
+    fn to_word(&self) -> Word {
+        match self {
+            PinSelector::All => Word::raw("*"),
+            PinSelector::Names(names) => list(names),
+            PinSelector::Pattern(pattern) => Word::from(pattern),
         }
+    }
+}
 
-        let status = Command::new("innovus")
-            .args(args)
-            .current_dir(self.work_dir.clone())
-            .status()
-            .expect("Failed to execute par.tcl");
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
 
-        if !status.success() {
-            eprintln!("Failed to execute par.tcl");
-            panic!("Stopped flow");
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+            Side::Top => write!(f, "top"),
+            Side::Bottom => write!(f, "bottom"),
         }
     }
+}
 
-    fn deps(&self) -> Vec<Arc<dyn Step>> {
-        self.dependencies.clone()
-    }
+/// A point in microns
+pub type Point = (Decimal, Decimal);
+
+/// How the selected pins are laid out along their side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinSpread {
+    /// Spread evenly between two points
+    Range { start: Point, end: Point },
+    /// From `start`, `pitch` apart
+    Start { start: Point, pitch: Decimal },
+    /// Centered on the side, `pitch` apart
+    Center { pitch: Decimal },
+    /// Spread evenly over the whole side, keeping clear of its ends
+    Side {
+        offset_start: Decimal,
+        offset_end: Decimal,
+    },
+    /// At fixed coordinates, one per pin of a [`PinSelector::Names`] selection
+    Fixed(Vec<Point>),
+}
 
-    fn pinned(&self) -> bool {
-        self.pinned
+/// Placement of a group of block pins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinPlacement {
+    pub pins: PinSelector,
+    pub side: Side,
+    /// Layers the pins may use, alternating between them
+    pub layers: Vec<String>,
+    pub spread: PinSpread,
+    /// Pin width, in microns; the layer minimum by default
+    pub width: Option<Decimal>,
+    /// Pin depth, in microns; the layer minimum by default
+    pub depth: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinPlanError {
+    NoLayers,
+    NoPins,
+    /// A fixed placement does not give exactly one location per named pin
+    FixedLocations {
+        pins: usize,
+        locations: usize,
+    },
+    /// A pitch, width, depth or offset is not positive
+    NotPositive(&'static str),
+}
+
+impl fmt::Display for PinPlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PinPlanError::NoLayers => write!(f, "pin placement has no layers"),
+            PinPlanError::NoPins => write!(f, "pin placement selects no pins"),
+            PinPlanError::FixedLocations { pins, locations } => {
+                write!(f, "{locations} fixed locations given for {pins} named pins")
+            }
+            PinPlanError::NotPositive(what) => write!(f, "pin {what} must be positive"),
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Layer {
-    pub top: String,
-    pub bot: String,
-    pub spacing: Decimal,
-    pub trim_antenna: bool,
-    pub add_stripes_command: String,
+impl std::error::Error for PinPlanError {}
+
+fn point(p: &Point) -> Word {
+    Word::raw(format!("{{{} {}}}", p.0, p.1))
 }
 
-#[derive(Debug, Clone)]
-pub struct PinAssignment {
-    pub pins: String,
-    pub module: String,
-    pub patterns: String,
-    pub layer: String,
-    pub side: String,
-    pub start: String,
-    pub end: String,
-    pub assign: String,
-    pub width: String,
-    pub depth: String,
+impl PinPlacement {
+    /// Places `pins` on `layer`, spread evenly over the whole of `side`
+    pub fn on_side(pins: PinSelector, side: Side, layer: &str) -> Self {
+        PinPlacement {
+            pins,
+            side,
+            layers: vec![layer.into()],
+            spread: PinSpread::Side {
+                offset_start: Decimal::ZERO,
+                offset_end: Decimal::ZERO,
+            },
+            width: None,
+            depth: None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), PinPlanError> {
+        if self.layers.is_empty() {
+            return Err(PinPlanError::NoLayers);
+        }
+        let positive = |value: Option<Decimal>, what| match value {
+            Some(v) if v <= Decimal::ZERO => Err(PinPlanError::NotPositive(what)),
+            _ => Ok(()),
+        };
+        positive(self.width, "width")?;
+        positive(self.depth, "depth")?;
+        match (&self.pins, &self.spread) {
+            (PinSelector::Names(names), _) if names.is_empty() => Err(PinPlanError::NoPins),
+            (PinSelector::Pattern(pattern), _) if pattern.is_empty() => Err(PinPlanError::NoPins),
+            (PinSelector::Names(names), PinSpread::Fixed(locations))
+                if names.len() != locations.len() =>
+            {
+                Err(PinPlanError::FixedLocations {
+                    pins: names.len(),
+                    locations: locations.len(),
+                })
+            }
+            (_, PinSpread::Fixed(locations)) if !matches!(self.pins, PinSelector::Names(_)) => {
+                Err(PinPlanError::FixedLocations {
+                    pins: 0,
+                    locations: locations.len(),
+                })
+            }
+            (_, PinSpread::Start { pitch, .. } | PinSpread::Center { pitch }) => {
+                positive(Some(*pitch), "pitch")
+            }
+            (
+                _,
+                PinSpread::Side {
+                    offset_start,
+                    offset_end,
+                },
+            ) if *offset_start < Decimal::ZERO || *offset_end < Decimal::ZERO => {
+                Err(PinPlanError::NotPositive("offset"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Renders the `edit_pin` commands placing the pins of `hinst`
+    pub fn to_commands(&self, hinst: &str) -> Result<Vec<Command>, PinPlanError> {
+        self.validate()?;
+        let base = |pins: Word| {
+            let mut command = Command::new("edit_pin")
+                .flag("fixed_pin")
+                .opt("pin", pins)
+                .opt("hinst", hinst)
+                .opt("side", self.side.to_string())
+                .opt("layer", list(&self.layers));
+            if let Some(width) = self.width {
+                command = command.opt("pin_width", width);
+            }
+            if let Some(depth) = self.depth {
+                command = command.opt("pin_depth", depth);
+            }
+            command
+        };
+        let command = base(self.pins.to_word());
+        Ok(match &self.spread {
+            PinSpread::Range { start, end } => vec![
+                command
+                    .opt("spread_type", "range")
+                    .opt("start", point(start))
+                    .opt("end", point(end)),
+            ],
+            PinSpread::Start { start, pitch } => vec![
+                command
+                    .opt("spread_type", "start")
+                    .opt("start", point(start))
+                    .opt("spacing", *pitch)
+                    .opt("unit", "micron"),
+            ],
+            PinSpread::Center { pitch } => vec![
+                command
+                    .opt("spread_type", "center")
+                    .opt("spacing", *pitch)
+                    .opt("unit", "micron"),
+            ],
+            PinSpread::Side {
+                offset_start,
+                offset_end,
+            } => vec![
+                command
+                    .opt("spread_type", "side")
+                    .opt("offset_start", *offset_start)
+                    .opt("offset_end", *offset_end),
+            ],
+            PinSpread::Fixed(locations) => {
+                let PinSelector::Names(names) = &self.pins else {
+                    unreachable!("validated above");
+                };
+                names
+                    .iter()
+                    .zip(locations)
+                    .map(|(name, location)| base(Word::from(name)).opt("assign", point(location)))
+                    .collect()
+            }
+        })
+    }
 }
 
 pub fn set_default_process(node_size: i64) -> Substep {
     Substep {
         checkpoint: false,
         name: "set_default_options".into(),
-        command: formatdoc!(
-            r#"
-        set_db design_process_node {} 
-        set_multi_cpu_usage -local_cpu 12
-        set_db timing_analysis_cppr both
-        set_db timing_analysis_type ocv
-        "#,
-            node_size
-        ),
+        command: Script::new()
+            .set_db("design_process_node", node_size)
+            .command(Command::new("set_multi_cpu_usage").opt("local_cpu", 12i64))
+            .set_db("timing_analysis_cppr", "both")
+            .set_db("timing_analysis_type", "ocv")
+            .to_string(),
     }
 }
 
@@ -271,50 +454,40 @@ pub fn par_read_design_files(files: DesignFiles<'_>) -> Substep {
     let mut sdc_file =
         File::create(work_dir.join("clock_pin_constraints.sdc")).expect("failed to create file");
     writeln!(sdc_file, "{}", sdc_content).expect("Failed to write");
-    let mmmc_tcl = mmmc(mmmc_conf);
+    let mmmc_tcl = mmmc_conf.to_tcl();
     let mmmc_tcl_path = work_dir.to_path_buf().join("mmmc.tcl");
     let _ = fs::write(&mmmc_tcl_path, mmmc_tcl);
-    let netlist = netlist_path.to_path_buf();
-    let mut lefs_vec = vec![tlef.display().to_string(), pdk_lef.display().to_string()];
+    let mut lefs: Vec<PathBuf> = vec![tlef.to_path_buf(), pdk_lef.to_path_buf()];
 
     if let Some(submodule_lefs) = &submodules {
-        lefs_vec.extend(
-            submodule_lefs
-                .iter()
-                .map(|p| p.lef.to_string_lossy().to_string()),
-        );
+        lefs.extend(submodule_lefs.iter().map(|p| p.lef.clone()));
     }
 
-    lefs_vec.extend(hard_macros.iter().map(|p| p.display().to_string()));
+    lefs.extend(hard_macros.iter().cloned());
 
-    let lefs: String = lefs_vec.join(" ");
-    let mut command = formatdoc!(
-        r#"
-        read_physical -lef {{ {} }}
-        read_mmmc {}
-        read_netlist {} -top {}
-        "#,
-        lefs,
-        mmmc_tcl_path.display(),
-        netlist.display(),
-        module.to_owned(),
-    );
+    let mut command = Script::new();
+    command
+        .command(Command::new("read_physical").opt("lef", list(&lefs)))
+        .command(Command::new("read_mmmc").arg(&mmmc_tcl_path))
+        .command(
+            Command::new("read_netlist")
+                .arg(netlist_path)
+                .opt("top", module),
+        );
 
     if let Some(submodule_vec) = submodules {
         for submodule in submodule_vec {
-            writeln!(
-                command,
-                "read_ilm -cell {} -directory {}",
-                submodule.name,
-                submodule.ilm.display(),
-            )
-            .unwrap();
+            command.command(
+                Command::new("read_ilm")
+                    .opt("cell", &submodule.name)
+                    .opt("directory", &submodule.ilm),
+            );
         }
     }
 
     Substep {
         checkpoint: false,
-        command,
+        command: command.into(),
         name: "read_design_files".into(),
     }
 }
@@ -330,16 +503,12 @@ pub fn par_init_design() -> Substep {
 pub fn innovus_settings(bottom_routing: i64, top_routing: i64) -> Substep {
     Substep {
         checkpoint: false,
-        command: formatdoc!(
-            r#"
-            set_db design_bottom_routing_layer {}
-            set_db design_top_routing_layer {}
-            set_db design_flow_effort standard
-            set_db design_power_effort low
-            "#,
-            bottom_routing,
-            top_routing
-        ),
+        command: Script::new()
+            .set_db("design_bottom_routing_layer", bottom_routing)
+            .set_db("design_top_routing_layer", top_routing)
+            .set_db("design_flow_effort", "standard")
+            .set_db("design_power_effort", "low")
+            .to_string(),
         name: "innovus_settings".into(),
     }
 }
@@ -354,166 +523,254 @@ pub fn floorplan_design(
     let mut floorplan_tcl_file = File::create(&floorplan_tcl_path).expect("failed to create file");
     let floorplan_tcl = generate_floorplan_tcl(floorplan, site_name);
     writeln!(floorplan_tcl_file, "{floorplan_tcl}").unwrap();
-    let floorplan_path_string = floorplan_tcl_path.display();
 
     let power_spec_file_path = work_dir.join("power_spec.cpf");
     let mut power_spec_file = File::create(&power_spec_file_path).expect("failed to create file");
     writeln!(power_spec_file, "{}", power_spec).expect("Failed to write");
-    let power_spec_file_string = power_spec_file_path.display();
-    let command = formatdoc!(
-        r#"
-        source -echo -verbose {floorplan_path_string} 
-        flatten_ilm 
-        read_power_intent -cpf {power_spec_file_string}
-        commit_power_intent
-        unflatten_ilm
-        "#
-    );
     Substep {
         checkpoint: true,
-        command,
+        command: Script::new()
+            .command(
+                Command::new("source")
+                    .flag("echo")
+                    .flag("verbose")
+                    .arg(&floorplan_tcl_path),
+            )
+            .command(Command::new("flatten_ilm"))
+            .command(Command::new("read_power_intent").opt("cpf", &power_spec_file_path))
+            .command(Command::new("commit_power_intent"))
+            .command(Command::new("unflatten_ilm"))
+            .to_string(),
         name: "floorplan_design".into(),
     }
 }
 
-pub fn place_tap_cells() -> Substep {
+/// Cells capping the left and right end of every placement row
+#[derive(Debug, Clone)]
+pub struct EndcapCells {
+    pub left: String,
+    pub right: String,
+}
+
+/// Well tap and endcap insertion settings for [`place_tap_cells`]
+#[derive(Debug, Clone)]
+pub struct TapCellSpec {
+    pub tap_cell: String,
+    /// Distance between tap cells in a row, in microns
+    pub pitch: Decimal,
+    /// Offsets the taps of every other row by half a pitch
+    pub checkerboard: bool,
+    pub endcaps: Option<EndcapCells>,
+    /// Placement halo around hard macros, in microns, so that rows cut by a macro are capped
+    /// and tapped clear of its boundary
+    pub macro_halo: Option<Decimal>,
+}
+
+/// Inserts endcaps at the ends of each row, then well taps along the rows
+pub fn place_tap_cells(spec: &TapCellSpec) -> Substep {
+    let mut script = Script::new();
+    if let Some(halo) = spec.macro_halo {
+        script.command(
+            Command::new("create_place_halo")
+                .flag("all_blocks")
+                .opt("halo_deltas", list([halo, halo, halo, halo])),
+        );
+    }
+    if let Some(endcaps) = &spec.endcaps {
+        script
+            .set_db("add_endcaps_left_edge", &endcaps.left)
+            .set_db("add_endcaps_right_edge", &endcaps.right)
+            .command(Command::new("add_endcaps").opt("prefix", "ENDCAP"));
+    }
+    let mut taps = Command::new("add_well_taps")
+        .opt("cell", &spec.tap_cell)
+        .opt("cell_interval", spec.pitch)
+        .opt("prefix", "WELLTAP");
+    if spec.checkerboard {
+        taps = taps.flag("checkerboard");
+    }
+    script.command(taps);
     Substep {
         checkpoint: true,
-        command: "".into(),
+        command: script.to_string(),
         name: "place_tap_cells".into(),
     }
 }
 
-pub fn power_straps(straps: Vec<Layer>) -> Substep {
-    let mut definitions = String::new();
-    for strap in straps.into_iter() {
-        writeln!(
-            &mut definitions,
-            "#Power strap definition for layer {}:",
-            strap.top
-        )
-        .expect("Failed to write");
-        writeln!(
-            &mut definitions,
-            "set_db add_stripes_stacked_via_top_layer {}",
-            strap.top
-        )
-        .expect("Failed to write");
-        writeln!(
-            &mut definitions,
-            "set_db add_stripes_stacked_via_bottom_layer {}",
-            strap.bot
-        )
-        .expect("Failed to write");
-
-        if strap.trim_antenna {
-            writeln!(
-                &mut definitions,
-                "set_db add_stripes_trim_antenna_back_to_shape {{stripe}}"
-            )
-            .expect("Failed to write");
-        }
-        writeln!(
-            &mut definitions,
-            "set_db add_stripes_spacing_from_block {}",
-            strap.spacing
-        )
-        .expect("Failed to write");
-        writeln!(&mut definitions, "{}", strap.add_stripes_command).expect("Failed to write");
-    }
-
+/// Builds the power grid of the block
+pub fn power_straps(grid: &PowerGrid) -> Substep {
     Substep {
         checkpoint: true,
-        command: definitions,
+        command: grid.to_tcl(),
         name: "power_straps".into(),
     }
 }
 
-pub fn place_pins(top_layer: &str, bot_layer: &str, assignments: Vec<PinAssignment>) -> Substep {
-    let mut place_pins_commands = String::new();
-    writeln!(place_pins_commands, "set_db assign_pins_edit_in_batch true")
-        .expect("Failed to write");
-    writeln!(
-        place_pins_commands,
-        "set_db assign_pins_promoted_macro_bottom_layer {bot_layer}"
-    )
-    .expect("Failed to write");
-    writeln!(
-        place_pins_commands,
-        "set_db assign_pins_promoted_macro_top_layer {top_layer}"
-    )
-    .expect("Failed to write");
-
-    writeln!(place_pins_commands, "set all_ppins \"\" ").expect("Failed to write");
-
-    for assignment in assignments.into_iter() {
-        writeln!(
-            place_pins_commands,
-            "edit_pin -fixed_pin -pin {} -hinst {} {} {} {} {} {} {} {} {}",
-            assignment.pins,
-            assignment.module,
-            assignment.patterns,
-            assignment.layer,
-            assignment.side,
-            assignment.start,
-            assignment.end,
-            assignment.assign,
-            assignment.width,
-            assignment.depth
-        )
-        .expect("Failed to write");
+/// Places the pins of `module` according to `placements`, with pins promoted from macros
+/// between `bot_layer` and `top_layer`
+pub fn place_pins(
+    top_layer: &str,
+    bot_layer: &str,
+    module: &str,
+    placements: &[PinPlacement],
+) -> Result<Substep, PinPlanError> {
+    let mut script = Script::new();
+    script
+        .set_db("assign_pins_edit_in_batch", true)
+        .set_db("assign_pins_promoted_macro_bottom_layer", bot_layer)
+        .set_db("assign_pins_promoted_macro_top_layer", top_layer)
+        .raw("set all_ppins \"\"");
+    for placement in placements {
+        for command in placement.to_commands(module)? {
+            script.command(command);
+        }
     }
+    script
+        .raw("if {[llength $all_ppins] ne 0} {assign_io_pins -move_fixed_pin -pins [get_db $all_ppins .net.name]}")
+        .set_db("assign_pins_edit_in_batch", false);
 
-    writeln!(place_pins_commands, "if {{[llength $all_ppins] ne 0}} {{assign_io_pins -move_fixed_pin -pins [get_db $all_ppins .net.name]}}").expect("Failed to write");
-    writeln!(
-        place_pins_commands,
-        "set_db assign_pins_edit_in_batch false"
-    )
-    .expect("Failed to write");
-
-    Substep {
+    Ok(Substep {
         checkpoint: true,
-        command: place_pins_commands,
+        command: script.to_string(),
         name: "place_pins".into(),
-    }
+    })
 }
 
 pub fn place_opt_design(sdc_files: Option<PathBuf>) -> Substep {
-    let sdc_command = if let Some(sdc_files) = sdc_files {
-        sdc_files.display().to_string()
-    } else {
-        "".to_string()
-    };
-
-    let command = formatdoc!(
+    let mut script = Script::new();
+    script.raw(indoc!(
         r#"
-        set unplaced_pins [get_db ports -if {{.place_status == unplaced}}]
-        if {{$unplaced_pins ne ""}} {{
+        set unplaced_pins [get_db ports -if {.place_status == unplaced}]
+        if {$unplaced_pins ne ""} {
             print_message -error "Some pins remain unplaced, which will cause invalid placement and routing. These are the unplaced pins: $unplaced_pins"
             exit 2
-        }}
-        {sdc_command}
-        set_db opt_enable_podv2_clock_opt_flow true
-        place_opt_design
+        }
         "#
-    );
+    ));
+    if let Some(sdc_files) = sdc_files {
+        script.command(Command::new("source").arg(sdc_files));
+    }
+    script
+        .set_db("opt_enable_podv2_clock_opt_flow", true)
+        .command(Command::new("place_opt_design"));
     Substep {
         checkpoint: true,
-        command,
+        command: script.to_string(),
         name: "place_opt_design".into(),
     }
 }
 
+/// Non-default routing rule for clock nets, widening and spacing wires on `layers`
+#[derive(Debug, Clone)]
+pub struct NonDefaultRule {
+    pub name: String,
+    pub width_multiplier: i64,
+    pub spacing_multiplier: i64,
+    /// Bottom and top layer the multipliers apply to
+    pub layers: (String, String),
+}
+
+/// Clock tree synthesis settings for [`clock_tree`]
+#[derive(Debug, Clone)]
+pub struct CtsSpec {
+    /// Target skew, in ns
+    pub target_skew: Decimal,
+    pub max_fanout: Option<i64>,
+    /// Target maximum transition on clock nets, in ns
+    pub max_transition: Option<Decimal>,
+    /// Buffers CTS may use; the tool picks from the library when empty
+    pub buffer_cells: Vec<String>,
+    /// Inverters CTS may use; the tool picks from the library when empty
+    pub inverter_cells: Vec<String>,
+    pub ndr: Option<NonDefaultRule>,
+    /// Preferred bottom and top layer for clock routing
+    pub routing_layers: Option<(String, String)>,
+    /// Fixes hold violations right after the clock tree is built
+    pub fix_hold: bool,
+}
+
+impl Default for CtsSpec {
+    fn default() -> Self {
+        CtsSpec {
+            target_skew: Decimal::new(3, 2),
+            max_fanout: Some(10),
+            max_transition: None,
+            buffer_cells: vec![],
+            inverter_cells: vec![],
+            ndr: None,
+            routing_layers: None,
+            fix_hold: false,
+        }
+    }
+}
+
+/// Builds the clock tree according to `spec`, optionally followed by post-CTS hold fixing
+pub fn clock_tree(spec: &CtsSpec) -> Substep {
+    let mut script = Script::new();
+    script.set_db("cts_target_skew", spec.target_skew);
+    if let Some(fanout) = spec.max_fanout {
+        script.set_db("cts_max_fanout", fanout);
+    }
+    if let Some(transition) = spec.max_transition {
+        script.set_db("cts_target_max_transition_time", transition);
+    }
+    if !spec.buffer_cells.is_empty() {
+        script.set_db("cts_buffer_cells", list(&spec.buffer_cells));
+    }
+    if !spec.inverter_cells.is_empty() {
+        script.set_db("cts_inverter_cells", list(&spec.inverter_cells));
+    }
+
+    if let Some(ndr) = &spec.ndr {
+        let (bottom, top) = &ndr.layers;
+        script.command(
+            Command::new("create_route_rule")
+                .opt("name", &ndr.name)
+                .opt(
+                    "width_multiplier",
+                    Word::raw(format!("{{{bottom}:{top} {}}}", ndr.width_multiplier)),
+                )
+                .opt(
+                    "spacing_multiplier",
+                    Word::raw(format!("{{{bottom}:{top} {}}}", ndr.spacing_multiplier)),
+                ),
+        );
+    }
+    if spec.ndr.is_some() || spec.routing_layers.is_some() {
+        let mut route_type = Command::new("create_route_type").opt("name", "clock_route");
+        if let Some(ndr) = &spec.ndr {
+            route_type = route_type.opt("route_rule", &ndr.name);
+        }
+        if let Some((bottom, top)) = &spec.routing_layers {
+            route_type = route_type
+                .opt("bottom_preferred_layer", bottom)
+                .opt("top_preferred_layer", top);
+        }
+        script
+            .command(route_type)
+            .set_db("cts_route_type_trunk", "clock_route")
+            .set_db("cts_route_type_leaf", "clock_route");
+    }
+
+    script.command(Command::new("create_clock_tree_spec"));
+    script.command(Command::new("ccopt_design"));
+    if spec.fix_hold {
+        script.command(Command::new("opt_design").flag("post_cts").flag("hold"));
+    }
+    Substep {
+        checkpoint: true,
+        command: script.to_string(),
+        name: "clock_tree".into(),
+    }
+}
+
 pub fn add_fillers(filler_cells: Vec<String>) -> Substep {
-    let cells = format!("\"{}\"", filler_cells.join(" "));
     Substep {
         checkpoint: true,
-        command: formatdoc!(
-            r#"
-            set_db add_fillers_cells {cells}
-            add_fillers
-            "#
-        ),
+        command: Script::new()
+            .set_db("add_fillers_cells", list(&filler_cells))
+            .command(Command::new("add_fillers"))
+            .to_string(),
         name: "add_fillers".into(),
     }
 }
@@ -550,6 +807,23 @@ pub fn opt_design() -> Substep {
     }
 }
 
+/// Writes the final timing, area and power reports read back into [`InnovusStep::qor`]
+pub fn par_write_reports() -> Substep {
+    let timing_summary = timing_summary_tcl("reports", "final");
+    Substep {
+        checkpoint: false,
+        command: formatdoc!(
+            r#"
+            file mkdir reports
+            {timing_summary}
+            report_area > reports/final_area.rpt
+            report_power -out_file reports/final_power.rpt
+            "#
+        ),
+        name: "write_reports".into(),
+    }
+}
+
 pub fn write_regs() -> Substep {
     // TODO: add childmodule.tcl
     let childmodule_tcl = "";
@@ -609,216 +883,284 @@ pub fn par_write_design(
     pdk_root: &Path,
     work_dir: &Path,
     module: &str,
-    corners: Vec<MmmcCorner>,
+    mmmc: &MmmcConfig,
 ) -> Substep {
-    let root = pdk_root.display();
-    let par_rundir = work_dir.display();
-    let module = module.to_owned();
-    let setup = corners
-        .iter()
-        .find(|p| p.corner_type == "setup")
-        .unwrap()
-        .name
-        .clone();
-    let hold = corners
-        .iter()
-        .find(|p| p.corner_type == "hold")
-        .unwrap()
-        .name
-        .clone();
-    let typical = corners
-        .iter()
-        .find(|p| p.corner_type == "extra")
-        .unwrap()
-        .name
-        .clone();
+    let out = |suffix: &str| work_dir.join(format!("{module}{suffix}"));
+    let (setup, hold, typical) = (mmmc.primary_setup(), mmmc.primary_hold(), mmmc.dynamic());
+    let pdk_gds = pdk_root.join("sky130/sky130_cds/sky130_scl_9T_0.0.5/gds/sky130_scl_9T.gds");
+
+    let mut script = Script::new();
+    script
+        .set_db("timing_enable_simultaneous_setup_hold_mode", true)
+        .command(
+            Command::new("write_db")
+                .arg(format!("{module}_FINAL"))
+                .flag("def")
+                .flag("verilog"),
+        )
+        .set_db("write_stream_virtual_connection", false);
+    for (global, base) in [
+        ("VDD", "VPWR"),
+        ("VDD", "VPB"),
+        ("VDD", "vdd"),
+        ("VSS", "VGND"),
+        ("VSS", "VNB"),
+        ("VSS", "vss"),
+    ] {
+        script.command(
+            Command::new("connect_global_net")
+                .arg(global)
+                .opt("type", "net")
+                .opt("net_base_name", base),
+        );
+    }
+    script
+        .command(
+            Command::new("write_netlist")
+                .arg(out(".lvs.v"))
+                .flag("top_module_first")
+                .opt("top_module", module)
+                .flag("exclude_leaf_cells")
+                .flag("phys")
+                .flag("flat")
+                .opt("exclude_insts_of_cells", ""),
+        )
+        .command(
+            Command::new("write_netlist")
+                .arg(out(".sim.v"))
+                .flag("top_module_first")
+                .opt("top_module", module)
+                .flag("exclude_leaf_cells")
+                .opt("exclude_insts_of_cells", ""),
+        )
+        .command(
+            Command::new("write_stream")
+                .opt("mode", "ALL")
+                .opt("format", "stream")
+                .opt(
+                    "map_file",
+                    "/scratch/cs199-cbc/rivet/pdks/sky130/src/sky130_lefpin.map",
+                )
+                .flag("uniquify_cell_names")
+                .opt("merge", list([pdk_gds]))
+                .arg(out(".gds")),
+        )
+        .command(
+            Command::new("write_sdf")
+                .opt("max_view", setup.name())
+                .opt("min_view", hold.name())
+                .opt("typical_view", typical.name())
+                .arg(out(".par.sdf")),
+        )
+        .set_db("extract_rc_coupled", true)
+        .command(Command::new("extract_rc"));
+    for view in [setup, hold, typical] {
+        script.command(
+            Command::new("write_parasitics")
+                .opt("spef_file", out(&format!(".{}.par.spef", view.corner.name)))
+                .opt("rc_corner", view.corner.rc_corner_name()),
+        );
+    }
+    script
+        .command(Command::new("write_db").arg("post_write_design"))
+        .raw("ln -sfn post_write_design latest");
 
     Substep {
         checkpoint: true,
-        command: formatdoc!(
-            r#"
-            set_db timing_enable_simultaneous_setup_hold_mode true
-            write_db {module}_FINAL -def -verilog
-            set_db write_stream_virtual_connection false
-            connect_global_net VDD -type net -net_base_name VPWR
-            connect_global_net VDD -type net -net_base_name VPB
-            connect_global_net VDD -type net -net_base_name vdd
-            connect_global_net VSS -type net -net_base_name VGND
-            connect_global_net VSS -type net -net_base_name VNB
-            connect_global_net VSS -type net -net_base_name vss
-            write_netlist {par_rundir}/{module}.lvs.v -top_module_first -top_module {module} -exclude_leaf_cells -phys -flat -exclude_insts_of_cells {{}}
-            write_netlist {par_rundir}/{module}.sim.v -top_module_first -top_module {module} -exclude_leaf_cells -exclude_insts_of_cells {{}}
-            write_stream -mode ALL -format stream -map_file /scratch/cs199-cbc/rivet/pdks/sky130/src/sky130_lefpin.map -uniquify_cell_names -merge {{ {root}/sky130/sky130_cds/sky130_scl_9T_0.0.5/gds/sky130_scl_9T.gds }}  {par_rundir}/{module}.gds
-            write_sdf -max_view {setup}.setup_view -min_view {hold}.hold_view -typical_view {typical}.extra_view {par_rundir}/{module}.par.sdf
-            set_db extract_rc_coupled true
-            extract_rc
-            write_parasitics -spef_file {par_rundir}/{module}.{setup}.par.spef -rc_corner {setup}.setup_rc
-            write_parasitics -spef_file {par_rundir}/{module}.{hold}.par.spef -rc_corner {hold}.hold_rc
-            write_parasitics -spef_file {par_rundir}/{module}.{typical}.par.spef -rc_corner {typical}.extra_rc
-            write_db post_write_design
-            ln -sfn post_write_design latest
-            "#
-        ),
+        command: script.to_string(),
         name: "write_design".into(),
     }
 }
 
-pub fn write_ilm(
-    work_dir: &Path,
-    module: &str,
-    layer: &Layer,
-    corners: Vec<MmmcCorner>,
-) -> Substep {
-    let sdc_corners: Vec<MmmcCorner> = corners
-        .into_iter()
-        .filter(|c| c.corner_type == "setup" || c.corner_type == "hold")
-        .collect();
-
-    let ilm_dir = work_dir
-        .join(format!("{}ILMDir", module))
-        .display()
-        .to_string();
-    let top_layer = layer.top.clone();
-
-    let genus_copy = format!("{ilm_dir}/mmmc/ilm_data/{module}/{module}_postRoute.ilm.v.gz");
-    let innovus_copy = format!("{ilm_dir}/mmmc/ilm_data/{module}/{module}_postRoute.v.gz");
+/// Writes the ILM and LEF abstract of the block, with power pins on `top_layer`
+pub fn write_ilm(work_dir: &Path, module: &str, top_layer: &str, mmmc: &MmmcConfig) -> Substep {
+    let mut sdc_views: Vec<String> = Vec::new();
+    for view in mmmc.setup().into_iter().chain(mmmc.hold()) {
+        if matches!(view.corner.role, CornerRole::Setup | CornerRole::Hold)
+            && !sdc_views.contains(&view.name())
+        {
+            sdc_views.push(view.name());
+        }
+    }
 
-    let mut command = formatdoc!(
-        r#"
-            set_db timing_enable_simultaneous_setup_hold_mode false
-            time_design -post_route
-            time_design -post_route -hold
-            check_process_antenna
-            write_lef_abstract -5.8 -top_layer {top_layer} -stripe_pins -pg_pin_layers {{{top_layer}}} {module}ILM.lef
-            flatten_ilm
-            write_ilm -model_type all -to_dir {ilm_dir} -type_flex_ilm ilm
-            cp {innovus_copy} {genus_copy}
-        "#
-    );
+    let ilm_dir = work_dir.join(format!("{module}ILMDir"));
+    let ilm_data = ilm_dir.join(format!("mmmc/ilm_data/{module}"));
+
+    let mut script = Script::new();
+    script
+        .set_db("timing_enable_simultaneous_setup_hold_mode", false)
+        .command(Command::new("time_design").flag("post_route"))
+        .command(Command::new("time_design").flag("post_route").flag("hold"))
+        .command(Command::new("check_process_antenna"))
+        .command(
+            Command::new("write_lef_abstract")
+                .raw("-5.8")
+                .opt("top_layer", top_layer)
+                .flag("stripe_pins")
+                .opt("pg_pin_layers", list([top_layer]))
+                .arg(format!("{module}ILM.lef")),
+        )
+        .command(Command::new("flatten_ilm"))
+        .command(
+            Command::new("write_ilm")
+                .opt("model_type", "all")
+                .opt("to_dir", &ilm_dir)
+                .opt("type_flex_ilm", "ilm"),
+        )
+        .command(
+            Command::new("cp")
+                .arg(ilm_data.join(format!("{module}_postRoute.v.gz")))
+                .arg(ilm_data.join(format!("{module}_postRoute.ilm.v.gz"))),
+        );
 
-    for sdc_corner in sdc_corners {
-        let sdc_in = format!(
-            "{}_postRoute_{}.{}_view.core.sdc",
-            module,
-            sdc_corner.name.clone(),
-            sdc_corner.corner_type.clone()
+    for view in sdc_views {
+        let sdc = format!("{module}_postRoute_{view}.core.sdc");
+        script.command(
+            Command::new("gzip")
+                .flag("d")
+                .flag("c")
+                .arg(ilm_data.join(format!("{sdc}.gz")))
+                .raw(">")
+                .arg(ilm_data.join(sdc)),
         );
-        let sdc_out = format!("{ilm_dir}/mmmc/ilm_data/{module}/{sdc_in}");
-        writeln!(
-            command,
-            "gzip -d -c {ilm_dir}/mmmc/ilm_data/{module}/{sdc_in}.gz | sed \"s/get_pins/get_pins -hierarchical/g\" > {sdc_out}"
-        ).unwrap();
     }
     Substep {
         checkpoint: false,
-        command,
+        command: script.to_string(),
         name: "write_ilm".into(),
     }
 }
 
 pub fn generate_floorplan_tcl(floorplan: Floorplan, site_name: &str) -> String {
-    let mut command = String::new();
+    let mut script = Script::new();
     let toplevel = floorplan.top;
-    let macros = floorplan.hard_macros;
-    let obstructions = floorplan.obstructs;
-
-    let w = toplevel.width.to_string();
-    let h = toplevel.height.to_string();
-    let l = toplevel.left.to_string();
-    let b = toplevel.bottom.to_string();
-    let r = toplevel.right.to_string();
-    let t = toplevel.top.to_string();
-    writeln!(
-        command,
-        "create_floorplan -core_margins_by die -flip f -die_size_by_io_height max -site {site_name} -die_size {{ {w} {h} {l} {b} {r} {t}}}"
-    ).unwrap();
-
-    for constraint in macros.into_iter() {
-        let inst = constraint.name.clone();
-        let x = constraint.x.to_string();
-        let y = constraint.y.to_string();
-        let orientation = constraint.orientation.clone();
-        let fixed = if constraint.create_physical {
-            "-fixed".to_string()
-        } else {
-            "".to_string()
-        };
 
+    script.command(
+        Command::new("create_floorplan")
+            .opt("core_margins_by", "die")
+            .opt("flip", "f")
+            .opt("die_size_by_io_height", "max")
+            .opt("site", site_name)
+            .opt(
+                "die_size",
+                list([
+                    toplevel.width,
+                    toplevel.height,
+                    toplevel.left,
+                    toplevel.bottom,
+                    toplevel.right,
+                    toplevel.top,
+                ]),
+            ),
+    );
+
+    for constraint in floorplan.hard_macros {
+        let inst = &constraint.name;
+        let (x, y) = (constraint.x, constraint.y);
+        let orientation = &constraint.orientation;
+
+        if constraint.create_physical {
+            script.command(
+                Command::new("create_inst")
+                    .opt("cell", &constraint.master)
+                    .opt("inst", inst)
+                    .opt("location", list([x, y]))
+                    .opt("orient", orientation)
+                    .flag("physical")
+                    .opt("status", "fixed"),
+            );
+        }
+
+        let mut place = Command::new("place_inst")
+            .arg(inst)
+            .arg(x)
+            .arg(y)
+            .arg(orientation);
         if constraint.create_physical {
-            let cell = constraint.master.clone().to_string();
-            writeln!(
-                command,
-                "create_inst -cell {cell} -inst {inst} -location {{{x} {y}}} -orient {orientation} -physical -status fixed"
-            ).unwrap();
+            place = place.flag("fixed");
         }
+        script.command(place);
 
-        writeln!(command, "place_inst {inst} {x} {y} {orientation} {fixed}").unwrap();
-        let mut layers = String::new();
-        let layer = constraint.top_layer;
-        let b = constraint.stackup[1].clone();
-        let s = constraint.route_halo_size;
+        let layer = &constraint.top_layer;
+        let layers = match constraint.stackup.iter().position(|x| x == layer) {
+            Some(index) => &constraint.stackup[..index],
+            None => &[],
+        };
         let p = constraint.place_halo_size;
-        let i = constraint.stackup.iter().position(|x| x == &layer);
+        let halo_shapes = Word::raw(format!(
+            "{}{}",
+            Word::from(Command::new("get_db").arg("hinsts").arg(inst)),
+            Word::from(Command::new("get_db").arg("insts").arg(inst)),
+        ));
+        script
+            .command(
+                Command::new("create_route_halo")
+                    .opt("bottom_layer", &constraint.stackup[1])
+                    .opt("space", constraint.route_halo_size)
+                    .opt("top_layer", layer)
+                    .opt("inst", inst),
+            )
+            .command(
+                Command::new("create_place_halo")
+                    .opt("insts", inst)
+                    .opt("halo_deltas", list([p, p, p, p]))
+                    .flag("snap_to_site"),
+            )
+            .command(
+                Command::new("set").arg("pg_blockage_shape").arg(
+                    Command::new("get_db")
+                        .arg(halo_shapes)
+                        .raw(".place_halo_polygon"),
+                ),
+            )
+            .command(
+                Command::new("create_route_blockage")
+                    .flag("pg_nets")
+                    .opt("layers", list(layers))
+                    .opt("polygon", Word::var("pg_blockage_shape")),
+            );
+    }
 
-        if let Some(index) = i {
-            layers = constraint.stackup.clone()[..index].to_vec().join(" ");
-        }
-        writeln!(
-            command,
-            "create_route_halo -bottom_layer {b} -space {s} -top_layer {layer} -inst {inst}"
-        )
-        .unwrap();
-        writeln!(
-            command,
-            "create_place_halo -insts {inst} -halo_deltas {{{p} {p} {p} {p}}} -snap_to_site"
-        )
-        .unwrap();
-        writeln!(
-                command,
-                "set pg_blockage_shape [get_db [get_db hinsts {inst}][get_db insts {inst}] .place_halo_polygon]"
-            ).unwrap();
-        writeln!(
-            command,
-            "create_route_blockage -pg_nets -layers {{{layers}}} -polygon $pg_blockage_shape"
-        )
-        .unwrap();
-    }
-    for constraint in obstructions.into_iter() {
-        let inst = constraint.name.clone();
-        let x1 = constraint.x.to_string();
-        let x2 = (constraint.x + constraint.width).to_string();
-        let y1 = constraint.y.to_string();
-        let y2 = (constraint.y + constraint.height).to_string();
-
-        let layers: String = if let Some(constraints) = &constraint.obs_layers {
-            let specific_layers = constraints.join(" ").to_string();
-            format!("layers {{{}}}", specific_layers)
-        } else {
-            "all {route}".to_string()
+    for constraint in floorplan.obstructs {
+        let inst = &constraint.name;
+        let area = list([
+            constraint.x,
+            constraint.y,
+            constraint.x + constraint.width,
+            constraint.y + constraint.height,
+        ]);
+        let with_layers = |command: Command| match &constraint.obs_layers {
+            Some(layers) => command.opt("layers", list(layers)),
+            None => command.opt("all", "route"),
         };
+        let has_type = |obs_type: &str| constraint.obs_types.iter().any(|t| t == obs_type);
 
-        if constraint.obs_types.contains(&"Place".to_string()) {
-            writeln!(
-                command,
-                "create_place_blockage -name {inst}_place -area {{{x1} {y1} {x2} {y2}}}"
-            )
-            .unwrap();
+        if has_type("Place") {
+            script.command(
+                Command::new("create_place_blockage")
+                    .opt("name", format!("{inst}_place"))
+                    .opt("area", area.clone()),
+            );
         }
-        if constraint.obs_types.contains(&"Route".to_string()) {
-            writeln!(
-                command,
-                "create_route_blockage -name {inst}_route -except_pg_nets -{layers} -spacing 0 -area {{{x1} {y1} {x2} {y2}}}"
-            ).unwrap();
+        if has_type("Route") {
+            let blockage = Command::new("create_route_blockage")
+                .opt("name", format!("{inst}_route"))
+                .flag("except_pg_nets");
+            script.command(
+                with_layers(blockage)
+                    .opt("spacing", 0i64)
+                    .opt("area", area.clone()),
+            );
         }
-        if constraint.obs_types.contains(&"Power".to_string()) {
-            writeln!(
-                command,
-                "create_route_blockage -name {inst}_power -pg_nets -{layers} -area {{{x1} {y1} {x2} {y2}}}"
-            ).unwrap();
+        if has_type("Power") {
+            let blockage = Command::new("create_route_blockage")
+                .opt("name", format!("{inst}_power"))
+                .flag("pg_nets");
+            script.command(with_layers(blockage).opt("area", area.clone()));
         }
     }
 
-    command
+    script.to_string()
 }
 
 /// left: x-coordinate of left edge, bottom: y-coordinate of bottom edge, right: x-coordinate of right edge, top: y-coordinate of top edge
@@ -832,6 +1174,26 @@ pub struct TopLevelConstraint {
     pub top: f64,
 }
 
+impl TopLevelConstraint {
+    /// Width and height of the die
+    pub fn die_size(&self) -> (Decimal, Decimal) {
+        (microns(self.width), microns(self.height))
+    }
+
+    /// Width and height of the core, inside the margins
+    pub fn core_size(&self) -> (Decimal, Decimal) {
+        let size = |length: f64| {
+            Decimal::try_from(length)
+                .expect("core size out of range")
+                .round_dp(3)
+        };
+        (
+            size(self.width - self.left - self.right),
+            size(self.height - self.bottom - self.top),
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct HardMacroConstraint {
     pub x: f64,
@@ -875,3 +1237,160 @@ pub fn generate_open_chip_script(work_dir: &Path, db: &str) -> io::Result<()> {
     fs::set_permissions(&script_path, perms)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tcl::check_syntax;
+
+    #[test]
+    fn clock_tree_emits_ndr_route_type_and_hold_fix() {
+        let spec = CtsSpec {
+            buffer_cells: vec!["sky130_fd_sc_hd__clkbuf_4".into()],
+            ndr: Some(NonDefaultRule {
+                name: "clk_2w2s".into(),
+                width_multiplier: 2,
+                spacing_multiplier: 2,
+                layers: ("met3".into(), "met4".into()),
+            }),
+            routing_layers: Some(("met3".into(), "met4".into())),
+            fix_hold: true,
+            ..Default::default()
+        };
+        let command = clock_tree(&spec).command;
+        check_syntax(&command).unwrap();
+        assert!(command.contains("set_db cts_buffer_cells [list sky130_fd_sc_hd__clkbuf_4]"));
+        assert!(command.contains("-width_multiplier {met3:met4 2}"));
+        assert!(command.contains(
+            "create_route_type -name clock_route -route_rule clk_2w2s -bottom_preferred_layer met3"
+        ));
+        assert!(command.ends_with("opt_design -post_cts -hold\n"));
+    }
+
+    #[test]
+    fn floorplan_tcl_quotes_instance_names() {
+        let floorplan = Floorplan {
+            top: TopLevelConstraint {
+                width: 100.0,
+                height: 80.0,
+                left: 5.0,
+                bottom: 5.0,
+                right: 5.0,
+                top: 5.0,
+            },
+            hard_macros: vec![HardMacroConstraint {
+                x: 10.0,
+                y: 20.0,
+                stackup: vec!["met1".into(), "met2".into(), "met3".into(), "met4".into()],
+                route_halo_size: 2.0,
+                place_halo_size: 1.5,
+                top_layer: "met3".into(),
+                orientation: "r0".into(),
+                create_physical: false,
+                master: "sram".into(),
+                name: "mem[0]".into(),
+            }],
+            obstructs: vec![ObstructionConstraint {
+                x: 0.0,
+                y: 0.0,
+                width: 4.0,
+                height: 6.0,
+                obs_layers: None,
+                obs_types: vec!["Place".into(), "Route".into()],
+                name: "keep out".into(),
+            }],
+        };
+        let tcl = generate_floorplan_tcl(floorplan, "unithd");
+        check_syntax(&tcl).unwrap();
+        assert!(tcl.contains("-die_size [list 100 80 5 5 5 5]"));
+        assert!(tcl.contains("place_inst {mem[0]} 10 20 r0\n"));
+        assert!(tcl.contains(
+            "set pg_blockage_shape [get_db [get_db hinsts {mem[0]}][get_db insts {mem[0]}] .place_halo_polygon]"
+        ));
+        assert!(tcl.contains("-pg_nets -layers [list met1 met2] -polygon $pg_blockage_shape"));
+        assert!(tcl.contains(
+            "create_route_blockage -name {keep out_route} -except_pg_nets -all route -spacing 0 -area [list 0 0 4 6]"
+        ));
+    }
+
+    #[test]
+    fn tap_cells_insert_endcaps_before_taps() {
+        let spec = TapCellSpec {
+            tap_cell: "sky130_fd_sc_hd__tapvpwrvgnd_1".into(),
+            pitch: Decimal::new(13, 0),
+            checkerboard: true,
+            endcaps: Some(EndcapCells {
+                left: "sky130_fd_sc_hd__decap_3".into(),
+                right: "sky130_fd_sc_hd__decap_3".into(),
+            }),
+            macro_halo: Some(Decimal::new(2, 0)),
+        };
+        let command = place_tap_cells(&spec).command;
+        check_syntax(&command).unwrap();
+        let endcaps = command.find("add_endcaps -prefix ENDCAP").unwrap();
+        let taps = command
+            .find("add_well_taps -cell sky130_fd_sc_hd__tapvpwrvgnd_1 -cell_interval 13")
+            .unwrap();
+        assert!(command.starts_with("create_place_halo -all_blocks -halo_deltas [list 2 2 2 2]"));
+        assert!(endcaps < taps);
+        assert!(command.contains("-checkerboard"));
+    }
+
+    #[test]
+    fn pin_placements_validate_and_render_edit_pin() {
+        let range = PinPlacement {
+            pins: PinSelector::All,
+            side: Side::Bottom,
+            layers: vec!["met4".into()],
+            spread: PinSpread::Range {
+                start: (Decimal::new(30, 0), Decimal::ZERO),
+                end: (Decimal::ZERO, Decimal::ZERO),
+            },
+            width: None,
+            depth: None,
+        };
+        assert_eq!(
+            range.to_commands("decoder").unwrap()[0].to_string(),
+            "edit_pin -fixed_pin -pin * -hinst decoder -side bottom -layer [list met4] \
+             -spread_type range -start {30 0} -end {0 0}"
+        );
+
+        let fixed = PinPlacement {
+            pins: PinSelector::Names(vec!["a".into(), "b".into()]),
+            spread: PinSpread::Fixed(vec![(Decimal::ONE, Decimal::ZERO)]),
+            ..range.clone()
+        };
+        assert_eq!(
+            fixed.validate(),
+            Err(PinPlanError::FixedLocations {
+                pins: 2,
+                locations: 1
+            })
+        );
+        let no_layers = PinPlacement {
+            layers: vec![],
+            ..range.clone()
+        };
+        assert!(place_pins("5", "1", "decoder", &[range, no_layers]).is_err());
+    }
+
+    #[test]
+    fn scopes_ilm_constraints_or_reports_missing_ilm() {
+        let work_dir = std::env::temp_dir().join(format!("rivet-ilm-{}", std::process::id()));
+        let step = InnovusStep::new(&work_dir, "block", vec![], false, vec![], false);
+        let ilm_data = step.ilm_path().join("mmmc/ilm_data/block");
+        assert!(matches!(
+            step.scope_ilm_constraints(),
+            Err(IlmError::Io { path, .. }) if path == ilm_data
+        ));
+
+        fs::create_dir_all(&ilm_data).unwrap();
+        let sdc = ilm_data.join("block.core.sdc");
+        fs::write(&sdc, "set_false_path -to [get_pins a/b]\n").unwrap();
+        let rewritten = step.scope_ilm_constraints();
+        let scoped = fs::read_to_string(&sdc).unwrap();
+        fs::remove_dir_all(&work_dir).unwrap();
+        assert_eq!(rewritten, Ok(vec![sdc]));
+        assert_eq!(scoped, "set_false_path -to [get_pins -hierarchical a/b]\n");
+    }
+}
@@ -1,11 +1,28 @@
+pub mod budget;
+pub mod checkpoint;
+pub mod floorplan;
 pub mod genus;
 pub mod innovus;
+pub mod log;
+pub mod mmmc;
 pub mod pegasus;
+pub mod pin_alignment;
+pub mod power_grid;
+pub mod qor;
+pub mod regression;
+pub mod sdc;
+pub mod sdc_reader;
+pub mod substep;
+pub mod tcl;
+#[cfg(test)]
+mod test_util;
+pub mod tool;
 
 use indoc::formatdoc;
-use rust_decimal::Decimal;
-use std::fmt::Write as FmtWrite;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub use mmmc::{AnalysisView, ConstraintMode, CornerRole, MmmcConfig, MmmcCorner, MmmcError};
 
 #[derive(Debug, Clone)]
 pub struct Substep {
@@ -29,124 +46,43 @@ pub struct SubmoduleInfo {
     pub lef: PathBuf,
 }
 
-/// Returns the TCL for clock_constraints and pin_constraints
-pub fn sdc() -> String {
+/// Wraps a block of TCL so that an error writes `name` and the TCL error message to
+/// `error_log` and exits the tool with a non-zero status
+pub fn trap_tcl_errors(name: &str, tcl: &str, error_log: &Path) -> String {
     formatdoc!(
         r#"
-        create_clock clk -name clk -period 2.0
-        set_clock_uncertainty 0.01 [get_clocks clk]
-        set_clock_groups -asynchronous  -group {{ clk }}
-        set_load 1.0 [all_outputs]
-        set_input_delay -clock clk 0 [all_inputs]
-        set_output_delay -clock clk 0 [all_outputs]
-        "#
+        if {{[catch {{
+        {tcl}
+        }} rivet_err]}} {{
+            set rivet_log [open {} w]
+            puts $rivet_log {}
+            puts $rivet_log $rivet_err
+            close $rivet_log
+            exit 1
+        }}
+        "#,
+        tcl::quote(&error_log.display().to_string()),
+        tcl::quote(name),
     )
 }
 
-/// Defines the properties of MMMC Corners with the label, library paths, and temperature
-#[derive(Clone)]
-pub struct MmmcCorner {
-    pub name: String,
-    pub corner_type: String,
-    pub libs: Vec<PathBuf>,
-    pub temperature: Decimal,
+/// Reads the failing substep name and TCL error message written by [`trap_tcl_errors`]
+pub fn read_error_log(error_log: &Path) -> Option<(String, String)> {
+    let contents = fs::read_to_string(error_log).ok()?;
+    let (name, message) = contents.split_once('\n')?;
+    Some((name.trim().to_string(), message.trim().to_string()))
 }
 
-/// Contains the parameters for generating the mmmc.tcl
-#[derive(Clone)]
-pub struct MmmcConfig {
-    pub sdc_files: Vec<PathBuf>,
-    pub corners: Vec<MmmcCorner>,
-    pub setup: Vec<MmmcCorner>,
-    pub hold: Vec<MmmcCorner>,
-    pub dynamic: MmmcCorner,
-    pub leakage: MmmcCorner,
+/// Returns the expected outputs that do not exist on disk
+pub fn missing_outputs(paths: &[PathBuf]) -> Vec<PathBuf> {
+    paths.iter().filter(|p| !p.exists()).cloned().collect()
 }
 
-/// Generates the tcl for the MMMC views
-pub fn mmmc(config: MmmcConfig) -> String {
-    for corner in config
-        .setup
-        .iter()
-        .chain(config.hold.iter())
-        .chain([&config.dynamic, &config.leakage])
-    {
-        assert!(
-            config.corners.iter().any(|c| c.name == *corner.name),
-            "corner referenced but not defined in the list of MMMC corners"
-        );
-    }
-
-    let sdc_files_vec: Vec<String> = config
-        .sdc_files
+/// Formats a list of paths as an indented, newline-separated block for error messages
+pub fn format_paths(paths: &[PathBuf]) -> String {
+    paths
         .iter()
-        .map(|p| p.display().to_string())
-        .collect();
-    let sdc_files = sdc_files_vec.join(" ");
-    let mut mmmc = String::new();
-    let constraint_mode_name = "my_constraint_mode";
-    writeln!(
-        &mut mmmc,
-        "create_constraint_mode -name {constraint_mode_name} -sdc_files [list {sdc_files}]"
-    )
-    .unwrap();
-
-    for corner in config.corners.iter() {
-        let library_set_name = format!("{}.{}_set", corner.name, corner.corner_type);
-        let timing_cond_name = format!("{}.{}_cond", corner.name, corner.corner_type);
-        let rc_corner_name = format!("{}.{}_rc", corner.name, corner.corner_type);
-        let delay_corner_name = format!("{}.{}_delay", corner.name, corner.corner_type);
-        let analysis_view_name = format!("{}.{}_view", corner.name, corner.corner_type);
-        write!(
-            &mut mmmc,
-            "create_library_set -name {library_set_name} -timing [list"
-        )
-        .unwrap();
-        for lib in corner.libs.iter() {
-            write!(&mut mmmc, " {lib:?}").unwrap();
-        }
-        writeln!(&mut mmmc, "]").unwrap();
-
-        writeln!(&mut mmmc, "create_timing_condition -name {timing_cond_name} -library_sets [list {library_set_name}]").unwrap();
-        writeln!(
-            &mut mmmc,
-            "create_rc_corner -name {rc_corner_name} -temperature {}",
-            corner.temperature
-        )
-        .unwrap();
-
-        writeln!(
-            &mut mmmc,
-            "create_delay_corner -name {delay_corner_name} -timing_condition {timing_cond_name} -rc_corner {rc_corner_name}",
-        )
-        .unwrap();
-
-        writeln!(
-            &mut mmmc,
-            "create_analysis_view -name {analysis_view_name} -delay_corner {delay_corner_name} -constraint_mode {constraint_mode_name}",
-        )
-        .unwrap();
-    }
-
-    write!(&mut mmmc, "set_analysis_view -setup {{").unwrap();
-    for corner in config.setup.iter() {
-        write!(&mut mmmc, " {}.{}_view", corner.name, corner.corner_type).unwrap();
-    }
-    write!(&mut mmmc, " }}").unwrap();
-    write!(&mut mmmc, " -hold {{").unwrap();
-    for corner in config.hold.iter() {
-        write!(&mut mmmc, " {}.{}_view", corner.name, corner.corner_type).unwrap();
-    }
-    write!(&mut mmmc, " }}").unwrap();
-    writeln!(
-        &mut mmmc,
-        " -dynamic {}.{}_view -leakage {}.{}_view",
-        config.dynamic.name,
-        config.dynamic.corner_type,
-        config.leakage.name,
-        config.leakage.corner_type,
-    )
-    .unwrap();
-
-    mmmc
+        .map(|p| format!("  {}", p.display()))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
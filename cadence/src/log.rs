@@ -0,0 +1,339 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use crate::tcl::quote;
+
+/// Marker printed by the generated TCL before each substep so log messages can be attributed
+pub const SUBSTEP_MARKER: &str = "--> rivet substep:";
+
+/// Returns the TCL that announces the start of a substep in the tool log
+pub fn substep_marker_tcl(name: &str) -> String {
+    format!("puts {}", quote(&format!("{SUBSTEP_MARKER} {name}")))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "ERROR"),
+            Severity::Warning => write!(f, "WARN"),
+        }
+    }
+}
+
+/// A single Cadence message such as `**ERROR: (IMPLF-200): ...` or `Warning : ... [LBR-38]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogMessage {
+    pub severity: Severity,
+    pub id: String,
+    pub substep: Option<String>,
+    pub text: String,
+}
+
+/// Messages collected from a Genus or Innovus log
+#[derive(Debug, Clone, Default)]
+pub struct LogSummary {
+    pub messages: Vec<LogMessage>,
+}
+
+impl LogSummary {
+    pub fn errors(&self) -> impl Iterator<Item = &LogMessage> {
+        self.messages
+            .iter()
+            .filter(|m| m.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &LogMessage> {
+        self.messages
+            .iter()
+            .filter(|m| m.severity == Severity::Warning)
+    }
+
+    /// Number of messages per severity and message ID
+    pub fn counts_by_id(&self) -> BTreeMap<(Severity, String), usize> {
+        let mut counts = BTreeMap::new();
+        for message in &self.messages {
+            *counts
+                .entry((message.severity, message.id.clone()))
+                .or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Number of messages per substep and severity; messages printed before the first
+    /// substep are grouped under `<preamble>`
+    pub fn counts_by_substep(&self) -> BTreeMap<(String, Severity), usize> {
+        let mut counts = BTreeMap::new();
+        for message in &self.messages {
+            let substep = message
+                .substep
+                .clone()
+                .unwrap_or_else(|| "<preamble>".to_string());
+            *counts.entry((substep, message.severity)).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+impl fmt::Display for LogSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} errors, {} warnings",
+            self.errors().count(),
+            self.warnings().count()
+        )?;
+        for ((severity, id), count) in self.counts_by_id() {
+            writeln!(f, "  {severity} {id}: {count}")?;
+        }
+        for ((substep, severity), count) in self.counts_by_substep() {
+            writeln!(f, "  {substep}: {count} {severity}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Decides which log messages fail a step even when the tool exits successfully
+#[derive(Debug, Clone, Default)]
+pub struct LogPolicy {
+    pub fail_on_error: bool,
+    pub fail_on_warnings: Vec<String>,
+}
+
+impl LogPolicy {
+    /// Returns the messages that violate the policy
+    pub fn violations<'a>(&self, summary: &'a LogSummary) -> Vec<&'a LogMessage> {
+        summary
+            .messages
+            .iter()
+            .filter(|m| match m.severity {
+                Severity::Error => self.fail_on_error,
+                Severity::Warning => self.fail_on_warnings.contains(&m.id),
+            })
+            .collect()
+    }
+}
+
+/// Parses Innovus (`**ERROR: (ID): text`) and Genus (`Error : text [ID]`) messages,
+/// attributing each to the substep announced by the most recent [`SUBSTEP_MARKER`]
+pub fn parse_log(contents: &str) -> LogSummary {
+    let innovus_re = Regex::new(r"^\*\*(ERROR|WARN):\s*\(([A-Za-z0-9_]+-\d+)\):\s*(.*)$").unwrap();
+    let genus_re = Regex::new(r"^(Error|Warning)\s*:\s*(.*?)\s*\[([A-Za-z0-9_]+-\d+)\]").unwrap();
+
+    let mut substep: Option<String> = None;
+    let mut messages = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim_end();
+        if let Some(name) = line.strip_prefix(SUBSTEP_MARKER) {
+            substep = Some(name.trim().to_string());
+            continue;
+        }
+
+        let parsed = if let Some(caps) = innovus_re.captures(line) {
+            Some((
+                &caps[1] == "ERROR",
+                caps[2].to_string(),
+                caps[3].to_string(),
+            ))
+        } else {
+            genus_re.captures(line).map(|caps| {
+                (
+                    &caps[1] == "Error",
+                    caps[3].to_string(),
+                    caps[2].to_string(),
+                )
+            })
+        };
+
+        if let Some((is_error, id, text)) = parsed {
+            messages.push(LogMessage {
+                severity: if is_error {
+                    Severity::Error
+                } else {
+                    Severity::Warning
+                },
+                id,
+                substep: substep.clone(),
+                text,
+            });
+        }
+    }
+
+    LogSummary { messages }
+}
+
+/// Sends each line of `stream` to `lines` until end of file, tagged with whether it came from
+/// stderr; tool logs are not always UTF-8, so invalid bytes are replaced rather than failing
+fn forward_lines(
+    stream: impl Read + Send + 'static,
+    is_stderr: bool,
+    lines: Sender<(bool, io::Result<String>)>,
+) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let line = match reader.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(_) => {
+                    let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+                    let line = line.strip_suffix(b"\r").unwrap_or(line);
+                    Ok(String::from_utf8_lossy(line).into_owned())
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => Err(e),
+            };
+            let failed = line.is_err();
+            if lines.send((is_stderr, line)).is_err() || failed {
+                break;
+            }
+        }
+    });
+}
+
+/// Runs `command`, echoing its stdout and stderr to the terminal while also writing both to
+/// `log_path`, interleaved in the order the lines arrive. Both pipes are drained until the
+/// tool exits even if the log cannot be written, so that the tool never blocks or gets
+/// SIGPIPE; the first error is returned afterwards.
+pub fn run_logged(command: &mut Command, log_path: &Path) -> io::Result<ExitStatus> {
+    let mut log_file = File::create(log_path)?;
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let (sender, lines) = mpsc::channel();
+    let stdout = child.stdout.take().expect("child stdout should be piped");
+    let stderr = child.stderr.take().expect("child stderr should be piped");
+    forward_lines(stdout, false, sender.clone());
+    forward_lines(stderr, true, sender);
+    let mut error = None;
+    for (is_stderr, line) in lines {
+        let written = line.and_then(|line| {
+            if is_stderr {
+                eprintln!("{line}");
+            } else {
+                println!("{line}");
+            }
+            writeln!(log_file, "{line}")
+        });
+        if let Err(e) = written {
+            error.get_or_insert(e);
+        }
+    }
+
+    let status = child.wait()?;
+    match error {
+        Some(e) => Err(e),
+        None => Ok(status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_innovus_and_genus_messages_per_substep() {
+        let log = "\
+**WARN: (IMPLF-200): Pin 'A' has no antenna model
+--> rivet substep: read_design_files
+**ERROR: (IMPLF-40): Macro 'foo' not found
+Warning : Libraries have inconsistent nominal operating conditions. [LBR-38]
+--> rivet substep: init_design
+**WARN: (IMPLF-200): Pin 'B' has no antenna model
+Info    : Elaborating design. [ELAB-1]
+";
+        let summary = parse_log(log);
+        assert_eq!(summary.messages.len(), 4);
+        assert_eq!(summary.errors().count(), 1);
+        assert_eq!(summary.messages[0].substep, None);
+        assert_eq!(
+            summary.messages[1].substep.as_deref(),
+            Some("read_design_files")
+        );
+        assert_eq!(summary.messages[2].id, "LBR-38");
+        assert_eq!(
+            summary.messages[2].text,
+            "Libraries have inconsistent nominal operating conditions."
+        );
+        assert_eq!(
+            summary.counts_by_id()[&(Severity::Warning, "IMPLF-200".to_string())],
+            2
+        );
+        assert_eq!(
+            summary.counts_by_substep()[&("init_design".to_string(), Severity::Warning)],
+            1
+        );
+    }
+
+    #[test]
+    fn policy_flags_errors_and_selected_warnings() {
+        let summary = parse_log(
+            "**ERROR: (IMPLF-40): missing\n**WARN: (IMPLF-200): a\n**WARN: (TA-112): b\n",
+        );
+        assert!(LogPolicy::default().violations(&summary).is_empty());
+
+        let policy = LogPolicy {
+            fail_on_error: true,
+            fail_on_warnings: vec!["TA-112".into()],
+        };
+        let ids: Vec<&str> = policy
+            .violations(&summary)
+            .iter()
+            .map(|m| m.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["IMPLF-40", "TA-112"]);
+    }
+
+    #[test]
+    fn run_logged_merges_stdout_and_stderr() {
+        let log_path = std::env::temp_dir().join(format!("rivet-log-{}.log", std::process::id()));
+        let status = run_logged(
+            Command::new("sh").args(["-c", "echo out; echo err >&2; exit 3"]),
+            &log_path,
+        )
+        .unwrap();
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        std::fs::remove_file(&log_path).unwrap();
+        assert_eq!(status.code(), Some(3));
+        let mut lines: Vec<&str> = log.lines().collect();
+        lines.sort();
+        assert_eq!(lines, ["err", "out"]);
+    }
+
+    #[test]
+    fn run_logged_keeps_non_utf8_output() {
+        let log_path =
+            std::env::temp_dir().join(format!("rivet-latin1-{}.log", std::process::id()));
+        let status = run_logged(
+            Command::new("sh").args(["-c", "printf 'caf\\351\\n'; echo done"]),
+            &log_path,
+        )
+        .unwrap();
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        std::fs::remove_file(&log_path).unwrap();
+        assert!(status.success());
+        assert_eq!(log, "caf\u{fffd}\ndone\n");
+    }
+
+    #[test]
+    fn substep_marker_is_one_quoted_word() {
+        let tcl = substep_marker_tcl("fix [drc] $x");
+        assert_eq!(crate::tcl::check_syntax(&tcl), Ok(()));
+        assert_eq!(tcl, format!("puts {{{SUBSTEP_MARKER} fix [drc] $x}}"));
+    }
+}
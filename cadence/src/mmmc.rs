@@ -0,0 +1,516 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use rust_decimal::Decimal;
+
+use crate::tcl::{Command, Script, list};
+
+/// What an MMMC corner is analyzed for; also the suffix of the names generated for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CornerRole {
+    Setup,
+    Hold,
+    /// Typical corner used for power and reporting
+    Extra,
+}
+
+impl CornerRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CornerRole::Setup => "setup",
+            CornerRole::Hold => "hold",
+            CornerRole::Extra => "extra",
+        }
+    }
+}
+
+impl fmt::Display for CornerRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// On-chip variation derates applied to the delay corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OcvDerate {
+    pub early: Decimal,
+    pub late: Decimal,
+}
+
+/// Defines the properties of MMMC Corners with the label, library paths, and RC extraction data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MmmcCorner {
+    pub name: String,
+    pub role: CornerRole,
+    pub libs: Vec<PathBuf>,
+    pub temperature: Decimal,
+    pub qrc_tech: Option<PathBuf>,
+    pub cap_table: Option<PathBuf>,
+    pub derate: Option<OcvDerate>,
+}
+
+impl MmmcCorner {
+    pub fn new(name: &str, role: CornerRole, libs: Vec<PathBuf>, temperature: Decimal) -> Self {
+        MmmcCorner {
+            name: name.to_string(),
+            role,
+            libs,
+            temperature,
+            qrc_tech: None,
+            cap_table: None,
+            derate: None,
+        }
+    }
+
+    pub fn qrc_tech(mut self, path: impl Into<PathBuf>) -> Self {
+        self.qrc_tech = Some(path.into());
+        self
+    }
+
+    pub fn cap_table(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cap_table = Some(path.into());
+        self
+    }
+
+    pub fn derate(mut self, early: Decimal, late: Decimal) -> Self {
+        self.derate = Some(OcvDerate { early, late });
+        self
+    }
+
+    fn object_name(&self, kind: &str) -> String {
+        format!("{}.{}_{kind}", self.name, self.role)
+    }
+
+    pub fn library_set_name(&self) -> String {
+        self.object_name("set")
+    }
+
+    pub fn rc_corner_name(&self) -> String {
+        self.object_name("rc")
+    }
+
+    pub fn delay_corner_name(&self) -> String {
+        self.object_name("delay")
+    }
+
+    pub fn view_name(&self) -> String {
+        self.object_name("view")
+    }
+}
+
+/// A named set of SDC files, such as functional or scan mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintMode {
+    pub name: String,
+    pub sdc_files: Vec<PathBuf>,
+}
+
+impl ConstraintMode {
+    pub fn new(name: &str, sdc_files: Vec<PathBuf>) -> Self {
+        ConstraintMode {
+            name: name.to_string(),
+            sdc_files,
+        }
+    }
+}
+
+/// Name of the mode created by [`MmmcConfig::new`]
+pub const DEFAULT_CONSTRAINT_MODE: &str = "my_constraint_mode";
+
+/// Selects the analysis view of `corner` under constraint mode `mode`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalysisView {
+    pub mode: String,
+    pub corner: String,
+}
+
+impl AnalysisView {
+    pub fn new(mode: &str, corner: &str) -> Self {
+        AnalysisView {
+            mode: mode.to_string(),
+            corner: corner.to_string(),
+        }
+    }
+}
+
+/// An analysis view resolved against the modes and corners of an [`MmmcConfig`]
+#[derive(Debug, Clone, Copy)]
+pub struct View<'a> {
+    pub mode: &'a ConstraintMode,
+    pub corner: &'a MmmcCorner,
+    /// Whether `mode` is the first mode, whose views keep the plain corner view name
+    primary_mode: bool,
+}
+
+impl View<'_> {
+    pub fn name(&self) -> String {
+        if self.primary_mode {
+            self.corner.view_name()
+        } else {
+            self.corner.object_name(&format!("{}_view", self.mode.name))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MmmcError {
+    /// A view references a corner that is not in the list of corners
+    UnknownCorner(String),
+    /// Two corners share a name
+    DuplicateCorner(String),
+    /// A view references a constraint mode that is not defined
+    UnknownMode(String),
+    /// Two constraint modes share a name
+    DuplicateMode(String),
+    /// No corner is analyzed for this role
+    NoViews(CornerRole),
+}
+
+impl fmt::Display for MmmcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MmmcError::UnknownCorner(name) => {
+                write!(f, "corner {name} is referenced but not defined")
+            }
+            MmmcError::DuplicateCorner(name) => write!(f, "corner {name} is defined twice"),
+            MmmcError::UnknownMode(name) => {
+                write!(f, "constraint mode {name} is referenced but not defined")
+            }
+            MmmcError::DuplicateMode(name) => {
+                write!(f, "constraint mode {name} is defined twice")
+            }
+            MmmcError::NoViews(role) => write!(f, "no {role} analysis views"),
+        }
+    }
+}
+
+impl std::error::Error for MmmcError {}
+
+fn check_unique<'a>(
+    names: impl Iterator<Item = &'a String>,
+    error: fn(String) -> MmmcError,
+) -> Result<(), MmmcError> {
+    let mut seen: Vec<&String> = Vec::new();
+    for name in names {
+        if seen.contains(&name) {
+            return Err(error(name.clone()));
+        }
+        seen.push(name);
+    }
+    Ok(())
+}
+
+/// Contains the parameters for generating the mmmc.tcl
+#[derive(Debug, Clone)]
+pub struct MmmcConfig {
+    modes: Vec<ConstraintMode>,
+    corners: Vec<MmmcCorner>,
+    setup: Vec<AnalysisView>,
+    hold: Vec<AnalysisView>,
+    dynamic: AnalysisView,
+    leakage: AnalysisView,
+}
+
+impl MmmcConfig {
+    /// A configuration with a single constraint mode reading `sdc_files`, with views selected by
+    /// corner name
+    pub fn new(
+        sdc_files: Vec<PathBuf>,
+        corners: Vec<MmmcCorner>,
+        setup: &[&str],
+        hold: &[&str],
+        dynamic: &str,
+        leakage: &str,
+    ) -> Result<Self, MmmcError> {
+        let view = |corner: &str| AnalysisView::new(DEFAULT_CONSTRAINT_MODE, corner);
+        MmmcConfig::with_modes(
+            vec![ConstraintMode::new(DEFAULT_CONSTRAINT_MODE, sdc_files)],
+            corners,
+            setup.iter().map(|c| view(c)).collect(),
+            hold.iter().map(|c| view(c)).collect(),
+            view(dynamic),
+            view(leakage),
+        )
+    }
+
+    /// Checks that mode and corner names are unique, that every view names a defined mode and
+    /// corner and that there is at least one setup and one hold view. An analysis view is
+    /// created for every mode and corner combination.
+    pub fn with_modes(
+        modes: Vec<ConstraintMode>,
+        corners: Vec<MmmcCorner>,
+        setup: Vec<AnalysisView>,
+        hold: Vec<AnalysisView>,
+        dynamic: AnalysisView,
+        leakage: AnalysisView,
+    ) -> Result<Self, MmmcError> {
+        check_unique(modes.iter().map(|m| &m.name), MmmcError::DuplicateMode)?;
+        check_unique(corners.iter().map(|c| &c.name), MmmcError::DuplicateCorner)?;
+        for view in setup.iter().chain(&hold).chain([&dynamic, &leakage]) {
+            if !modes.iter().any(|m| m.name == view.mode) {
+                return Err(MmmcError::UnknownMode(view.mode.clone()));
+            }
+            if !corners.iter().any(|c| c.name == view.corner) {
+                return Err(MmmcError::UnknownCorner(view.corner.clone()));
+            }
+        }
+        if setup.is_empty() {
+            return Err(MmmcError::NoViews(CornerRole::Setup));
+        }
+        if hold.is_empty() {
+            return Err(MmmcError::NoViews(CornerRole::Hold));
+        }
+        Ok(MmmcConfig {
+            modes,
+            corners,
+            setup,
+            hold,
+            dynamic,
+            leakage,
+        })
+    }
+
+    pub fn modes(&self) -> &[ConstraintMode] {
+        &self.modes
+    }
+
+    pub fn corners(&self) -> &[MmmcCorner] {
+        &self.corners
+    }
+
+    fn resolve(&self, view: &AnalysisView) -> View<'_> {
+        let mode = self
+            .modes
+            .iter()
+            .position(|m| m.name == view.mode)
+            .expect("views are validated in MmmcConfig::with_modes");
+        View {
+            mode: &self.modes[mode],
+            corner: self
+                .corners
+                .iter()
+                .find(|c| c.name == view.corner)
+                .expect("views are validated in MmmcConfig::with_modes"),
+            primary_mode: mode == 0,
+        }
+    }
+
+    /// Every mode and corner combination, in the order the views are created
+    pub fn views(&self) -> Vec<View<'_>> {
+        self.modes
+            .iter()
+            .flat_map(|mode| {
+                self.corners
+                    .iter()
+                    .map(|corner| self.resolve(&AnalysisView::new(&mode.name, &corner.name)))
+            })
+            .collect()
+    }
+
+    pub fn setup(&self) -> Vec<View<'_>> {
+        self.setup.iter().map(|v| self.resolve(v)).collect()
+    }
+
+    pub fn hold(&self) -> Vec<View<'_>> {
+        self.hold.iter().map(|v| self.resolve(v)).collect()
+    }
+
+    /// The first setup view, used for max delays in sign-off outputs
+    pub fn primary_setup(&self) -> View<'_> {
+        self.resolve(&self.setup[0])
+    }
+
+    /// The first hold view, used for min delays in sign-off outputs
+    pub fn primary_hold(&self) -> View<'_> {
+        self.resolve(&self.hold[0])
+    }
+
+    pub fn dynamic(&self) -> View<'_> {
+        self.resolve(&self.dynamic)
+    }
+
+    pub fn leakage(&self) -> View<'_> {
+        self.resolve(&self.leakage)
+    }
+
+    /// Generates the tcl for the MMMC views
+    pub fn to_tcl(&self) -> String {
+        let mut mmmc = Script::new();
+        for mode in &self.modes {
+            mmmc.command(
+                Command::new("create_constraint_mode")
+                    .opt("name", &mode.name)
+                    .opt("sdc_files", list(&mode.sdc_files)),
+            );
+        }
+
+        for corner in self.corners.iter() {
+            let library_set_name = corner.library_set_name();
+            let timing_cond_name = corner.object_name("cond");
+            let rc_corner_name = corner.rc_corner_name();
+            mmmc.command(
+                Command::new("create_library_set")
+                    .opt("name", &library_set_name)
+                    .opt("timing", list(&corner.libs)),
+            );
+            mmmc.command(
+                Command::new("create_timing_condition")
+                    .opt("name", &timing_cond_name)
+                    .opt("library_sets", list([&library_set_name])),
+            );
+            let mut rc_corner = Command::new("create_rc_corner")
+                .opt("name", &rc_corner_name)
+                .opt("temperature", corner.temperature);
+            if let Some(qrc_tech) = &corner.qrc_tech {
+                rc_corner = rc_corner.opt("qrc_tech", qrc_tech);
+            }
+            if let Some(cap_table) = &corner.cap_table {
+                rc_corner = rc_corner.opt("cap_table", cap_table);
+            }
+            mmmc.command(rc_corner);
+            mmmc.command(
+                Command::new("create_delay_corner")
+                    .opt("name", corner.delay_corner_name())
+                    .opt("timing_condition", &timing_cond_name)
+                    .opt("rc_corner", &rc_corner_name),
+            );
+        }
+
+        for view in self.views() {
+            mmmc.command(
+                Command::new("create_analysis_view")
+                    .opt("name", view.name())
+                    .opt("delay_corner", view.corner.delay_corner_name())
+                    .opt("constraint_mode", &view.mode.name),
+            );
+        }
+
+        mmmc.command(
+            Command::new("set_analysis_view")
+                .opt("setup", list(self.setup().iter().map(View::name)))
+                .opt("hold", list(self.hold().iter().map(View::name)))
+                .opt("dynamic", self.dynamic().name())
+                .opt("leakage", self.leakage().name()),
+        );
+
+        for corner in &self.corners {
+            if let Some(derate) = corner.derate {
+                for (flag, value) in [("early", derate.early), ("late", derate.late)] {
+                    mmmc.command(
+                        Command::new("set_timing_derate")
+                            .opt("delay_corner", corner.delay_corner_name())
+                            .opt(flag, value),
+                    );
+                }
+            }
+        }
+
+        mmmc.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corners() -> Vec<MmmcCorner> {
+        vec![
+            MmmcCorner::new(
+                "ss",
+                CornerRole::Setup,
+                vec!["ss.lib".into()],
+                Decimal::new(100, 0),
+            )
+            .qrc_tech("ss.qrc")
+            .derate(Decimal::new(95, 2), Decimal::new(105, 2)),
+            MmmcCorner::new(
+                "ff",
+                CornerRole::Hold,
+                vec!["ff.lib".into()],
+                Decimal::new(-40, 0),
+            )
+            .cap_table("ff.captbl"),
+        ]
+    }
+
+    #[test]
+    fn validates_views_and_renders_rc_data() {
+        assert_eq!(
+            MmmcConfig::new(vec![], corners(), &["ss"], &["tt"], "ss", "ss").unwrap_err(),
+            MmmcError::UnknownCorner("tt".into())
+        );
+        assert_eq!(
+            MmmcConfig::new(vec![], corners(), &["ss"], &[], "ss", "ss").unwrap_err(),
+            MmmcError::NoViews(CornerRole::Hold)
+        );
+        let mut duplicated = corners();
+        duplicated.push(duplicated[0].clone());
+        assert_eq!(
+            MmmcConfig::new(vec![], duplicated, &["ss"], &["ff"], "ss", "ss").unwrap_err(),
+            MmmcError::DuplicateCorner("ss".into())
+        );
+
+        let config = MmmcConfig::new(
+            vec!["a.sdc".into()],
+            corners(),
+            &["ss"],
+            &["ff"],
+            "ss",
+            "ff",
+        )
+        .unwrap();
+        assert_eq!(config.primary_hold().name(), "ff.hold_view");
+        let tcl = config.to_tcl();
+        assert!(
+            tcl.contains("create_rc_corner -name ss.setup_rc -temperature 100 -qrc_tech ss.qrc\n")
+        );
+        assert!(
+            tcl.contains(
+                "create_rc_corner -name ff.hold_rc -temperature -40 -cap_table ff.captbl\n"
+            )
+        );
+        assert!(tcl.contains("set_timing_derate -delay_corner ss.setup_delay -late 1.05\n"));
+    }
+
+    #[test]
+    fn creates_views_for_every_mode_and_corner() {
+        let modes = vec![
+            ConstraintMode::new("func", vec!["func.sdc".into()]),
+            ConstraintMode::new("scan", vec!["scan.sdc".into()]),
+        ];
+        assert_eq!(
+            MmmcConfig::with_modes(
+                modes.clone(),
+                corners(),
+                vec![AnalysisView::new("test", "ss")],
+                vec![AnalysisView::new("func", "ff")],
+                AnalysisView::new("func", "ss"),
+                AnalysisView::new("func", "ss"),
+            )
+            .unwrap_err(),
+            MmmcError::UnknownMode("test".into())
+        );
+
+        let config = MmmcConfig::with_modes(
+            modes,
+            corners(),
+            vec![
+                AnalysisView::new("func", "ss"),
+                AnalysisView::new("scan", "ss"),
+            ],
+            vec![AnalysisView::new("scan", "ff")],
+            AnalysisView::new("func", "ss"),
+            AnalysisView::new("func", "ss"),
+        )
+        .unwrap();
+        assert_eq!(config.views().len(), 4);
+        let tcl = config.to_tcl();
+        assert!(tcl.contains("create_constraint_mode -name scan -sdc_files [list scan.sdc]\n"));
+        assert!(tcl.contains(
+            "create_analysis_view -name ff.hold_scan_view -delay_corner ff.hold_delay -constraint_mode scan\n"
+        ));
+        assert!(tcl.contains(
+            "set_analysis_view -setup [list ss.setup_view ss.setup_scan_view] -hold [list ff.hold_scan_view]"
+        ));
+    }
+}
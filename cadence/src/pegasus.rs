@@ -1,14 +1,96 @@
 use std::fmt::Debug;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::{fs, io};
 
 use crate::Substep;
+use crate::qor::QorError;
 use fs::File;
 use rivet::Step;
 use std::sync::Arc;
 
+/// File in which a Pegasus run records whether `func` passed
+pub fn outcome_path(work_dir: &Path, func: &str) -> PathBuf {
+    work_dir.join(format!("pegasus_{func}.status"))
+}
+
+/// Whether the last `func` run in `work_dir` passed, or `None` if it never finished
+pub fn read_outcome(work_dir: &Path, func: &str) -> Option<bool> {
+    let outcome = fs::read_to_string(outcome_path(work_dir, func)).ok()?;
+    match outcome.trim() {
+        "passed" => Some(true),
+        "failed" => Some(false),
+        _ => None,
+    }
+}
+
+/// Summary report a DRC run of `module` writes to `work_dir`
+pub fn drc_summary_path(work_dir: &Path, module: &str) -> PathBuf {
+    work_dir.join(format!("{module}.sum"))
+}
+
+/// Number of violations in the summary of the last DRC run of `module`, or `None` if DRC
+/// has not run
+pub fn read_drc_violations(work_dir: &Path, module: &str) -> Result<Option<u64>, QorError> {
+    let path = drc_summary_path(work_dir, module);
+    let Ok(summary) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    for (i, line) in summary.lines().enumerate() {
+        let Some((label, value)) = line.split_once(':') else {
+            continue;
+        };
+        let label = label.to_ascii_lowercase();
+        if !(label.contains("total") && label.contains("result")) {
+            continue;
+        }
+        let count = value.split_whitespace().next().unwrap_or_default();
+        return count.parse().map(Some).map_err(|_| QorError::Parse {
+            path: path.clone(),
+            line: i + 1,
+            message: format!("invalid DRC result count `{count}`"),
+        });
+    }
+    Err(QorError::Parse {
+        path,
+        line: 0,
+        message: "no total DRC result count".into(),
+    })
+}
+
+/// Comparison report an LVS run of `module` writes to `work_dir`
+pub fn lvs_report_path(work_dir: &Path, module: &str) -> PathBuf {
+    work_dir.join(format!("{module}.lvs.report"))
+}
+
+/// Whether the layout and schematic of `module` matched in the last LVS run, or `None` if LVS
+/// has not run
+pub fn read_lvs_match(work_dir: &Path, module: &str) -> Result<Option<bool>, QorError> {
+    let path = lvs_report_path(work_dir, module);
+    let Ok(report) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    for line in report.lines() {
+        let Some((label, value)) = line.split_once(':') else {
+            continue;
+        };
+        if !label.to_ascii_lowercase().contains("result") {
+            continue;
+        }
+        match value.trim().to_ascii_uppercase().as_str() {
+            "MATCH" | "CORRECT" => return Ok(Some(true)),
+            "MISMATCH" | "INCORRECT" => return Ok(Some(false)),
+            _ => {}
+        }
+    }
+    Err(QorError::Parse {
+        path,
+        line: 0,
+        message: "no LVS comparison result".into(),
+    })
+}
+
 #[derive(Debug)]
 pub struct PegasusStep {
     pub work_dir: PathBuf,
@@ -36,6 +118,12 @@ impl PegasusStep {
         }
     }
 
+    fn record_outcome(&self, passed: bool) {
+        let outcome = if passed { "passed" } else { "failed" };
+        fs::write(outcome_path(&self.work_dir, &self.func), outcome)
+            .expect("Failed to record pegasus outcome");
+    }
+
     #[allow(dead_code)]
     fn make_ctl_file(
         &self,
@@ -114,12 +202,19 @@ impl Step for PegasusStep {
                 .current_dir(self.work_dir.clone())
                 .status()
                 .expect("Failed to execute pegasus for LVS");
+            // Pegasus exits successfully when the comparison finds mismatches
+            let matched = read_lvs_match(&self.work_dir, &self.module);
+            self.record_outcome(lvs_status.success() && matched == Ok(Some(true)));
 
             if !lvs_status.success() {
                 eprintln!("Pegasus LVS command failed with status: {}", lvs_status);
                 panic!("Stopped flow due to LVS failure");
-            } else {
-                println!("Pegasus LVS completed successfully.");
+            }
+            match matched {
+                Ok(Some(true)) => println!("Pegasus LVS completed successfully."),
+                Ok(Some(false)) => panic!("Stopped flow due to LVS mismatches"),
+                Ok(None) => panic!("Pegasus LVS wrote no comparison report"),
+                Err(e) => panic!("Failed to read the LVS report: {e}"),
             }
         }
 
@@ -140,12 +235,19 @@ impl Step for PegasusStep {
                 .current_dir(self.work_dir.clone())
                 .status()
                 .expect("Failed to execute pegasus for DRC");
+            // Pegasus exits successfully when the layout has violations
+            let violations = read_drc_violations(&self.work_dir, &self.module);
+            self.record_outcome(drc_status.success() && violations == Ok(Some(0)));
 
             if !drc_status.success() {
                 eprintln!("Pegasus DRC command failed with status: {}", drc_status);
                 panic!("Stopped flow due to DRC failure");
-            } else {
-                println!("Pegasus DRC completed successfully.");
+            }
+            match violations {
+                Ok(Some(0)) => println!("Pegasus DRC completed successfully."),
+                Ok(Some(count)) => panic!("Stopped flow due to {count} DRC violations"),
+                Ok(None) => panic!("Pegasus DRC wrote no summary"),
+                Err(e) => panic!("Failed to read the DRC summary: {e}"),
             }
         }
     }
@@ -157,3 +259,39 @@ impl Step for PegasusStep {
         self.pinned
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_drc_violations_from_summary() {
+        let work_dir = std::env::temp_dir().join(format!("rivet-pegasus-{}", std::process::id()));
+        fs::create_dir_all(&work_dir).unwrap();
+        assert_eq!(read_drc_violations(&work_dir, "top"), Ok(None));
+
+        let summary = drc_summary_path(&work_dir, "top");
+        fs::write(&summary, "Rules checked : 512\nTotal DRC Results : 7 (9)\n").unwrap();
+        assert_eq!(read_drc_violations(&work_dir, "top"), Ok(Some(7)));
+        fs::write(&summary, "Rules checked : 512\n").unwrap();
+        let missing = read_drc_violations(&work_dir, "top");
+        fs::remove_dir_all(&work_dir).unwrap();
+        assert!(matches!(missing, Err(QorError::Parse { line: 0, .. })));
+    }
+
+    #[test]
+    fn reads_lvs_comparison_result() {
+        let work_dir = std::env::temp_dir().join(format!("rivet-lvs-{}", std::process::id()));
+        fs::create_dir_all(&work_dir).unwrap();
+        assert_eq!(read_lvs_match(&work_dir, "top"), Ok(None));
+
+        let report = lvs_report_path(&work_dir, "top");
+        fs::write(&report, "Layout cell : top\nRun Result : MISMATCH\n").unwrap();
+        let mismatch = read_lvs_match(&work_dir, "top");
+        fs::write(&report, "Run Result : MATCH\n").unwrap();
+        let matched = read_lvs_match(&work_dir, "top");
+        fs::remove_dir_all(&work_dir).unwrap();
+        assert_eq!(mismatch, Ok(Some(false)));
+        assert_eq!(matched, Ok(Some(true)));
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use crate::floorplan::{Orientation, microns};
+use crate::innovus::{
+    Floorplan, HardMacroConstraint, PinPlacement, PinSelector, PinSpread, Point, Side,
+    TopLevelConstraint,
+};
+
+/// One end of a net of a parent block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetEnd {
+    /// Port `port` of the hard macro instance `instance`
+    Instance { instance: String, port: String },
+    /// A port of the parent block on one of its sides
+    Boundary(Side),
+    /// A fixed point of the parent block, in microns
+    Point(Point),
+}
+
+/// A net of a parent block, connecting its hard macros to each other and to its ports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParentNet {
+    pub name: String,
+    pub ends: Vec<NetEnd>,
+}
+
+impl ParentNet {
+    pub fn new(name: impl Into<String>, ends: Vec<NetEnd>) -> Self {
+        ParentNet {
+            name: name.into(),
+            ends,
+        }
+    }
+}
+
+/// Outline and ports of a block that its parent instantiates as a hard macro
+#[derive(Debug, Clone)]
+pub struct BlockOutline {
+    pub master: String,
+    pub width: Decimal,
+    pub height: Decimal,
+    pub ports: Vec<String>,
+}
+
+impl BlockOutline {
+    pub fn new(master: impl Into<String>, top: &TopLevelConstraint, ports: Vec<String>) -> Self {
+        let (width, height) = top.die_size();
+        BlockOutline {
+            master: master.into(),
+            width,
+            height,
+            ports,
+        }
+    }
+}
+
+/// How the pins of a child block are laid out once their sides are known
+#[derive(Debug, Clone)]
+pub struct AlignmentSpec {
+    /// Layers of the pins on the left and right sides
+    pub horizontal_layers: Vec<String>,
+    /// Layers of the pins on the top and bottom sides
+    pub vertical_layers: Vec<String>,
+    /// Smallest distance between two pins of a side
+    pub pitch: Decimal,
+    /// Distance kept clear of pins at both ends of a side
+    pub margin: Decimal,
+    /// Side of the ports that are not connected in the parent
+    pub unconnected_side: Side,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlignmentError {
+    UnknownInstance(String),
+    UnknownMaster(String),
+    UnknownPort {
+        master: String,
+        port: String,
+    },
+    UnknownOrientation(String),
+    /// More pins face a side than fit on it at the pin pitch
+    SideFull {
+        master: String,
+        side: Side,
+        pins: usize,
+        capacity: usize,
+    },
+}
+
+impl fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignmentError::UnknownInstance(name) => write!(f, "no hard macro named `{name}`"),
+            AlignmentError::UnknownMaster(master) => write!(f, "no outline for block `{master}`"),
+            AlignmentError::UnknownPort { master, port } => {
+                write!(f, "block `{master}` has no port `{port}`")
+            }
+            AlignmentError::UnknownOrientation(orientation) => {
+                write!(f, "unknown orientation `{orientation}`")
+            }
+            AlignmentError::SideFull {
+                master,
+                side,
+                pins,
+                capacity,
+            } => write!(
+                f,
+                "{pins} pins of `{master}` face its {side} side, which fits {capacity}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AlignmentError {}
+
+/// A hard macro of the parent, located in its coordinates
+struct Placed<'a> {
+    origin: Point,
+    /// Outline after orientation; zero when the macro is not a block being aligned
+    size: Point,
+    orientation: Orientation,
+    outline: Option<&'a BlockOutline>,
+}
+
+impl<'a> Placed<'a> {
+    fn new(
+        constraint: &HardMacroConstraint,
+        blocks: &'a [BlockOutline],
+    ) -> Result<Self, AlignmentError> {
+        let orientation = Orientation::parse(&constraint.orientation)
+            .ok_or_else(|| AlignmentError::UnknownOrientation(constraint.orientation.clone()))?;
+        let outline = blocks.iter().find(|b| b.master == constraint.master);
+        let size = outline.map_or((Decimal::ZERO, Decimal::ZERO), |b| {
+            orientation.placed_size(b.width, b.height)
+        });
+        Ok(Placed {
+            origin: (microns(constraint.x), microns(constraint.y)),
+            size,
+            orientation,
+            outline,
+        })
+    }
+
+    fn center(&self) -> Point {
+        (
+            self.origin.0 + self.size.0 / Decimal::TWO,
+            self.origin.1 + self.size.1 / Decimal::TWO,
+        )
+    }
+}
+
+/// The side of a `width` by `height` block that `target` lies beyond, and the position
+/// along that side closest to it
+fn facing_side(target: Point, width: Decimal, height: Decimal) -> Option<(Side, Decimal)> {
+    let (du, dv) = (
+        target.0 - width / Decimal::TWO,
+        target.1 - height / Decimal::TWO,
+    );
+    if du.is_zero() && dv.is_zero() {
+        return None;
+    }
+    Some(if du.abs() * height >= dv.abs() * width {
+        let side = if du < Decimal::ZERO {
+            Side::Left
+        } else {
+            Side::Right
+        };
+        (side, target.1)
+    } else {
+        let side = if dv < Decimal::ZERO {
+            Side::Bottom
+        } else {
+            Side::Top
+        };
+        (side, target.0)
+    })
+}
+
+/// Spreads `desired` positions, sorted, at least `pitch` apart between `low` and `high`,
+/// moving each as little as possible
+fn spread(desired: &[Decimal], low: Decimal, high: Decimal, pitch: Decimal) -> Vec<Decimal> {
+    let mut positions: Vec<Decimal> = Vec::with_capacity(desired.len());
+    for &d in desired {
+        let floor = positions.last().map_or(low, |&p| p + pitch);
+        positions.push(d.clamp(low, high).max(floor));
+    }
+    let mut ceiling = high;
+    for p in positions.iter_mut().rev() {
+        *p = (*p).min(ceiling);
+        ceiling = *p - pitch;
+    }
+    positions
+}
+
+/// Derives the pin placement of the block `master` from where its parent instantiates it:
+/// each port goes on the side facing the blocks and parent ports it connects to, as close to
+/// them as the pin pitch allows. Ports of blocks instantiated more than once face the average
+/// of their neighbors over all instances.
+pub fn align_block_pins(
+    parent: &Floorplan,
+    nets: &[ParentNet],
+    blocks: &[BlockOutline],
+    master: &str,
+    spec: &AlignmentSpec,
+) -> Result<Vec<PinPlacement>, AlignmentError> {
+    let block = blocks
+        .iter()
+        .find(|b| b.master == master)
+        .ok_or_else(|| AlignmentError::UnknownMaster(master.into()))?;
+    let parent_size = (microns(parent.top.width), microns(parent.top.height));
+    let macros = parent
+        .hard_macros
+        .iter()
+        .map(|m| Ok((m.name.as_str(), Placed::new(m, blocks)?)))
+        .collect::<Result<HashMap<_, _>, AlignmentError>>()?;
+    let placed = |name: &str| {
+        macros
+            .get(name)
+            .ok_or_else(|| AlignmentError::UnknownInstance(name.into()))
+    };
+
+    // Sum and count of the neighbor positions of each port, in block coordinates
+    let mut targets: HashMap<&str, (Point, Decimal)> = HashMap::new();
+    for (name, instance) in &macros {
+        if instance.outline.is_none_or(|o| o.master != master) {
+            continue;
+        }
+        for net in nets {
+            for end in &net.ends {
+                let NetEnd::Instance { instance: i, port } = end else {
+                    continue;
+                };
+                if i != name {
+                    continue;
+                }
+                if !block.ports.contains(port) {
+                    return Err(AlignmentError::UnknownPort {
+                        master: master.into(),
+                        port: port.clone(),
+                    });
+                }
+                let center = instance.center();
+                let mut neighbors = Vec::new();
+                for other in net.ends.iter().filter(|&e| e != end) {
+                    neighbors.push(match other {
+                        NetEnd::Instance { instance, .. } => placed(instance)?.center(),
+                        NetEnd::Boundary(Side::Left) => (Decimal::ZERO, center.1),
+                        NetEnd::Boundary(Side::Right) => (parent_size.0, center.1),
+                        NetEnd::Boundary(Side::Bottom) => (center.0, Decimal::ZERO),
+                        NetEnd::Boundary(Side::Top) => (center.0, parent_size.1),
+                        NetEnd::Point(p) => *p,
+                    });
+                }
+                if neighbors.is_empty() {
+                    continue;
+                }
+                let count = Decimal::from(neighbors.len());
+                let (x, y) = neighbors
+                    .iter()
+                    .fold((Decimal::ZERO, Decimal::ZERO), |(x, y), p| {
+                        (x + p.0, y + p.1)
+                    });
+                let relative = (x / count - instance.origin.0, y / count - instance.origin.1);
+                let (u, v) = instance
+                    .orientation
+                    .to_cell(relative, block.width, block.height);
+                let entry = targets
+                    .entry(port.as_str())
+                    .or_insert(((Decimal::ZERO, Decimal::ZERO), Decimal::ZERO));
+                entry.0 = (entry.0.0 + u, entry.0.1 + v);
+                entry.1 += Decimal::ONE;
+            }
+        }
+    }
+
+    let unconnected = |side: Side| match side {
+        Side::Left | Side::Right => (side, block.height / Decimal::TWO),
+        Side::Top | Side::Bottom => (side, block.width / Decimal::TWO),
+    };
+    let mut by_side: Vec<(Side, Decimal, &str)> = block
+        .ports
+        .iter()
+        .map(|port| {
+            let (side, position) = targets
+                .get(port.as_str())
+                .and_then(|&((u, v), n)| facing_side((u / n, v / n), block.width, block.height))
+                .unwrap_or_else(|| unconnected(spec.unconnected_side));
+            (side, position, port.as_str())
+        })
+        .collect();
+    by_side.sort_by(|a, b| a.1.cmp(&b.1).then(a.2.cmp(b.2)));
+
+    let mut placements = Vec::new();
+    for side in [Side::Left, Side::Right, Side::Top, Side::Bottom] {
+        let (names, desired): (Vec<String>, Vec<Decimal>) = by_side
+            .iter()
+            .filter(|(s, ..)| *s == side)
+            .map(|(_, position, port)| (port.to_string(), *position))
+            .unzip();
+        if names.is_empty() {
+            continue;
+        }
+        let (length, layers) = match side {
+            Side::Left | Side::Right => (block.height, &spec.horizontal_layers),
+            Side::Top | Side::Bottom => (block.width, &spec.vertical_layers),
+        };
+        let (low, high) = (spec.margin, length - spec.margin);
+        let capacity = if high < low {
+            0
+        } else {
+            ((high - low) / spec.pitch)
+                .floor()
+                .try_into()
+                .map_or(usize::MAX, |c: usize| c + 1)
+        };
+        if names.len() > capacity {
+            return Err(AlignmentError::SideFull {
+                master: master.into(),
+                side,
+                pins: names.len(),
+                capacity,
+            });
+        }
+        let locations = spread(&desired, low, high, spec.pitch)
+            .into_iter()
+            .map(|p| {
+                let p = p.round_dp(3);
+                match side {
+                    Side::Left => (Decimal::ZERO, p),
+                    Side::Right => (block.width, p),
+                    Side::Bottom => (p, Decimal::ZERO),
+                    Side::Top => (p, block.height),
+                }
+            })
+            .collect();
+        placements.push(PinPlacement {
+            pins: PinSelector::Names(names),
+            side,
+            layers: layers.clone(),
+            spread: PinSpread::Fixed(locations),
+            width: None,
+            depth: None,
+        });
+    }
+    Ok(placements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::macro_at;
+
+    fn port(instance: &str, port: &str) -> NetEnd {
+        NetEnd::Instance {
+            instance: instance.into(),
+            port: port.into(),
+        }
+    }
+
+    #[test]
+    fn pins_face_connected_neighbors() {
+        let top = |width, height| TopLevelConstraint {
+            width,
+            height,
+            left: 0.0,
+            bottom: 0.0,
+            right: 0.0,
+            top: 0.0,
+        };
+        // `u1` sits to the right of `u0`, mirrored so that its `cin` faces `u0` too
+        let parent = Floorplan {
+            top: top(100.0, 40.0),
+            hard_macros: vec![
+                macro_at("adder", "u0", 10.0, 10.0, "r0"),
+                macro_at("adder", "u1", 60.0, 10.0, "my"),
+            ],
+            obstructs: vec![],
+        };
+        let ports = ["a", "cin", "cout", "spare"].map(String::from).to_vec();
+        let blocks = vec![BlockOutline::new("adder", &top(20.0, 20.0), ports)];
+        let nets = vec![
+            ParentNet::new("carry", vec![port("u0", "cout"), port("u1", "cin")]),
+            ParentNet::new("a0", vec![NetEnd::Boundary(Side::Bottom), port("u0", "a")]),
+            ParentNet::new("a1", vec![NetEnd::Boundary(Side::Bottom), port("u1", "a")]),
+        ];
+        let spec = AlignmentSpec {
+            horizontal_layers: vec!["met3".into()],
+            vertical_layers: vec!["met4".into()],
+            pitch: Decimal::ONE,
+            margin: Decimal::TWO,
+            unconnected_side: Side::Top,
+        };
+        let plan = align_block_pins(&parent, &nets, &blocks, "adder", &spec).unwrap();
+        let sides: Vec<(Side, PinSelector)> =
+            plan.iter().map(|p| (p.side, p.pins.clone())).collect();
+        let names = |n: &[&str]| PinSelector::Names(n.iter().map(|s| s.to_string()).collect());
+        assert_eq!(
+            sides,
+            vec![
+                (Side::Right, names(&["cin", "cout"])),
+                (Side::Top, names(&["spare"])),
+                (Side::Bottom, names(&["a"])),
+            ]
+        );
+        assert_eq!(
+            plan[0].spread,
+            PinSpread::Fixed(vec![
+                (Decimal::from(20), Decimal::from(10)),
+                (Decimal::from(20), Decimal::from(11)),
+            ])
+        );
+
+        let full = AlignmentSpec {
+            margin: Decimal::from(10),
+            ..spec
+        };
+        assert!(matches!(
+            align_block_pins(&parent, &nets, &blocks, "adder", &full),
+            Err(AlignmentError::SideFull {
+                side: Side::Right,
+                pins: 2,
+                capacity: 1,
+                ..
+            })
+        ));
+    }
+}
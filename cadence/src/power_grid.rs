@@ -0,0 +1,453 @@
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use crate::qor::PowerSummary;
+use crate::tcl::{Command, Script, Word, list};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Horizontal,
+    Vertical,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Horizontal => write!(f, "horizontal"),
+            Direction::Vertical => write!(f, "vertical"),
+        }
+    }
+}
+
+/// A ring of power nets around the core or around each hard macro; all lengths in microns
+#[derive(Debug, Clone)]
+pub struct Ring {
+    /// Layer of the bottom and top sides
+    pub horizontal_layer: String,
+    /// Layer of the left and right sides
+    pub vertical_layer: String,
+    pub width: Decimal,
+    pub spacing: Decimal,
+    /// Distance from the core or macro boundary
+    pub offset: Decimal,
+}
+
+/// Rails along the power pins of the standard cell rows
+#[derive(Debug, Clone)]
+pub struct Followpins {
+    pub layer: String,
+    pub width: Decimal,
+}
+
+/// Sets of parallel power stripes on one layer; all lengths in microns
+#[derive(Debug, Clone)]
+pub struct Stripes {
+    pub layer: String,
+    pub direction: Direction,
+    pub width: Decimal,
+    /// Spacing between the stripes of different nets within a set
+    pub spacing: Decimal,
+    /// Distance between consecutive sets of stripes
+    pub pitch: Decimal,
+    /// Distance of the first set from the lower left corner of the core
+    pub offset: Decimal,
+    /// Lowest layer the stacked vias dropped from these stripes reach
+    pub via_bottom_layer: String,
+    /// Spacing kept between the stripes and hard macros
+    pub spacing_from_block: Decimal,
+    pub trim_antenna: bool,
+    /// Creates power pins on these stripes, for the abstract of the block
+    pub create_pins: bool,
+}
+
+/// Power grid of a block, from the core ring down to the standard cell rails
+#[derive(Debug, Clone)]
+pub struct PowerGrid {
+    pub nets: Vec<String>,
+    pub core_ring: Option<Ring>,
+    /// Ring placed around each hard macro
+    pub block_ring: Option<Ring>,
+    pub followpins: Option<Followpins>,
+    /// Stripes from the lowest to the highest layer
+    pub stripes: Vec<Stripes>,
+}
+
+impl PowerGrid {
+    pub fn new(nets: &[&str]) -> Self {
+        PowerGrid {
+            nets: nets.iter().map(|n| n.to_string()).collect(),
+            core_ring: None,
+            block_ring: None,
+            followpins: None,
+            stripes: Vec::new(),
+        }
+    }
+
+    /// Highest layer carrying stripes
+    pub fn top_layer(&self) -> Option<&str> {
+        self.stripes.last().map(|s| s.layer.as_str())
+    }
+
+    fn ring(&self, ring: &Ring, kind: &str) -> Command {
+        let layers = format!(
+            "{{top {h} bottom {h} left {v} right {v}}}",
+            h = ring.horizontal_layer,
+            v = ring.vertical_layer
+        );
+        let mut command = Command::new("add_rings")
+            .opt("nets", list(&self.nets))
+            .opt("type", kind);
+        if kind == "block_rings" {
+            command = command.opt("around", "each_block");
+        }
+        command
+            .opt("layer", Word::raw(layers))
+            .opt("width", ring.width)
+            .opt("spacing", ring.spacing)
+            .opt("offset", ring.offset)
+    }
+
+    /// Renders the `add_rings`, `route_special` and `add_stripes` commands building the grid
+    pub fn to_tcl(&self) -> String {
+        let mut script = Script::new();
+        if let Some(ring) = &self.core_ring {
+            script.command(self.ring(ring, "core_rings"));
+        }
+        if let Some(ring) = &self.block_ring {
+            script.command(self.ring(ring, "block_rings"));
+        }
+        if let Some(rails) = &self.followpins {
+            script.command(
+                Command::new("route_special")
+                    .opt("connect", "core_pin")
+                    .opt("nets", list(&self.nets))
+                    .opt("core_pin_layer", &rails.layer)
+                    .opt("core_pin_width", rails.width)
+                    .opt("allow_layer_change", false),
+            );
+        }
+
+        for stripes in &self.stripes {
+            // Lower left corner of the core, along the axis the stripes are stepped on
+            let axis = match stripes.direction {
+                Direction::Vertical => 0,
+                Direction::Horizontal => 1,
+            };
+            let start = format!(
+                "[expr [lindex [lindex [get_db designs .core_bbox] 0] {axis}] + {}]",
+                stripes.offset
+            );
+            script
+                .comment(&format!("Power stripes on {}", stripes.layer))
+                .set_db("add_stripes_stacked_via_top_layer", &stripes.layer)
+                .set_db(
+                    "add_stripes_stacked_via_bottom_layer",
+                    &stripes.via_bottom_layer,
+                );
+            if stripes.trim_antenna {
+                script.set_db("add_stripes_trim_antenna_back_to_shape", list(["stripe"]));
+            }
+            script
+                .set_db("add_stripes_spacing_from_block", stripes.spacing_from_block)
+                .command(
+                    Command::new("add_stripes")
+                        .opt("nets", list(&self.nets))
+                        .opt("layer", &stripes.layer)
+                        .opt("direction", stripes.direction.to_string())
+                        .opt("width", stripes.width)
+                        .opt("spacing", stripes.spacing)
+                        .opt("set_to_set_distance", stripes.pitch)
+                        .opt("area", Word::raw("[get_db designs .core_bbox]"))
+                        .opt("start", Word::raw(start))
+                        .opt("create_pins", stripes.create_pins)
+                        .opt("switch_layer_over_obs", false)
+                        .opt("block_ring_top_layer_limit", &stripes.layer)
+                        .opt("block_ring_bottom_layer_limit", &stripes.via_bottom_layer)
+                        .opt("pad_core_ring_top_layer_limit", &stripes.layer)
+                        .opt(
+                            "pad_core_ring_bottom_layer_limit",
+                            &stripes.via_bottom_layer,
+                        ),
+                );
+        }
+        script.to_string()
+    }
+}
+
+/// Design rules and electrical data of a routing layer
+#[derive(Debug, Clone)]
+pub struct MetalLayer {
+    pub name: String,
+    /// Minimum wire width, in microns
+    pub min_width: Decimal,
+    /// Maximum wire width, in microns, for layers that limit it
+    pub max_width: Option<Decimal>,
+    /// Minimum spacing between wires, in microns
+    pub min_spacing: Decimal,
+    /// Sheet resistance, in ohms per square
+    pub sheet_resistance: Decimal,
+}
+
+/// What the stripes of a layer must achieve, for [`Stripes::size_for`]
+#[derive(Debug, Clone)]
+pub struct GridTarget {
+    /// Fraction of the layer area covered by the stripes of all nets
+    pub density: Decimal,
+    /// Current drawn by the block, in mA
+    pub current: Decimal,
+    /// Allowed IR drop along the stripes, in mV
+    pub max_ir_drop: Decimal,
+}
+
+impl GridTarget {
+    /// Estimates the block current from the total power of a power report, drawn from a
+    /// `supply` in V
+    pub fn from_power(
+        power: &PowerSummary,
+        supply: Decimal,
+        density: Decimal,
+        max_ir_drop: Decimal,
+    ) -> Result<Self, SizingError> {
+        if supply <= Decimal::ZERO {
+            return Err(SizingError::InvalidTarget(format!(
+                "supply voltage {supply} must be positive"
+            )));
+        }
+        Ok(GridTarget {
+            density,
+            current: power.total * Decimal::ONE_THOUSAND / supply,
+            max_ir_drop,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SizingError {
+    /// The target is not a density between 0 and 1 or a positive IR drop
+    InvalidTarget(String),
+    /// Meeting the IR drop needs more of the layer than the stripes can cover
+    DensityTooHigh { layer: String, required: Decimal },
+    /// The stripes of a set, with their spacing, do not fit in the pitch
+    SetTooWide { layer: String, pitch: Decimal },
+    /// The stripe spacing is below the minimum spacing of the layer
+    SpacingTooSmall { layer: String, spacing: Decimal },
+}
+
+impl fmt::Display for SizingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SizingError::InvalidTarget(message) => write!(f, "invalid grid target: {message}"),
+            SizingError::DensityTooHigh { layer, required } => write!(
+                f,
+                "{layer} stripes need a density of {required} to meet the IR drop target"
+            ),
+            SizingError::SetTooWide { layer, pitch } => {
+                write!(f, "{layer} stripe sets do not fit in a pitch of {pitch}")
+            }
+            SizingError::SpacingTooSmall { layer, spacing } => {
+                write!(
+                    f,
+                    "{layer} stripe spacing {spacing} is below the minimum spacing"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SizingError {}
+
+/// Largest density the stripes of one layer may take, leaving room for signal routing
+const MAX_DENSITY: Decimal = Decimal::from_parts(5, 0, 0, false, 1);
+
+impl Stripes {
+    /// Sets the width and pitch of the stripes so that they cover `target.density` of the
+    /// layer, or more if needed to keep the IR drop within budget. The current pitch is kept
+    /// as far as the width rules of `layer` allow. `length` is the length of the stripes and
+    /// `span` the core dimension they are stepped across, in microns.
+    pub fn size_for(
+        &mut self,
+        layer: &MetalLayer,
+        length: Decimal,
+        span: Decimal,
+        nets: usize,
+        target: &GridTarget,
+    ) -> Result<(), SizingError> {
+        if target.density <= Decimal::ZERO || target.density > Decimal::ONE {
+            return Err(SizingError::InvalidTarget(format!(
+                "density {} is not in (0, 1]",
+                target.density
+            )));
+        }
+        if nets == 0 {
+            return Err(SizingError::InvalidTarget(
+                "the grid carries no nets".into(),
+            ));
+        }
+        if target.max_ir_drop <= Decimal::ZERO || span <= Decimal::ZERO {
+            return Err(SizingError::InvalidTarget(
+                "IR drop budget and core span must be positive".into(),
+            ));
+        }
+        if self.spacing < layer.min_spacing {
+            return Err(SizingError::SpacingTooSmall {
+                layer: self.layer.clone(),
+                spacing: self.spacing,
+            });
+        }
+
+        // Each stripe carries its share of the current, fed from one end with the load spread
+        // along it, so its worst drop is I_stripe * R_stripe / 2. With density
+        // d = nets * width / pitch this gives a drop of I * Rs * length * nets / (2 * span * d)
+        // whatever the width.
+        let nets = Decimal::from(nets as u64);
+        let ir_density = target.current * layer.sheet_resistance * length * nets
+            / (Decimal::TWO * span * target.max_ir_drop);
+        let density = target.density.max(ir_density);
+        if density > MAX_DENSITY {
+            return Err(SizingError::DensityTooHigh {
+                layer: self.layer.clone(),
+                required: density.round_dp(3),
+            });
+        }
+
+        let mut width = density * self.pitch / nets;
+        if width < layer.min_width {
+            width = layer.min_width;
+        }
+        if let Some(max) = layer.max_width
+            && width > max
+        {
+            width = max;
+        }
+        let width = width.round_dp(2).max(layer.min_width);
+        let pitch = (nets * width / density).round_dp(2);
+        if nets * width + (nets - Decimal::ONE) * self.spacing + layer.min_spacing > pitch {
+            return Err(SizingError::SetTooWide {
+                layer: self.layer.clone(),
+                pitch,
+            });
+        }
+        self.width = width;
+        self.pitch = pitch;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qor::PowerUnit;
+    use crate::tcl::check_syntax;
+
+    #[test]
+    fn renders_rings_rails_and_stripes() {
+        let mut grid = PowerGrid::new(&["VDD", "VSS"]);
+        grid.core_ring = Some(Ring {
+            horizontal_layer: "met5".into(),
+            vertical_layer: "met4".into(),
+            width: Decimal::new(2, 0),
+            spacing: Decimal::new(1, 0),
+            offset: Decimal::new(1, 0),
+        });
+        grid.followpins = Some(Followpins {
+            layer: "met1".into(),
+            width: Decimal::new(48, 2),
+        });
+        grid.stripes.push(Stripes {
+            layer: "met4".into(),
+            direction: Direction::Vertical,
+            width: Decimal::new(186, 2),
+            spacing: Decimal::new(366, 2),
+            pitch: Decimal::new(7590, 2),
+            offset: Decimal::new(735, 2),
+            via_bottom_layer: "met1".into(),
+            spacing_from_block: Decimal::new(2, 0),
+            trim_antenna: true,
+            create_pins: false,
+        });
+
+        let tcl = grid.to_tcl();
+        check_syntax(&tcl).unwrap();
+        assert!(tcl.contains(
+            "add_rings -nets [list VDD VSS] -type core_rings -layer {top met5 bottom met5 left met4 right met4}"
+        ));
+        assert!(tcl.contains("route_special -connect core_pin"));
+        assert!(tcl.contains("-set_to_set_distance 75.90"));
+        assert!(tcl.contains("[lindex [lindex [get_db designs .core_bbox] 0] 0] + 7.35]"));
+        assert_eq!(grid.top_layer(), Some("met4"));
+    }
+
+    #[test]
+    fn sizes_stripes_for_density_and_ir_drop() {
+        let met4 = MetalLayer {
+            name: "met4".into(),
+            min_width: Decimal::new(3, 1),
+            max_width: Some(Decimal::new(5, 0)),
+            min_spacing: Decimal::new(3, 1),
+            sheet_resistance: Decimal::new(47, 3),
+        };
+        let mut stripes = Stripes {
+            layer: "met4".into(),
+            direction: Direction::Vertical,
+            width: Decimal::new(186, 2),
+            spacing: Decimal::new(366, 2),
+            pitch: Decimal::new(50, 0),
+            offset: Decimal::new(735, 2),
+            via_bottom_layer: "met1".into(),
+            spacing_from_block: Decimal::new(2, 0),
+            trim_antenna: true,
+            create_pins: false,
+        };
+        let light = GridTarget {
+            density: Decimal::new(1, 1),
+            current: Decimal::ONE,
+            max_ir_drop: Decimal::new(10, 0),
+        };
+        let (length, span) = (Decimal::new(200, 0), Decimal::new(200, 0));
+        stripes.size_for(&met4, length, span, 2, &light).unwrap();
+        assert_eq!(stripes.width, Decimal::new(25, 1));
+        assert_eq!(stripes.pitch, Decimal::new(50, 0));
+
+        // 100 mA over 0.047 ohm/sq needs a density of 0.47 to stay within 10 mV
+        let heavy = GridTarget {
+            current: Decimal::new(100, 0),
+            ..light.clone()
+        };
+        stripes.size_for(&met4, length, span, 2, &heavy).unwrap();
+        assert_eq!(stripes.width, Decimal::new(5, 0));
+        assert_eq!(stripes.pitch, Decimal::new(2128, 2));
+
+        let too_heavy = GridTarget {
+            current: Decimal::new(200, 0),
+            ..light.clone()
+        };
+        assert!(matches!(
+            stripes.size_for(&met4, length, span, 2, &too_heavy),
+            Err(SizingError::DensityTooHigh { .. })
+        ));
+        assert!(matches!(
+            stripes.size_for(&met4, length, span, 0, &light),
+            Err(SizingError::InvalidTarget(_))
+        ));
+
+        let power = PowerSummary {
+            unit: PowerUnit::Milliwatt,
+            leakage: Decimal::ZERO,
+            internal: Decimal::ZERO,
+            switching: Decimal::ZERO,
+            total: Decimal::new(18, 3),
+        };
+        let target = GridTarget::from_power(
+            &power,
+            Decimal::new(18, 1),
+            light.density,
+            light.max_ir_drop,
+        );
+        assert_eq!(target.unwrap().current, Decimal::new(10, 0));
+        assert!(
+            GridTarget::from_power(&power, Decimal::ZERO, light.density, light.max_ir_drop)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,561 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use crate::tcl::{Command, Word};
+
+/// Suffix of the per-view timing summaries written by [`timing_summary_tcl`]
+pub const TIMING_SUMMARY_SUFFIX: &str = ".timing_summary.rpt";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QorError {
+    /// A report could not be read
+    Io { path: PathBuf, message: String },
+    /// A report does not have the expected layout; `line` is 0 when the problem is not on a
+    /// particular line
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for QorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QorError::Io { path, message } => write!(f, "{}: {message}", path.display()),
+            QorError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for QorError {}
+
+/// Worst and total negative slack of one timing check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slack {
+    pub wns: Decimal,
+    pub tns: Decimal,
+    pub violating_paths: u64,
+}
+
+/// Setup and hold slack of one analysis view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewTiming {
+    pub view: String,
+    pub setup: Option<Slack>,
+    pub hold: Option<Slack>,
+}
+
+/// One instance of the hierarchical area report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AreaEntry {
+    pub instance: String,
+    pub module: Option<String>,
+    /// Nesting level in the hierarchy, 0 for the top module
+    pub depth: usize,
+    pub cell_count: u64,
+    pub cell_area: Decimal,
+    pub net_area: Decimal,
+    pub total_area: Decimal,
+}
+
+/// Instance count and area of a cell or of a class of cells
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellUsage {
+    pub name: String,
+    pub instances: u64,
+    pub area: Decimal,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GateCount {
+    /// Usage of each library cell
+    pub cells: Vec<CellUsage>,
+    /// Usage by cell type, such as sequential, inverter or logic
+    pub types: Vec<CellUsage>,
+    pub total: Option<CellUsage>,
+}
+
+/// Unit a power report is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUnit {
+    Watt,
+    Milliwatt,
+    Microwatt,
+    Nanowatt,
+}
+
+impl PowerUnit {
+    fn parse(unit: &str) -> Option<Self> {
+        // Innovus prefixes the unit with a scale, as in `Power Units = 1mW`
+        Some(match unit.trim().trim_start_matches('1') {
+            "W" => PowerUnit::Watt,
+            "mW" => PowerUnit::Milliwatt,
+            "uW" => PowerUnit::Microwatt,
+            "nW" => PowerUnit::Nanowatt,
+            _ => return None,
+        })
+    }
+
+    /// Size of the unit, in watts
+    pub fn watts(self) -> Decimal {
+        match self {
+            PowerUnit::Watt => Decimal::ONE,
+            PowerUnit::Milliwatt => Decimal::new(1, 3),
+            PowerUnit::Microwatt => Decimal::new(1, 6),
+            PowerUnit::Nanowatt => Decimal::new(1, 9),
+        }
+    }
+}
+
+impl fmt::Display for PowerUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self {
+            PowerUnit::Watt => "W",
+            PowerUnit::Milliwatt => "mW",
+            PowerUnit::Microwatt => "uW",
+            PowerUnit::Nanowatt => "nW",
+        };
+        write!(f, "{unit}")
+    }
+}
+
+/// Power of the whole design, in watts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerSummary {
+    /// Unit of the report the values were converted from
+    pub unit: PowerUnit,
+    pub leakage: Decimal,
+    pub internal: Decimal,
+    pub switching: Decimal,
+    pub total: Decimal,
+}
+
+impl PowerSummary {
+    pub fn dynamic(&self) -> Decimal {
+        self.internal + self.switching
+    }
+}
+
+/// Quality of results of a module, extracted from the reports of a Genus or Innovus run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Qor {
+    pub timing: Vec<ViewTiming>,
+    pub area: Vec<AreaEntry>,
+    pub gates: Option<GateCount>,
+    pub power: Option<PowerSummary>,
+}
+
+impl Qor {
+    /// Reads the `{tag}_*` reports of `dir`; reports that were not written are left empty
+    pub fn read(dir: &Path, tag: &str) -> Result<Qor, QorError> {
+        let mut qor = Qor::default();
+        if let Some(text) = read_optional(&dir.join(format!("{tag}_area.rpt")))? {
+            qor.area = parse_area(&text).map_err(|e| e.at(dir.join(format!("{tag}_area.rpt"))))?;
+        }
+        if let Some(text) = read_optional(&dir.join(format!("{tag}_gates.rpt")))? {
+            qor.gates = Some(parse_gates(&text));
+        }
+        if let Some(text) = read_optional(&dir.join(format!("{tag}_power.rpt")))? {
+            qor.power =
+                Some(parse_power(&text).map_err(|e| e.at(dir.join(format!("{tag}_power.rpt"))))?);
+        }
+
+        let prefix = format!("{tag}_");
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Ok(qor);
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let Some(view) = name
+                .strip_prefix(&prefix)
+                .and_then(|n| n.strip_suffix(TIMING_SUMMARY_SUFFIX))
+            else {
+                continue;
+            };
+            let view = view.to_owned();
+            let text = read(&path)?;
+            let (setup, hold) = parse_timing_summary(&text).map_err(|e| e.at(path.clone()))?;
+            qor.timing.push(ViewTiming { view, setup, hold });
+        }
+        Ok(qor)
+    }
+
+    /// The worst setup slack over all views
+    pub fn worst_setup(&self) -> Option<&Slack> {
+        self.timing
+            .iter()
+            .filter_map(|t| t.setup.as_ref())
+            .min_by_key(|s| s.wns)
+    }
+
+    /// The worst hold slack over all views
+    pub fn worst_hold(&self) -> Option<&Slack> {
+        self.timing
+            .iter()
+            .filter_map(|t| t.hold.as_ref())
+            .min_by_key(|s| s.wns)
+    }
+
+    /// Area of the top module
+    pub fn total_area(&self) -> Option<Decimal> {
+        self.area.first().map(|a| a.total_area)
+    }
+}
+
+/// Writes a timing summary of every analysis view to `{dir}/{tag}_{view}.timing_summary.rpt`
+pub fn timing_summary_tcl(dir: &str, tag: &str) -> String {
+    let prefix = Word::from(Command::new("file").arg("join").arg(dir).arg(tag));
+    let report = Command::new("report_timing_summary")
+        .opt("views", Word::var("view"))
+        .raw(">")
+        .raw(format!("{prefix}_${{view}}{TIMING_SUMMARY_SUFFIX}"));
+    format!("foreach view [get_db analysis_views .name] {{\n    {report}\n}}")
+}
+
+/// Parse failure before the report path is known
+#[derive(Debug)]
+struct LineError {
+    line: usize,
+    message: String,
+}
+
+impl LineError {
+    fn at(self, path: PathBuf) -> QorError {
+        QorError::Parse {
+            path,
+            line: self.line,
+            message: self.message,
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<String, QorError> {
+    fs::read_to_string(path).map_err(|e| QorError::Io {
+        path: path.to_owned(),
+        message: e.to_string(),
+    })
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>, QorError> {
+    if path.exists() {
+        read(path).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Parses plain and scientific notation numbers
+fn number(s: &str) -> Option<Decimal> {
+    Decimal::from_str(s)
+        .or_else(|_| Decimal::from_scientific(s))
+        .ok()
+}
+
+fn is_rule(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && line.chars().all(|c| matches!(c, '-' | '=' | '+'))
+}
+
+/// Parses the setup and hold tables of a `report_timing_summary` report, using the `all`
+/// path group column
+fn parse_timing_summary(text: &str) -> Result<(Option<Slack>, Option<Slack>), LineError> {
+    let mut setup = None;
+    let mut hold = None;
+    let mut mode: Option<(bool, Option<Decimal>, Option<Decimal>)> = None;
+    for (i, line) in text.lines().enumerate() {
+        let cells: Vec<&str> = line
+            .trim()
+            .trim_matches('|')
+            .split('|')
+            .map(str::trim)
+            .collect();
+        if !line.trim_start().starts_with('|') || cells.len() < 2 {
+            continue;
+        }
+        let label = cells[0].trim_end_matches(':');
+        if label.ends_with("mode") {
+            mode = Some((label.starts_with("Setup"), None, None));
+            continue;
+        }
+        let Some((is_setup, wns, tns)) = mode.as_mut() else {
+            continue;
+        };
+        let value = cells[1];
+        let invalid = |what: &str| LineError {
+            line: i + 1,
+            message: format!("invalid {what} `{value}`"),
+        };
+        match label {
+            "WNS (ns)" => *wns = Some(number(value).ok_or_else(|| invalid("WNS"))?),
+            "TNS (ns)" => *tns = Some(number(value).ok_or_else(|| invalid("TNS"))?),
+            "Violating Paths" => {
+                let slack = Slack {
+                    wns: wns.unwrap_or_default(),
+                    tns: tns.unwrap_or_default(),
+                    violating_paths: value.parse().map_err(|_| invalid("path count"))?,
+                };
+                if *is_setup {
+                    setup = Some(slack);
+                } else {
+                    hold = Some(slack);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((setup, hold))
+}
+
+/// Parses a hierarchical `report_area` report; the indentation of an instance gives its depth
+fn parse_area(text: &str) -> Result<Vec<AreaEntry>, LineError> {
+    let mut entries = Vec::new();
+    let mut in_table = false;
+    let mut indent = None;
+    for (i, line) in text.lines().enumerate() {
+        if is_rule(line) {
+            in_table = true;
+            continue;
+        }
+        if !in_table || line.trim().is_empty() {
+            continue;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some(first_number) = words.iter().position(|w| number(w).is_some()) else {
+            continue;
+        };
+        let values: Vec<Decimal> = words[first_number..]
+            .iter()
+            .take(4)
+            .map_while(|w| number(w))
+            .collect();
+        let [count, cell_area, net_area, total_area] = values[..] else {
+            return Err(LineError {
+                line: i + 1,
+                message: "expected cell count, cell, net and total area".into(),
+            });
+        };
+        let leading = line.len() - line.trim_start().len();
+        let base = *indent.get_or_insert(leading);
+        entries.push(AreaEntry {
+            instance: words[0].to_owned(),
+            module: (first_number > 1).then(|| words[1].to_owned()),
+            depth: leading.saturating_sub(base) / 2,
+            cell_count: count.try_into().unwrap_or_default(),
+            cell_area,
+            net_area,
+            total_area,
+        });
+    }
+    Ok(entries)
+}
+
+/// Parses the per-cell and per-type tables of a `report_gates` report
+fn parse_gates(text: &str) -> GateCount {
+    let mut gates = GateCount::default();
+    let mut section = None;
+    for line in text.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.first().copied() {
+            Some("Gate") => section = Some(false),
+            Some("Type") => section = Some(true),
+            _ => {}
+        }
+        let Some(is_type) = section else {
+            continue;
+        };
+        let [name, instances, area, ..] = words[..] else {
+            continue;
+        };
+        let (Ok(instances), Some(area)) = (instances.parse(), number(area)) else {
+            continue;
+        };
+        let usage = CellUsage {
+            name: name.to_owned(),
+            instances,
+            area,
+        };
+        if name == "total" {
+            if !is_type {
+                gates.total = Some(usage);
+            }
+            section = None;
+        } else if is_type {
+            gates.types.push(usage);
+        } else {
+            gates.cells.push(usage);
+        }
+    }
+    gates
+}
+
+/// Parses the totals of a Genus (`Subtotal` row) or Innovus (`Total ... Power:` lines)
+/// `report_power` report, converted to watts from the unit given in its header
+fn parse_power(text: &str) -> Result<PowerSummary, LineError> {
+    let error = |line: usize, message: &str| LineError {
+        line,
+        message: message.into(),
+    };
+    let unit = text
+        .lines()
+        .find_map(|line| {
+            let (label, unit) = line.split_once([':', '='])?;
+            label
+                .trim_start_matches(['*', ' ', '\t'])
+                .starts_with("Power Unit")
+                .then(|| PowerUnit::parse(unit))
+        })
+        .ok_or_else(|| error(0, "no power unit"))?
+        .ok_or_else(|| error(0, "unknown power unit"))?;
+    let watts = |value: Decimal| value * unit.watts();
+
+    for (i, line) in text.lines().enumerate() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if let ["Subtotal", leakage, internal, switching, total, ..] = words[..] {
+            let value = |w: &str| {
+                number(w)
+                    .map(watts)
+                    .ok_or_else(|| error(i + 1, "invalid power"))
+            };
+            return Ok(PowerSummary {
+                unit,
+                leakage: value(leakage)?,
+                internal: value(internal)?,
+                switching: value(switching)?,
+                total: value(total)?,
+            });
+        }
+    }
+
+    let total = |kind: &str| {
+        text.lines()
+            .find_map(|line| {
+                let value = line.trim().strip_prefix(&format!("Total {kind}Power:"))?;
+                number(value.split_whitespace().next()?)
+            })
+            .map(watts)
+            .ok_or_else(|| error(0, &format!("no `Total {kind}Power:` line")))
+    };
+    Ok(PowerSummary {
+        unit,
+        leakage: total("Leakage ")?,
+        internal: total("Internal ")?,
+        switching: total("Switching ")?,
+        total: total("")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_timing_and_area_reports() {
+        let timing = r#"
++--------------------+---------+---------+---------+
+|     Setup mode     |   all   | reg2reg | default |
++--------------------+---------+---------+---------+
+|           WNS (ns):| -0.125  |  0.045  | -0.125  |
+|           TNS (ns):| -1.500  |  0.000  | -1.500  |
+|    Violating Paths:|   12    |    0    |   12    |
+|          All Paths:|   345   |   200   |   145   |
++--------------------+---------+---------+---------+
+
++--------------------+---------+---------+---------+
+|     Hold mode      |   all   | reg2reg | default |
++--------------------+---------+---------+---------+
+|           WNS (ns):|  0.020  |  0.020  |  0.310  |
+|           TNS (ns):|  0.000  |  0.000  |  0.000  |
+|    Violating Paths:|    0    |    0    |    0    |
++--------------------+---------+---------+---------+
+"#;
+        let (setup, hold) = parse_timing_summary(timing).unwrap();
+        assert_eq!(
+            setup,
+            Some(Slack {
+                wns: Decimal::new(-125, 3),
+                tns: Decimal::new(-15, 1),
+                violating_paths: 12,
+            })
+        );
+        assert_eq!(hold.unwrap().wns, Decimal::new(2, 2));
+
+        let area = r#"
+  Instance    Module   Cell Count  Cell Area  Net Area   Total Area  Wireload
+-------------------------------------------------------------------------------
+decoder                       120    450.250     0.000      450.250  <none> (D)
+  u_sub       sub_block        20     80.000     0.000       80.000  <none> (D)
+"#;
+        let area = parse_area(area).unwrap();
+        assert_eq!(area.len(), 2);
+        assert_eq!(area[0].module, None);
+        assert_eq!(area[1].module.as_deref(), Some("sub_block"));
+        assert_eq!(area[1].depth, 1);
+        assert_eq!(area[0].total_area, Decimal::new(45025, 2));
+    }
+
+    #[test]
+    fn parses_gate_and_power_reports() {
+        let gates = r#"
+  Gate                    Instances   Area     Library
+--------------------------------------------------------------
+sky130_fd_sc_hd__and2_1       3       18.768  sky130_fd_sc_hd__ss_100C_1v60
+sky130_fd_sc_hd__dfxtp_1      2       40.038  sky130_fd_sc_hd__ss_100C_1v60
+--------------------------------------------------------------
+total                         5       58.806
+
+       Type        Instances   Area   Area %
+----------------------------------------------
+sequential             2      40.038   68.1
+logic                  3      18.768   31.9
+----------------------------------------------
+total                  5      58.806  100.0
+"#;
+        let gates = parse_gates(gates);
+        assert_eq!(gates.cells.len(), 2);
+        assert_eq!(gates.types[0].name, "sequential");
+        assert_eq!(gates.total.unwrap().instances, 5);
+
+        let genus = r#"
+Power Unit: W
+  Category         Leakage     Internal    Switching        Total    Row%
+--------------------------------------------------------------------------
+  Subtotal     1.50000e-09  2.00000e-06  1.00000e-06  3.00150e-06 100.00%
+"#;
+        let power = parse_power(genus).unwrap();
+        assert_eq!(power.leakage, Decimal::new(15, 10));
+        assert_eq!(power.dynamic(), Decimal::new(3, 6));
+
+        let innovus = r#"
+*	Power Units = 1mW
+Total Internal Power:        0.20000000 	   66.6667%
+Total Switching Power:       0.09999000 	   33.3300%
+Total Leakage Power:         0.00001000 	    0.0033%
+Total Power:                 0.30000000
+"#;
+        let power = parse_power(innovus).unwrap();
+        assert_eq!(power.unit, PowerUnit::Milliwatt);
+        assert_eq!(power.switching, Decimal::new(9999, 8));
+        assert_eq!(power.total, Decimal::new(3, 4));
+        assert!(parse_power("Total Power: 0.3").is_err());
+    }
+
+    #[test]
+    fn timing_summary_quotes_report_dir() {
+        let tcl = timing_summary_tcl("my reports", "final");
+        assert_eq!(crate::tcl::check_syntax(&tcl), Ok(()));
+        assert!(tcl.contains("> [file join {my reports} final]_${view}.timing_summary.rpt"));
+    }
+}
@@ -0,0 +1,412 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use crate::qor::Qor;
+
+const MANIFEST_HEADER: &str = "module\twns\ttns\tarea\tpower\tdrc";
+
+/// Headline QoR metrics of one module of a run; power is in watts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModuleQor {
+    pub module: String,
+    pub wns: Option<Decimal>,
+    pub tns: Option<Decimal>,
+    pub area: Option<Decimal>,
+    pub power: Option<Decimal>,
+    /// Number of DRC violations reported by sign-off verification
+    pub drc: Option<u64>,
+}
+
+impl ModuleQor {
+    /// Summarizes `qor` with the worst setup slack over all views and the total power
+    pub fn from_qor(module: impl Into<String>, qor: &Qor, drc: Option<u64>) -> Self {
+        let setup = qor.worst_setup();
+        ModuleQor {
+            module: module.into(),
+            wns: setup.map(|s| s.wns),
+            tns: setup.map(|s| s.tns),
+            area: qor.total_area(),
+            power: qor.power.as_ref().map(|p| p.total),
+            drc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestError {
+    Io(String),
+    /// A line of the manifest does not have the expected columns
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Io(message) => write!(f, "{message}"),
+            ManifestError::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+/// The QoR of every module of a run, stored as a tab separated file so that a later run can
+/// be compared against it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunManifest {
+    pub modules: Vec<ModuleQor>,
+}
+
+fn optional<T: fmt::Display>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "-".to_owned(), |v| v.to_string())
+}
+
+impl RunManifest {
+    pub fn get(&self, module: &str) -> Option<&ModuleQor> {
+        self.modules.iter().find(|m| m.module == module)
+    }
+
+    pub fn parse(text: &str) -> Result<RunManifest, ManifestError> {
+        let mut manifest = RunManifest::default();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line == MANIFEST_HEADER {
+                continue;
+            }
+            let error = |message: String| ManifestError::Parse {
+                line: i + 1,
+                message,
+            };
+            let [module, wns, tns, area, power, drc] = line.split('\t').collect::<Vec<_>>()[..]
+            else {
+                return Err(error("expected 6 tab separated columns".into()));
+            };
+            fn value<T: FromStr>(column: &str) -> Result<Option<T>, String> {
+                if column == "-" {
+                    return Ok(None);
+                }
+                column
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("invalid value `{column}`"))
+            }
+            manifest.modules.push(ModuleQor {
+                module: module.to_owned(),
+                wns: value(wns).map_err(error)?,
+                tns: value(tns).map_err(error)?,
+                area: value(area).map_err(error)?,
+                power: value(power).map_err(error)?,
+                drc: value(drc).map_err(error)?,
+            });
+        }
+        Ok(manifest)
+    }
+
+    pub fn read(path: &Path) -> Result<RunManifest, ManifestError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| ManifestError::Io(format!("{}: {e}", path.display())))?;
+        RunManifest::parse(&text)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), ManifestError> {
+        fs::write(path, self.to_string())
+            .map_err(|e| ManifestError::Io(format!("{}: {e}", path.display())))
+    }
+}
+
+impl fmt::Display for RunManifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{MANIFEST_HEADER}")?;
+        for m in &self.modules {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                m.module,
+                optional(&m.wns),
+                optional(&m.tns),
+                optional(&m.area),
+                optional(&m.power),
+                optional(&m.drc)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// How much worse than the baseline a metric may get before the run counts as regressed
+#[derive(Debug, Clone)]
+pub struct Thresholds {
+    /// Allowed decrease of the worst negative slack, in ns
+    pub wns: Decimal,
+    /// Allowed decrease of the total negative slack, in ns
+    pub tns: Decimal,
+    /// Allowed area increase, in percent of the baseline
+    pub area_percent: Decimal,
+    /// Allowed power increase, in percent of the baseline
+    pub power_percent: Decimal,
+    /// Allowed number of additional DRC violations
+    pub drc: u64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            wns: Decimal::new(1, 2),
+            tns: Decimal::new(1, 1),
+            area_percent: Decimal::ONE,
+            power_percent: Decimal::TWO,
+            drc: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Wns,
+    Tns,
+    Area,
+    Power,
+    Drc,
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Metric::Wns => "WNS",
+            Metric::Tns => "TNS",
+            Metric::Area => "area",
+            Metric::Power => "power",
+            Metric::Drc => "DRC",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Change of one metric of a module between the baseline and the current run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricDelta {
+    pub metric: Metric,
+    pub baseline: Decimal,
+    pub current: Decimal,
+    pub regressed: bool,
+}
+
+impl MetricDelta {
+    pub fn delta(&self) -> Decimal {
+        self.current - self.baseline
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleComparison {
+    pub module: String,
+    pub deltas: Vec<MetricDelta>,
+    /// The module is in the baseline but was not found in the current run
+    pub missing: bool,
+    /// The module is in the current run but not in the baseline, so there is nothing to
+    /// compare it against
+    pub added: bool,
+}
+
+impl ModuleComparison {
+    pub fn regressed(&self) -> bool {
+        self.missing || self.deltas.iter().any(|d| d.regressed)
+    }
+}
+
+/// Result of comparing a run against a baseline manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    pub modules: Vec<ModuleComparison>,
+}
+
+impl Comparison {
+    pub fn regressed(&self) -> bool {
+        self.modules.iter().any(|m| m.regressed())
+    }
+
+    pub fn regressions(&self) -> impl Iterator<Item = &ModuleComparison> {
+        self.modules.iter().filter(|m| m.regressed())
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for m in &self.modules {
+            if m.missing {
+                writeln!(f, "{}: missing from the current run  REGRESSED", m.module)?;
+                continue;
+            }
+            if m.added {
+                writeln!(f, "{}: not in the baseline", m.module)?;
+                continue;
+            }
+            for d in &m.deltas {
+                writeln!(
+                    f,
+                    "{} {}: {} -> {} ({:+}){}",
+                    m.module,
+                    d.metric,
+                    d.baseline,
+                    d.current,
+                    d.delta(),
+                    if d.regressed { "  REGRESSED" } else { "" }
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares `current` against `baseline` module by module; metrics missing from either run
+/// are skipped and modules only in the current run are listed without deltas
+pub fn compare(
+    baseline: &RunManifest,
+    current: &RunManifest,
+    thresholds: &Thresholds,
+) -> Comparison {
+    let percent =
+        |baseline: Decimal, allowed: Decimal| baseline.abs() * allowed / Decimal::ONE_HUNDRED;
+    let modules = baseline.modules.iter().map(|base| {
+        let Some(cur) = current.get(&base.module) else {
+            return ModuleComparison {
+                module: base.module.clone(),
+                deltas: Vec::new(),
+                missing: true,
+                added: false,
+            };
+        };
+        // (metric, baseline, current, largest allowed worsening, higher is worse)
+        let metrics = [
+            (Metric::Wns, base.wns, cur.wns, thresholds.wns, false),
+            (Metric::Tns, base.tns, cur.tns, thresholds.tns, false),
+            (
+                Metric::Area,
+                base.area,
+                cur.area,
+                base.area
+                    .map_or(Decimal::ZERO, |a| percent(a, thresholds.area_percent)),
+                true,
+            ),
+            (
+                Metric::Power,
+                base.power,
+                cur.power,
+                base.power
+                    .map_or(Decimal::ZERO, |p| percent(p, thresholds.power_percent)),
+                true,
+            ),
+            (
+                Metric::Drc,
+                base.drc.map(Decimal::from),
+                cur.drc.map(Decimal::from),
+                Decimal::from(thresholds.drc),
+                true,
+            ),
+        ];
+        let deltas = metrics
+            .into_iter()
+            .filter_map(|(metric, baseline, current, allowed, higher_is_worse)| {
+                let (baseline, current) = (baseline?, current?);
+                let worsening = if higher_is_worse {
+                    current - baseline
+                } else {
+                    baseline - current
+                };
+                Some(MetricDelta {
+                    metric,
+                    baseline,
+                    current,
+                    regressed: worsening > allowed,
+                })
+            })
+            .collect();
+        ModuleComparison {
+            module: base.module.clone(),
+            deltas,
+            missing: false,
+            added: false,
+        }
+    });
+    let added = current
+        .modules
+        .iter()
+        .filter(|cur| baseline.get(&cur.module).is_none())
+        .map(|cur| ModuleComparison {
+            module: cur.module.clone(),
+            deltas: Vec::new(),
+            missing: false,
+            added: true,
+        });
+    Comparison {
+        modules: modules.chain(added).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_regressions_beyond_thresholds() {
+        let baseline = RunManifest {
+            modules: vec![
+                ModuleQor {
+                    module: "decoder".into(),
+                    wns: Some(Decimal::new(5, 2)),
+                    tns: Some(Decimal::ZERO),
+                    area: Some(Decimal::new(1000, 0)),
+                    power: Some(Decimal::new(2, 3)),
+                    drc: Some(0),
+                },
+                ModuleQor {
+                    module: "sub".into(),
+                    area: Some(Decimal::new(100, 0)),
+                    ..Default::default()
+                },
+            ],
+        };
+        let parsed = RunManifest::parse(&baseline.to_string()).unwrap();
+        assert_eq!(parsed, baseline);
+
+        let mut current = baseline.clone();
+        current.modules[0].wns = Some(Decimal::new(-2, 2));
+        current.modules[0].area = Some(Decimal::new(1005, 0));
+        current.modules[0].drc = Some(3);
+        let comparison = compare(&baseline, &current, &Thresholds::default());
+        let regressed: Vec<Metric> = comparison.modules[0]
+            .deltas
+            .iter()
+            .filter(|d| d.regressed)
+            .map(|d| d.metric)
+            .collect();
+        assert_eq!(regressed, vec![Metric::Wns, Metric::Drc]);
+        assert!(!comparison.modules[1].regressed());
+
+        current.modules.pop();
+        let comparison = compare(&baseline, &current, &Thresholds::default());
+        assert!(comparison.modules[1].missing);
+        assert_eq!(comparison.regressions().count(), 2);
+
+        current.modules.push(ModuleQor {
+            module: "new_sub".into(),
+            ..Default::default()
+        });
+        let comparison = compare(&baseline, &current, &Thresholds::default());
+        let added = &comparison.modules[2];
+        assert!(added.added && !added.regressed());
+        assert!(
+            comparison
+                .to_string()
+                .contains("new_sub: not in the baseline")
+        );
+    }
+}
@@ -0,0 +1,630 @@
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use crate::tcl::{Command, Script, Word, quote};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdcError {
+    /// No clock with this name is defined
+    UnknownClock(String),
+    /// No port matches this name
+    UnknownPort(String),
+    /// The period of a clock is not positive
+    InvalidPeriod { clock: String, period: Decimal },
+    /// A generated clock has neither a non-zero divide_by nor a non-zero multiply_by
+    MissingRatio(String),
+    /// The file is not lexically valid TCL
+    Syntax { line: usize, message: String },
+    /// A supported command has arguments that cannot be interpreted
+    Invalid {
+        line: usize,
+        command: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SdcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdcError::UnknownClock(name) => write!(f, "no clock named {name}"),
+            SdcError::UnknownPort(name) => write!(f, "no port matches {name}"),
+            SdcError::InvalidPeriod { clock, period } => {
+                write!(f, "clock {clock} has a non-positive period {period}")
+            }
+            SdcError::MissingRatio(name) => write!(
+                f,
+                "generated clock {name} needs a non-zero divide_by or multiply_by"
+            ),
+            SdcError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            SdcError::Invalid {
+                line,
+                command,
+                message,
+            } => write!(f, "line {line}: {command}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SdcError {}
+
+/// Formats object names as a single SDC object list word
+fn objects(names: &[String]) -> Word {
+    match names {
+        [name] => Word::from(name),
+        _ => Word::raw(format!(
+            "{{{}}}",
+            names.iter().map(|n| quote(n)).collect::<Vec<_>>().join(" ")
+        )),
+    }
+}
+
+/// Matches `pattern` against `name`, where `*` matches any sequence of characters
+fn glob_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=name.len())
+                .filter(|&i| name.is_char_boundary(i))
+                .any(|i| glob_match(rest, &name[i..]))
+        }
+    }
+}
+
+/// Returns true if the port reference `name` matches one of `ports`; bits of a bus match
+/// the bus name and the other way round
+fn port_exists(name: &str, ports: &[String]) -> bool {
+    let bus = |n: &str| n.split('[').next().unwrap_or(n).to_string();
+    ports
+        .iter()
+        .any(|p| glob_match(name, p) || bus(name) == bus(p))
+}
+
+/// Ports an I/O constraint applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortGroup {
+    AllInputs,
+    AllOutputs,
+    /// All inputs except the listed ports, typically the clock ports
+    InputsExcept(Vec<String>),
+    Ports(Vec<String>),
+}
+
+impl PortGroup {
+    fn ports(&self) -> &[String] {
+        match self {
+            PortGroup::AllInputs | PortGroup::AllOutputs => &[],
+            PortGroup::InputsExcept(ports) | PortGroup::Ports(ports) => ports,
+        }
+    }
+
+    fn word(&self) -> Word {
+        match self {
+            PortGroup::AllInputs => Command::new("all_inputs").into(),
+            PortGroup::AllOutputs => Command::new("all_outputs").into(),
+            PortGroup::InputsExcept(ports) => Command::new("remove_from_collection")
+                .arg(Command::new("all_inputs"))
+                .arg(Command::new("get_ports").arg(objects(ports)))
+                .into(),
+            PortGroup::Ports(ports) => Command::new("get_ports").arg(objects(ports)).into(),
+        }
+    }
+}
+
+/// Start, through or end points of a timing exception
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathPoints {
+    Clocks(Vec<String>),
+    Ports(Vec<String>),
+    Pins(Vec<String>),
+    Cells(Vec<String>),
+}
+
+impl PathPoints {
+    fn word(&self) -> Word {
+        let (command, names) = match self {
+            PathPoints::Clocks(names) => ("get_clocks", names),
+            PathPoints::Ports(names) => ("get_ports", names),
+            PathPoints::Pins(names) => ("get_pins", names),
+            PathPoints::Cells(names) => ("get_cells", names),
+        };
+        Command::new(command).arg(objects(names)).into()
+    }
+}
+
+/// A primary clock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clock {
+    pub name: String,
+    /// Port the clock is defined on, or `None` for a virtual clock
+    pub port: Option<String>,
+    pub period: Decimal,
+    /// Rising and falling edge times within the period
+    pub waveform: Option<(Decimal, Decimal)>,
+    pub uncertainty: Option<Decimal>,
+}
+
+impl Clock {
+    /// A clock named after the port it is defined on
+    pub fn on_port(port: &str, period: Decimal) -> Self {
+        Clock {
+            name: port.to_string(),
+            port: Some(port.to_string()),
+            period,
+            waveform: None,
+            uncertainty: None,
+        }
+    }
+
+    /// A clock that is not attached to any port, used as a reference for I/O delays
+    pub fn virtual_clock(name: &str, period: Decimal) -> Self {
+        Clock {
+            name: name.to_string(),
+            port: None,
+            period,
+            waveform: None,
+            uncertainty: None,
+        }
+    }
+
+    pub fn waveform(mut self, rise: Decimal, fall: Decimal) -> Self {
+        self.waveform = Some((rise, fall));
+        self
+    }
+
+    pub fn uncertainty(mut self, uncertainty: Decimal) -> Self {
+        self.uncertainty = Some(uncertainty);
+        self
+    }
+}
+
+/// A clock derived from a master clock by a divider or multiplier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedClock {
+    pub name: String,
+    /// Port or pin of the master clock the clock is derived from
+    pub source: PathPoints,
+    pub master: String,
+    /// Port or pin the clock is defined on
+    pub target: PathPoints,
+    pub divide_by: Option<u32>,
+    pub multiply_by: Option<u32>,
+}
+
+/// An input or output delay relative to a clock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IoDelay {
+    pub clock: String,
+    pub delay: Decimal,
+    pub ports: PortGroup,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Load {
+    pub capacitance: Decimal,
+    pub ports: PortGroup,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Exception {
+    pub from: Option<PathPoints>,
+    pub through: Option<PathPoints>,
+    pub to: Option<PathPoints>,
+}
+
+impl Exception {
+    fn apply(&self, mut command: Command) -> Command {
+        for (flag, points) in [
+            ("from", &self.from),
+            ("through", &self.through),
+            ("to", &self.to),
+        ] {
+            if let Some(points) = points {
+                command = command.opt(flag, points.word());
+            }
+        }
+        command
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MulticycleKind {
+    Setup,
+    Hold,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MulticyclePath {
+    pub cycles: u32,
+    pub kind: MulticycleKind,
+    pub path: Exception,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockRelation {
+    Asynchronous,
+    LogicallyExclusive,
+    PhysicallyExclusive,
+}
+
+/// Clocks whose groups are not timed against each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockGroups {
+    pub relation: ClockRelation,
+    pub groups: Vec<Vec<String>>,
+}
+
+/// Timing constraints of a module, rendered as SDC through [`fmt::Display`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Constraints {
+    pub clocks: Vec<Clock>,
+    pub generated_clocks: Vec<GeneratedClock>,
+    pub clock_groups: Vec<ClockGroups>,
+    pub input_delays: Vec<IoDelay>,
+    pub output_delays: Vec<IoDelay>,
+    pub loads: Vec<Load>,
+    pub false_paths: Vec<Exception>,
+    pub multicycle_paths: Vec<MulticyclePath>,
+}
+
+impl Constraints {
+    pub fn new() -> Self {
+        Constraints::default()
+    }
+
+    /// A single clock on `port` with the I/O of the module timed against it
+    pub fn single_clock(port: &str, period: Decimal) -> Self {
+        let mut constraints = Constraints::new();
+        constraints
+            .clock(Clock::on_port(port, period).uncertainty(Decimal::new(1, 2)))
+            .clock_group(ClockRelation::Asynchronous, vec![vec![port.to_string()]])
+            .load(Decimal::ONE, PortGroup::AllOutputs)
+            .input_delay(
+                port,
+                Decimal::ZERO,
+                PortGroup::InputsExcept(vec![port.to_string()]),
+            )
+            .output_delay(port, Decimal::ZERO, PortGroup::AllOutputs);
+        constraints
+    }
+
+    pub fn clock(&mut self, clock: Clock) -> &mut Self {
+        self.clocks.push(clock);
+        self
+    }
+
+    pub fn generated_clock(&mut self, clock: GeneratedClock) -> &mut Self {
+        self.generated_clocks.push(clock);
+        self
+    }
+
+    pub fn clock_group(&mut self, relation: ClockRelation, groups: Vec<Vec<String>>) -> &mut Self {
+        self.clock_groups.push(ClockGroups { relation, groups });
+        self
+    }
+
+    pub fn input_delay(&mut self, clock: &str, delay: Decimal, ports: PortGroup) -> &mut Self {
+        self.input_delays.push(IoDelay {
+            clock: clock.to_string(),
+            delay,
+            ports,
+        });
+        self
+    }
+
+    pub fn output_delay(&mut self, clock: &str, delay: Decimal, ports: PortGroup) -> &mut Self {
+        self.output_delays.push(IoDelay {
+            clock: clock.to_string(),
+            delay,
+            ports,
+        });
+        self
+    }
+
+    pub fn load(&mut self, capacitance: Decimal, ports: PortGroup) -> &mut Self {
+        self.loads.push(Load { capacitance, ports });
+        self
+    }
+
+    pub fn false_path(&mut self, path: Exception) -> &mut Self {
+        self.false_paths.push(path);
+        self
+    }
+
+    pub fn multicycle_path(
+        &mut self,
+        cycles: u32,
+        kind: MulticycleKind,
+        path: Exception,
+    ) -> &mut Self {
+        self.multicycle_paths
+            .push(MulticyclePath { cycles, kind, path });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Constraints::default()
+    }
+
+    pub fn get_clock(&self, name: &str) -> Option<&Clock> {
+        self.clocks.iter().find(|c| c.name == name)
+    }
+
+    /// Changes the period of clock `name`, scaling an explicit waveform with it
+    pub fn set_clock_period(&mut self, name: &str, period: Decimal) -> Result<(), SdcError> {
+        let clock = self
+            .clocks
+            .iter_mut()
+            .find(|c| c.name == name)
+            .ok_or_else(|| SdcError::UnknownClock(name.to_string()))?;
+        for period in [period, clock.period] {
+            if period <= Decimal::ZERO {
+                return Err(SdcError::InvalidPeriod {
+                    clock: name.to_string(),
+                    period,
+                });
+            }
+        }
+        if let Some((rise, fall)) = clock.waveform {
+            let scale = period / clock.period;
+            clock.waveform = Some((rise * scale, fall * scale));
+        }
+        clock.period = period;
+        Ok(())
+    }
+
+    /// Checks that every referenced clock is defined, every referenced port is in `ports` and
+    /// every clock has a valid period or ratio
+    pub fn validate(&self, ports: &[String]) -> Result<(), Vec<SdcError>> {
+        let clock_names: Vec<&String> = self
+            .clocks
+            .iter()
+            .map(|c| &c.name)
+            .chain(self.generated_clocks.iter().map(|c| &c.name))
+            .collect();
+        let mut clocks: Vec<&String> = Vec::new();
+        let mut port_refs: Vec<&String> = Vec::new();
+        port_refs.extend(self.clocks.iter().filter_map(|c| c.port.as_ref()));
+        clocks.extend(self.generated_clocks.iter().map(|c| &c.master));
+        clocks.extend(
+            self.clock_groups
+                .iter()
+                .flat_map(|g| g.groups.iter().flatten()),
+        );
+        for delay in self.input_delays.iter().chain(&self.output_delays) {
+            clocks.push(&delay.clock);
+            port_refs.extend(delay.ports.ports());
+        }
+        port_refs.extend(self.loads.iter().flat_map(|l| l.ports.ports()));
+        let mut points: Vec<&PathPoints> = Vec::new();
+        for clock in &self.generated_clocks {
+            points.extend([&clock.source, &clock.target]);
+        }
+        for path in self
+            .false_paths
+            .iter()
+            .chain(self.multicycle_paths.iter().map(|m| &m.path))
+        {
+            points.extend([&path.from, &path.through, &path.to].into_iter().flatten());
+        }
+        for points in points {
+            match points {
+                PathPoints::Clocks(names) => clocks.extend(names),
+                PathPoints::Ports(names) => port_refs.extend(names),
+                PathPoints::Pins(_) | PathPoints::Cells(_) => {}
+            }
+        }
+
+        let mut errors = Vec::new();
+        for clock in &self.clocks {
+            if clock.period <= Decimal::ZERO {
+                errors.push(SdcError::InvalidPeriod {
+                    clock: clock.name.clone(),
+                    period: clock.period,
+                });
+            }
+        }
+        for clock in &self.generated_clocks {
+            let ratio = |r: Option<u32>| r.is_some_and(|r| r > 0);
+            if !ratio(clock.divide_by) && !ratio(clock.multiply_by) {
+                errors.push(SdcError::MissingRatio(clock.name.clone()));
+            }
+        }
+        for clock in clocks {
+            let known = clock_names.iter().any(|c| glob_match(clock, c));
+            let error = SdcError::UnknownClock(clock.clone());
+            if !known && !errors.contains(&error) {
+                errors.push(error);
+            }
+        }
+        for port in port_refs {
+            let error = SdcError::UnknownPort(port.clone());
+            if !port_exists(port, ports) && !errors.contains(&error) {
+                errors.push(error);
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Renders the constraints as an SDC script
+    pub fn to_script(&self) -> Script {
+        let mut sdc = Script::new();
+        for clock in &self.clocks {
+            let mut create = Command::new("create_clock")
+                .opt("name", &clock.name)
+                .opt("period", clock.period);
+            if let Some((rise, fall)) = clock.waveform {
+                create = create.opt("waveform", Word::raw(format!("{{{rise} {fall}}}")));
+            }
+            if let Some(port) = &clock.port {
+                create = create.arg(Command::new("get_ports").arg(port));
+            }
+            sdc.command(create);
+            if let Some(uncertainty) = clock.uncertainty {
+                sdc.command(
+                    Command::new("set_clock_uncertainty")
+                        .arg(uncertainty)
+                        .arg(Command::new("get_clocks").arg(&clock.name)),
+                );
+            }
+        }
+        for clock in &self.generated_clocks {
+            let mut create = Command::new("create_generated_clock")
+                .opt("name", &clock.name)
+                .opt("source", clock.source.word())
+                .opt(
+                    "master_clock",
+                    Command::new("get_clocks").arg(&clock.master),
+                );
+            if let Some(divide_by) = clock.divide_by {
+                create = create.opt("divide_by", i64::from(divide_by));
+            }
+            if let Some(multiply_by) = clock.multiply_by {
+                create = create.opt("multiply_by", i64::from(multiply_by));
+            }
+            sdc.command(create.arg(clock.target.word()));
+        }
+        for groups in &self.clock_groups {
+            let mut command = Command::new("set_clock_groups").flag(match groups.relation {
+                ClockRelation::Asynchronous => "asynchronous",
+                ClockRelation::LogicallyExclusive => "logically_exclusive",
+                ClockRelation::PhysicallyExclusive => "physically_exclusive",
+            });
+            for group in &groups.groups {
+                command = command.opt("group", Command::new("get_clocks").arg(objects(group)));
+            }
+            sdc.command(command);
+        }
+        for load in &self.loads {
+            sdc.command(
+                Command::new("set_load")
+                    .arg(load.capacitance)
+                    .arg(load.ports.word()),
+            );
+        }
+        for (command, delays) in [
+            ("set_input_delay", &self.input_delays),
+            ("set_output_delay", &self.output_delays),
+        ] {
+            for delay in delays {
+                sdc.command(
+                    Command::new(command)
+                        .opt("clock", Command::new("get_clocks").arg(&delay.clock))
+                        .arg(delay.delay)
+                        .arg(delay.ports.word()),
+                );
+            }
+        }
+        for path in &self.false_paths {
+            sdc.command(path.apply(Command::new("set_false_path")));
+        }
+        for multicycle in &self.multicycle_paths {
+            let command = Command::new("set_multicycle_path")
+                .arg(i64::from(multicycle.cycles))
+                .flag(match multicycle.kind {
+                    MulticycleKind::Setup => "setup",
+                    MulticycleKind::Hold => "hold",
+                });
+            sdc.command(multicycle.path.apply(command));
+        }
+        sdc
+    }
+}
+
+impl fmt::Display for Constraints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_script())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tcl::check_syntax;
+
+    #[test]
+    fn renders_multi_clock_constraints() {
+        let mut sdc = Constraints::new();
+        sdc.clock(Clock::on_port("clk", Decimal::new(20, 1)).uncertainty(Decimal::new(5, 2)))
+            .clock(
+                Clock::virtual_clock("vclk", Decimal::TEN)
+                    .waveform(Decimal::ZERO, Decimal::new(5, 0)),
+            )
+            .generated_clock(GeneratedClock {
+                name: "clk_div2".into(),
+                source: PathPoints::Ports(vec!["clk".into()]),
+                master: "clk".into(),
+                target: PathPoints::Pins(vec!["div/q".into()]),
+                divide_by: Some(2),
+                multiply_by: None,
+            })
+            .clock_group(
+                ClockRelation::Asynchronous,
+                vec![vec!["clk".into(), "clk_div2".into()], vec!["vclk".into()]],
+            )
+            .input_delay(
+                "vclk",
+                Decimal::ONE,
+                PortGroup::Ports(vec!["data[0]".into(), "valid".into()]),
+            )
+            .false_path(Exception {
+                from: Some(PathPoints::Ports(vec!["rst".into()])),
+                ..Default::default()
+            })
+            .multicycle_path(
+                2,
+                MulticycleKind::Setup,
+                Exception {
+                    to: Some(PathPoints::Pins(vec!["acc/d".into()])),
+                    ..Default::default()
+                },
+            );
+        sdc.set_clock_period("vclk", Decimal::new(20, 0)).unwrap();
+        assert_eq!(
+            sdc.set_clock_period("missing", Decimal::ONE),
+            Err(SdcError::UnknownClock("missing".into()))
+        );
+        assert_eq!(
+            sdc.set_clock_period("vclk", Decimal::ZERO),
+            Err(SdcError::InvalidPeriod {
+                clock: "vclk".into(),
+                period: Decimal::ZERO
+            })
+        );
+
+        let rendered = sdc.to_string();
+        assert_eq!(
+            rendered.lines().collect::<Vec<_>>(),
+            [
+                "create_clock -name clk -period 2.0 [get_ports clk]",
+                "set_clock_uncertainty 0.05 [get_clocks clk]",
+                "create_clock -name vclk -period 20 -waveform {0 10}",
+                "create_generated_clock -name clk_div2 -source [get_ports clk] -master_clock [get_clocks clk] -divide_by 2 [get_pins div/q]",
+                "set_clock_groups -asynchronous -group [get_clocks {clk clk_div2}] -group [get_clocks vclk]",
+                "set_input_delay -clock [get_clocks vclk] 1 [get_ports {{data[0]} valid}]",
+                "set_false_path -from [get_ports rst]",
+                "set_multicycle_path 2 -setup -to [get_pins acc/d]",
+            ]
+        );
+        assert_eq!(check_syntax(&rendered), Ok(()));
+
+        let ports: Vec<String> = ["clk", "data", "valid", "rst"].map(String::from).into();
+        assert_eq!(sdc.validate(&ports), Ok(()));
+        sdc.generated_clocks[0].divide_by = None;
+        sdc.generated_clocks[0].source = PathPoints::Ports(vec!["clk_in".into()]);
+        assert_eq!(
+            sdc.validate(&ports),
+            Err(vec![
+                SdcError::MissingRatio("clk_div2".into()),
+                SdcError::UnknownPort("clk_in".into())
+            ])
+        );
+    }
+}
@@ -0,0 +1,795 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use crate::sdc::{
+    Clock, ClockRelation, Constraints, Exception, GeneratedClock, MulticycleKind, PathPoints,
+    PortGroup, SdcError,
+};
+use crate::tcl::{check_syntax, quote};
+
+/// A word of an SDC command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdcWord {
+    /// A value with its braces, quotes and escapes removed
+    Literal(String),
+    /// A word kept verbatim because it uses variables or partial substitutions
+    Raw(String),
+    /// A `[...]` command substitution
+    Subst(SdcCommand),
+}
+
+impl SdcWord {
+    fn literal(&self) -> Option<&str> {
+        match self {
+            SdcWord::Literal(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn subst(&self) -> Option<&SdcCommand> {
+        match self {
+            SdcWord::Subst(command) => Some(command),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SdcWord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdcWord::Literal(s) => write!(f, "{}", quote(s)),
+            SdcWord::Raw(s) => write!(f, "{s}"),
+            SdcWord::Subst(command) => write!(f, "[{command}]"),
+        }
+    }
+}
+
+/// A command read from an SDC file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdcCommand {
+    pub name: String,
+    pub words: Vec<SdcWord>,
+    /// Line of the file the command starts on
+    pub line: usize,
+}
+
+impl SdcCommand {
+    /// Applies `f` to this command and every command substituted into it, innermost first
+    pub fn visit_mut(&mut self, f: &mut impl FnMut(&mut SdcCommand)) {
+        for word in &mut self.words {
+            if let SdcWord::Subst(command) = word {
+                command.visit_mut(f);
+            }
+        }
+        f(self);
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        self.words.iter().any(|w| w.literal() == Some(flag))
+    }
+}
+
+impl fmt::Display for SdcCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for word in &self.words {
+            write!(f, " {word}")?;
+        }
+        Ok(())
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        if c == '\n' {
+            self.line += 1;
+        }
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn at_word_end(&self, in_bracket: bool) -> bool {
+        match self.peek() {
+            None | Some(' ' | '\t' | '\n' | '\r' | ';') => true,
+            Some(']') => in_bracket,
+            _ => false,
+        }
+    }
+
+    fn script(&mut self, in_bracket: bool) -> Vec<SdcCommand> {
+        let mut commands = Vec::new();
+        loop {
+            while let Some(' ' | '\t' | '\n' | '\r' | ';') = self.peek() {
+                self.bump();
+            }
+            match self.peek() {
+                None => return commands,
+                Some(']') if in_bracket => {
+                    self.bump();
+                    return commands;
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        if self.bump() == Some('\\') {
+                            self.bump();
+                        }
+                    }
+                }
+                Some(_) => commands.extend(self.command(in_bracket)),
+            }
+        }
+    }
+
+    fn command(&mut self, in_bracket: bool) -> Option<SdcCommand> {
+        let line = self.line;
+        let mut words = Vec::new();
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r') => {
+                    self.bump();
+                }
+                Some('\\') if self.chars.get(self.pos + 1) == Some(&'\n') => {
+                    self.bump();
+                    self.bump();
+                }
+                None | Some('\n' | ';') => break,
+                Some(']') if in_bracket => break,
+                Some(_) => words.push(self.word(in_bracket)),
+            }
+        }
+        if words.is_empty() {
+            return None;
+        }
+        let name = match words.remove(0) {
+            SdcWord::Literal(name) | SdcWord::Raw(name) => name,
+            SdcWord::Subst(command) => format!("[{command}]"),
+        };
+        Some(SdcCommand { name, words, line })
+    }
+
+    fn word(&mut self, in_bracket: bool) -> SdcWord {
+        let start = self.pos;
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                let mut depth = 1;
+                let mut value = String::new();
+                while let Some(c) = self.bump() {
+                    match c {
+                        '\\' => {
+                            value.push(c);
+                            value.extend(self.bump());
+                            continue;
+                        }
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    value.push(c);
+                }
+                SdcWord::Literal(value)
+            }
+            Some('[') => {
+                self.bump();
+                let mut commands = self.script(true);
+                if self.at_word_end(in_bracket) && commands.len() == 1 {
+                    return SdcWord::Subst(commands.remove(0));
+                }
+                self.rest_of_word(start, in_bracket)
+            }
+            Some('"') => {
+                self.bump();
+                let mut value = String::new();
+                let mut raw = false;
+                while let Some(c) = self.bump() {
+                    match c {
+                        '"' => break,
+                        '\\' => value.extend(self.bump()),
+                        '[' => {
+                            raw = true;
+                            self.script(true);
+                        }
+                        '$' => raw = true,
+                        _ => value.push(c),
+                    }
+                }
+                if raw {
+                    SdcWord::Raw(self.chars[start..self.pos].iter().collect())
+                } else {
+                    SdcWord::Literal(value)
+                }
+            }
+            _ => {
+                let mut value = String::new();
+                while !self.at_word_end(in_bracket) {
+                    match self.peek() {
+                        Some('[' | '$') => return self.rest_of_word(start, in_bracket),
+                        Some('\\') => {
+                            self.bump();
+                            value.extend(self.bump());
+                        }
+                        _ => value.extend(self.bump()),
+                    }
+                }
+                SdcWord::Literal(value)
+            }
+        }
+    }
+
+    /// Skips to the end of a word that cannot be interpreted and returns it verbatim
+    fn rest_of_word(&mut self, start: usize, in_bracket: bool) -> SdcWord {
+        while !self.at_word_end(in_bracket) {
+            match self.bump() {
+                Some('[') => {
+                    self.script(true);
+                }
+                Some('\\') => {
+                    self.bump();
+                }
+                _ => {}
+            }
+        }
+        SdcWord::Raw(self.chars[start..self.pos].iter().collect())
+    }
+}
+
+/// Splits SDC text into commands, dropping comments
+pub fn parse_sdc(text: &str) -> Result<Vec<SdcCommand>, SdcError> {
+    check_syntax(text).map_err(|e| SdcError::Syntax {
+        line: e.line,
+        message: e.message,
+    })?;
+    Ok(Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    }
+    .script(false))
+}
+
+/// Rewrites every command in `text`, including substituted ones, with `f`
+pub fn rewrite_sdc(text: &str, mut f: impl FnMut(&mut SdcCommand)) -> Result<String, SdcError> {
+    let mut rewritten = String::new();
+    for mut command in parse_sdc(text)? {
+        command.visit_mut(&mut f);
+        rewritten.push_str(&command.to_string());
+        rewritten.push('\n');
+    }
+    Ok(rewritten)
+}
+
+/// Makes `get_pins` search the whole hierarchy, for constraints of a block read into its parent
+pub fn scope_pins_hierarchically(command: &mut SdcCommand) {
+    if command.name == "get_pins" && !command.has_flag("-hierarchical") {
+        command
+            .words
+            .insert(0, SdcWord::Literal("-hierarchical".into()));
+    }
+}
+
+/// An SDC file read into the typed constraints model
+#[derive(Debug, Clone, Default)]
+pub struct SdcFile {
+    pub constraints: Constraints,
+    /// Commands outside the subset covered by [`Constraints`]
+    pub unsupported: Vec<SdcCommand>,
+}
+
+/// Splits a TCL list into its elements
+fn split_list(list: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut chars = list.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut item = String::new();
+        if c == '{' {
+            chars.next();
+            let mut depth = 1;
+            for c in chars.by_ref() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                item.push(c);
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                item.push(c);
+                chars.next();
+            }
+        }
+        items.push(item);
+    }
+    items
+}
+
+/// Options and positional arguments of a command
+struct Args<'a> {
+    command: &'a SdcCommand,
+    options: Vec<(&'a str, Option<&'a SdcWord>)>,
+    positional: Vec<&'a SdcWord>,
+}
+
+impl<'a> Args<'a> {
+    /// Splits the words of `command`; the flags in `valued` take the following word as value
+    fn parse(command: &'a SdcCommand, valued: &[&str]) -> Self {
+        let mut args = Args {
+            command,
+            options: Vec::new(),
+            positional: Vec::new(),
+        };
+        let mut words = command.words.iter();
+        while let Some(word) = words.next() {
+            match word.literal() {
+                Some(flag) if flag.starts_with('-') && Decimal::from_str(flag).is_err() => {
+                    let value = if valued.contains(&flag) {
+                        words.next()
+                    } else {
+                        None
+                    };
+                    args.options.push((flag, value));
+                }
+                _ => args.positional.push(word),
+            }
+        }
+        args
+    }
+
+    fn invalid<T>(&self, message: impl Into<String>) -> Result<T, SdcError> {
+        Err(SdcError::Invalid {
+            line: self.command.line,
+            command: self.command.name.clone(),
+            message: message.into(),
+        })
+    }
+
+    fn flag(&self, flag: &str) -> bool {
+        self.options.iter().any(|(f, _)| *f == flag)
+    }
+
+    /// Returns true if any option other than `known` is present
+    fn has_other_options(&self, known: &[&str]) -> bool {
+        self.options.iter().any(|(f, _)| !known.contains(f))
+    }
+
+    /// Returns the values given to `flag`, in order
+    fn values(&self, flag: &str) -> Vec<&'a SdcWord> {
+        self.options
+            .iter()
+            .filter(|(f, _)| *f == flag)
+            .filter_map(|(_, v)| *v)
+            .collect()
+    }
+
+    fn value(&self, flag: &str) -> Option<&'a SdcWord> {
+        self.values(flag).into_iter().next()
+    }
+
+    fn number<T: FromStr>(&self, word: Option<&SdcWord>, what: &str) -> Result<T, SdcError> {
+        match word.and_then(SdcWord::literal).map(str::parse) {
+            Some(Ok(value)) => Ok(value),
+            _ => self.invalid(format!("missing or invalid {what}")),
+        }
+    }
+}
+
+/// Returns the object names of a `[get_<kind> ...]` word, or of a plain list
+fn object_names(word: &SdcWord, kind: &str) -> Option<Vec<String>> {
+    match word {
+        SdcWord::Literal(list) => Some(split_list(list)),
+        SdcWord::Subst(command) if command.name == kind => {
+            let args = Args::parse(command, &[]);
+            if !args.options.is_empty() {
+                return None;
+            }
+            let mut names = Vec::new();
+            for word in args.positional {
+                names.extend(split_list(word.literal()?));
+            }
+            Some(names)
+        }
+        _ => None,
+    }
+}
+
+fn port_group(word: &SdcWord) -> Option<PortGroup> {
+    let command = word.subst()?;
+    match command.name.as_str() {
+        "all_inputs" if command.words.is_empty() => Some(PortGroup::AllInputs),
+        "all_outputs" if command.words.is_empty() => Some(PortGroup::AllOutputs),
+        "get_ports" => Some(PortGroup::Ports(object_names(word, "get_ports")?)),
+        "remove_from_collection" => match command.words.as_slice() {
+            [all, except] if port_group(all)? == PortGroup::AllInputs => {
+                Some(PortGroup::InputsExcept(object_names(except, "get_ports")?))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn path_points(word: &SdcWord) -> Option<PathPoints> {
+    let command = word.subst()?;
+    let names = object_names(word, &command.name)?;
+    match command.name.as_str() {
+        "get_clocks" => Some(PathPoints::Clocks(names)),
+        "get_ports" => Some(PathPoints::Ports(names)),
+        "get_pins" => Some(PathPoints::Pins(names)),
+        "get_cells" => Some(PathPoints::Cells(names)),
+        _ => None,
+    }
+}
+
+/// Returns the ports or pins of a `[get_ports ...]` or `[get_pins ...]` word
+fn pin_or_port(word: &SdcWord) -> Option<PathPoints> {
+    match path_points(word)? {
+        points @ (PathPoints::Ports(_) | PathPoints::Pins(_)) => Some(points),
+        _ => None,
+    }
+}
+
+fn single_name(word: Option<&SdcWord>, kind: &str) -> Option<String> {
+    match object_names(word?, kind)?.as_slice() {
+        [name] => Some(name.clone()),
+        _ => None,
+    }
+}
+
+fn exception(args: &Args<'_>) -> Option<Exception> {
+    let points = |flag| match args.values(flag).as_slice() {
+        [] => Some(None),
+        [word] => path_points(word).map(Some),
+        _ => None,
+    };
+    Some(Exception {
+        from: points("-from")?,
+        through: points("-through")?,
+        to: points("-to")?,
+    })
+}
+
+/// Adds `command` to `constraints`, returning false if it is outside the supported subset
+fn read_command(constraints: &mut Constraints, command: &SdcCommand) -> Result<bool, SdcError> {
+    match command.name.as_str() {
+        "create_clock" => {
+            let args = Args::parse(command, &["-name", "-period", "-waveform"]);
+            if args.has_other_options(&["-name", "-period", "-waveform"]) {
+                return Ok(false);
+            }
+            let period = args.number(args.value("-period"), "period")?;
+            let port = match args.positional.as_slice() {
+                [] => None,
+                [port] => match single_name(Some(port), "get_ports") {
+                    Some(port) => Some(port),
+                    None => return Ok(false),
+                },
+                _ => return args.invalid("expected a single source object"),
+            };
+            let Some(name) = args
+                .value("-name")
+                .and_then(SdcWord::literal)
+                .map(str::to_string)
+                .or_else(|| port.clone())
+            else {
+                return args.invalid("virtual clock without -name");
+            };
+            let waveform = match args.value("-waveform") {
+                Some(word) => {
+                    let edges = split_list(word.literal().unwrap_or_default());
+                    match edges.as_slice() {
+                        [rise, fall] => match (rise.parse(), fall.parse()) {
+                            (Ok(rise), Ok(fall)) => Some((rise, fall)),
+                            _ => return args.invalid("invalid waveform"),
+                        },
+                        _ => return Ok(false),
+                    }
+                }
+                None => None,
+            };
+            constraints.clock(Clock {
+                name,
+                port,
+                period,
+                waveform,
+                uncertainty: None,
+            });
+        }
+        "set_clock_uncertainty" => {
+            let args = Args::parse(command, &[]);
+            if !args.options.is_empty() {
+                return Ok(false);
+            }
+            let [value, clocks] = args.positional.as_slice() else {
+                return Ok(false);
+            };
+            let uncertainty = args.number(Some(value), "uncertainty")?;
+            let Some(names) = object_names(clocks, "get_clocks") else {
+                return Ok(false);
+            };
+            if names.iter().any(|n| constraints.get_clock(n).is_none()) {
+                return Ok(false);
+            }
+            for clock in &mut constraints.clocks {
+                if names.contains(&clock.name) {
+                    clock.uncertainty = Some(uncertainty);
+                }
+            }
+        }
+        "create_generated_clock" => {
+            let valued = [
+                "-name",
+                "-source",
+                "-master_clock",
+                "-divide_by",
+                "-multiply_by",
+            ];
+            let args = Args::parse(command, &valued);
+            if args.has_other_options(&valued) {
+                return Ok(false);
+            }
+            let (Some(source), Some(master), [target]) = (
+                args.value("-source").and_then(pin_or_port),
+                single_name(args.value("-master_clock"), "get_clocks"),
+                args.positional.as_slice(),
+            ) else {
+                return Ok(false);
+            };
+            let Some(target) = pin_or_port(target) else {
+                return Ok(false);
+            };
+            let Some(name) = args
+                .value("-name")
+                .and_then(SdcWord::literal)
+                .or(match &target {
+                    PathPoints::Ports(names) | PathPoints::Pins(names) if names.len() == 1 => {
+                        Some(names[0].as_str())
+                    }
+                    _ => None,
+                })
+            else {
+                return Ok(false);
+            };
+            let optional = |flag| match args.value(flag) {
+                Some(word) => args.number(Some(word), flag).map(Some),
+                None => Ok(None),
+            };
+            let (divide_by, multiply_by) = (optional("-divide_by")?, optional("-multiply_by")?);
+            if divide_by.unwrap_or(0) == 0 && multiply_by.unwrap_or(0) == 0 {
+                return args.invalid("needs a non-zero -divide_by or -multiply_by");
+            }
+            constraints.generated_clock(GeneratedClock {
+                name: name.to_string(),
+                source,
+                master,
+                target,
+                divide_by,
+                multiply_by,
+            });
+        }
+        "set_clock_groups" => {
+            let args = Args::parse(command, &["-group", "-name"]);
+            let relation = match (
+                args.flag("-asynchronous"),
+                args.flag("-logically_exclusive"),
+                args.flag("-physically_exclusive"),
+            ) {
+                (true, false, false) => ClockRelation::Asynchronous,
+                (false, true, false) => ClockRelation::LogicallyExclusive,
+                (false, false, true) => ClockRelation::PhysicallyExclusive,
+                _ => return Ok(false),
+            };
+            let groups: Option<Vec<Vec<String>>> = args
+                .values("-group")
+                .into_iter()
+                .map(|g| object_names(g, "get_clocks"))
+                .collect();
+            match groups {
+                Some(groups) if args.positional.is_empty() => {
+                    constraints.clock_group(relation, groups);
+                }
+                _ => return Ok(false),
+            }
+        }
+        "set_input_delay" | "set_output_delay" => {
+            let args = Args::parse(command, &["-clock"]);
+            if args.has_other_options(&["-clock"]) {
+                return Ok(false);
+            }
+            let [delay, ports] = args.positional.as_slice() else {
+                return Ok(false);
+            };
+            let delay = args.number(Some(delay), "delay")?;
+            let (Some(clock), Some(ports)) = (
+                single_name(args.value("-clock"), "get_clocks"),
+                port_group(ports),
+            ) else {
+                return Ok(false);
+            };
+            if command.name == "set_input_delay" {
+                constraints.input_delay(&clock, delay, ports);
+            } else {
+                constraints.output_delay(&clock, delay, ports);
+            }
+        }
+        "set_load" => {
+            let args = Args::parse(command, &[]);
+            let ([capacitance, ports], true) =
+                (args.positional.as_slice(), args.options.is_empty())
+            else {
+                return Ok(false);
+            };
+            let capacitance = args.number(Some(capacitance), "load")?;
+            let Some(ports) = port_group(ports) else {
+                return Ok(false);
+            };
+            constraints.load(capacitance, ports);
+        }
+        "set_false_path" => {
+            let valued = ["-from", "-through", "-to"];
+            let args = Args::parse(command, &valued);
+            match exception(&args) {
+                Some(path) if !args.has_other_options(&valued) && args.positional.is_empty() => {
+                    constraints.false_path(path);
+                }
+                _ => return Ok(false),
+            }
+        }
+        "set_multicycle_path" => {
+            let valued = ["-from", "-through", "-to"];
+            let args = Args::parse(command, &valued);
+            let kind = match (args.flag("-setup"), args.flag("-hold")) {
+                (false, false) | (true, false) => MulticycleKind::Setup,
+                (false, true) => MulticycleKind::Hold,
+                (true, true) => return Ok(false),
+            };
+            let [cycles] = args.positional.as_slice() else {
+                return Ok(false);
+            };
+            let cycles = args.number(Some(cycles), "cycle count")?;
+            match exception(&args) {
+                Some(path)
+                    if !args
+                        .has_other_options(&["-from", "-through", "-to", "-setup", "-hold"]) =>
+                {
+                    constraints.multicycle_path(cycles, kind, path);
+                }
+                _ => return Ok(false),
+            }
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Reads the commands of `text` covered by the typed constraints model
+pub fn read_sdc(text: &str) -> Result<SdcFile, SdcError> {
+    let mut file = SdcFile::default();
+    for command in parse_sdc(text)? {
+        if !read_command(&mut file.constraints, &command)? {
+            file.unsupported.push(command);
+        }
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SDC: &str = r#"
+# written by hand
+set sdc_version 2.0
+create_clock -name core -period 5 -waveform {0 2.5} [get_ports clk]
+set_clock_uncertainty 0.1 [get_clocks core]
+create_clock -name vio -period 10
+create_generated_clock -name half -source [get_pins pll/out] -master_clock [get_clocks core] \
+    -divide_by 2 [get_pins div/q]
+set_clock_groups -asynchronous -group {core half} -group [get_clocks vio]
+set_input_delay -clock [get_clocks vio] 1.5 [remove_from_collection [all_inputs] [get_ports clk]]
+set_output_delay -clock vio -0.5 [get_ports {{dout[0]} dout[1]}]
+set_output_delay -clock vio -max 2 [all_outputs]
+set_load 0.02 [all_outputs]
+set_false_path -from [get_ports rst_n]
+set_multicycle_path 2 -hold -to [get_pins acc/d]
+"#;
+
+    #[test]
+    fn reads_supported_commands_and_keeps_the_rest() {
+        let file = read_sdc(SDC).unwrap();
+        let c = &file.constraints;
+        let core = c.get_clock("core").unwrap();
+        assert_eq!(core.port.as_deref(), Some("clk"));
+        assert_eq!(core.waveform, Some((Decimal::ZERO, Decimal::new(25, 1))));
+        assert_eq!(core.uncertainty, Some(Decimal::new(1, 1)));
+        assert_eq!(c.get_clock("vio").unwrap().port, None);
+        assert_eq!(c.generated_clocks[0].divide_by, Some(2));
+        assert_eq!(
+            c.clock_groups[0].groups,
+            [vec!["core", "half"], vec!["vio"]]
+        );
+        assert_eq!(
+            c.input_delays[0].ports,
+            PortGroup::InputsExcept(vec!["clk".into()])
+        );
+        assert_eq!(c.output_delays[0].delay, Decimal::new(-5, 1));
+        assert_eq!(
+            c.output_delays[0].ports,
+            PortGroup::Ports(vec!["dout[0]".into(), "dout[1]".into()])
+        );
+        assert_eq!(c.multicycle_paths[0].kind, MulticycleKind::Hold);
+        let unsupported: Vec<&str> = file.unsupported.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(unsupported, ["set", "set_output_delay"]);
+        assert_eq!(file.unsupported[1].line, 12);
+
+        assert_eq!(
+            c.validate(&["clk".into(), "dout".into(), "rst_n".into()]),
+            Ok(())
+        );
+        assert_eq!(
+            c.validate(&["clk".into()]),
+            Err(vec![
+                SdcError::UnknownPort("dout[0]".into()),
+                SdcError::UnknownPort("dout[1]".into()),
+                SdcError::UnknownPort("rst_n".into()),
+            ])
+        );
+
+        assert_eq!(read_sdc(&c.to_string()).unwrap().constraints, *c);
+        assert!(matches!(
+            read_sdc("create_clock -period fast [get_ports clk]"),
+            Err(SdcError::Invalid { line: 1, .. })
+        ));
+        let divided = read_sdc(
+            "create_generated_clock -source [get_ports clk] -master_clock core -divide_by 2 [get_pins div/q]",
+        )
+        .unwrap();
+        let divided = &divided.constraints.generated_clocks[0];
+        assert_eq!(divided.name, "div/q");
+        assert_eq!(divided.source, PathPoints::Ports(vec!["clk".into()]));
+        assert!(matches!(
+            read_sdc(
+                "create_generated_clock -source [get_ports clk] -master_clock core [get_pins q]"
+            ),
+            Err(SdcError::Invalid { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn scopes_pins_hierarchically() {
+        let sdc = "set_false_path -to [get_pins {a/b[0]}]\nset_case_analysis 0 [get_pins -hierarchical x/y]\n";
+        assert_eq!(
+            rewrite_sdc(sdc, scope_pins_hierarchically).unwrap(),
+            "set_false_path -to [get_pins -hierarchical {a/b[0]}]\nset_case_analysis 0 [get_pins -hierarchical x/y]\n"
+        );
+    }
+}
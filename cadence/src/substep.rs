@@ -0,0 +1,266 @@
+use regex::Regex;
+use std::fmt;
+use std::ops::Deref;
+
+use crate::Substep;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubstepError {
+    /// No substep with this name exists in the list
+    Unknown(String),
+    /// A substep with this name already exists in the list
+    Duplicate(String),
+    /// A substep was to be moved before or after itself
+    MoveOntoItself(String),
+    /// The endpoint of a slice comes before its start
+    EndBeforeStart { start_after: String, end_at: String },
+}
+
+impl fmt::Display for SubstepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubstepError::Unknown(name) => write!(f, "no substep named {name}"),
+            SubstepError::Duplicate(name) => write!(f, "substep {name} already exists"),
+            SubstepError::MoveOntoItself(name) => {
+                write!(f, "cannot move substep {name} relative to itself")
+            }
+            SubstepError::EndBeforeStart {
+                start_after,
+                end_at,
+            } => write!(f, "endpoint {end_at} comes before {start_after}"),
+        }
+    }
+}
+
+impl std::error::Error for SubstepError {}
+
+/// Ordered list of substeps with name-checked editing operations
+#[derive(Debug, Clone, Default)]
+pub struct SubstepList {
+    substeps: Vec<Substep>,
+}
+
+impl SubstepList {
+    pub fn new(substeps: Vec<Substep>) -> Self {
+        SubstepList { substeps }
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.substeps.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Substep> {
+        self.substeps.iter().find(|s| s.name == name)
+    }
+
+    /// Returns the index of the substep called `name`
+    pub fn position(&self, name: &str) -> Result<usize, SubstepError> {
+        self.substeps
+            .iter()
+            .position(|s| s.name == name)
+            .ok_or_else(|| SubstepError::Unknown(name.to_string()))
+    }
+
+    /// Returns the substeps whose names match `pattern`
+    pub fn find(&self, pattern: &Regex) -> Vec<&Substep> {
+        self.substeps
+            .iter()
+            .filter(|s| pattern.is_match(&s.name))
+            .collect()
+    }
+
+    fn check_new_name(&self, name: &str) -> Result<(), SubstepError> {
+        if self.get(name).is_some() {
+            return Err(SubstepError::Duplicate(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Appends a substep at the end of the list
+    pub fn push(&mut self, substep: Substep) -> Result<(), SubstepError> {
+        self.check_new_name(&substep.name)?;
+        self.substeps.push(substep);
+        Ok(())
+    }
+
+    pub fn insert_before(&mut self, target: &str, substep: Substep) -> Result<(), SubstepError> {
+        let index = self.position(target)?;
+        self.check_new_name(&substep.name)?;
+        self.substeps.insert(index, substep);
+        Ok(())
+    }
+
+    pub fn insert_after(&mut self, target: &str, substep: Substep) -> Result<(), SubstepError> {
+        let index = self.position(target)?;
+        self.check_new_name(&substep.name)?;
+        self.substeps.insert(index + 1, substep);
+        Ok(())
+    }
+
+    /// Replaces the substep called `target`, returning the replaced substep
+    pub fn replace(&mut self, target: &str, substep: Substep) -> Result<Substep, SubstepError> {
+        let index = self.position(target)?;
+        if substep.name != target {
+            self.check_new_name(&substep.name)?;
+        }
+        Ok(std::mem::replace(&mut self.substeps[index], substep))
+    }
+
+    pub fn remove(&mut self, target: &str) -> Result<Substep, SubstepError> {
+        let index = self.position(target)?;
+        Ok(self.substeps.remove(index))
+    }
+
+    /// Moves the substep called `name` to just before `target`, or just after it if `after`
+    fn move_relative(&mut self, name: &str, target: &str, after: bool) -> Result<(), SubstepError> {
+        let from = self.position(name)?;
+        let to = self.position(target)?;
+        if from == to {
+            return Err(SubstepError::MoveOntoItself(name.to_string()));
+        }
+        let substep = self.substeps.remove(from);
+        let to = if from < to { to - 1 } else { to };
+        self.substeps
+            .insert(if after { to + 1 } else { to }, substep);
+        Ok(())
+    }
+
+    /// Moves the substep called `name` so that it runs right before `target`
+    pub fn move_before(&mut self, name: &str, target: &str) -> Result<(), SubstepError> {
+        self.move_relative(name, target, false)
+    }
+
+    /// Moves the substep called `name` so that it runs right after `target`
+    pub fn move_after(&mut self, name: &str, target: &str) -> Result<(), SubstepError> {
+        self.move_relative(name, target, true)
+    }
+
+    /// Surrounds the command of the substep called `target` with TCL that runs before and after it
+    pub fn wrap(
+        &mut self,
+        target: &str,
+        prologue: &str,
+        epilogue: &str,
+    ) -> Result<(), SubstepError> {
+        let index = self.position(target)?;
+        let substep = &mut self.substeps[index];
+        substep.command = format!("{prologue}\n{}\n{epilogue}", substep.command);
+        Ok(())
+    }
+
+    /// Returns the substeps after `start_after` up to and including `end_at`
+    pub fn slice(
+        &self,
+        start_after: Option<&str>,
+        end_at: Option<&str>,
+    ) -> Result<Vec<Substep>, SubstepError> {
+        let start = match start_after {
+            Some(name) => self.position(name)? + 1,
+            None => 0,
+        };
+        let end = match end_at {
+            Some(name) => self.position(name)? + 1,
+            None => self.substeps.len(),
+        };
+        if end < start {
+            return Err(SubstepError::EndBeforeStart {
+                start_after: start_after.unwrap_or_default().to_string(),
+                end_at: end_at.unwrap_or_default().to_string(),
+            });
+        }
+        Ok(self.substeps[start..end].to_vec())
+    }
+}
+
+impl Deref for SubstepList {
+    type Target = [Substep];
+
+    fn deref(&self) -> &[Substep] {
+        &self.substeps
+    }
+}
+
+impl From<Vec<Substep>> for SubstepList {
+    fn from(substeps: Vec<Substep>) -> Self {
+        SubstepList::new(substeps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn substep(name: &str) -> Substep {
+        Substep {
+            name: name.into(),
+            command: name.into(),
+            checkpoint: false,
+        }
+    }
+
+    fn list() -> SubstepList {
+        SubstepList::new(vec![substep("a"), substep("b"), substep("c")])
+    }
+
+    #[test]
+    fn edits_by_name() {
+        let mut substeps = list();
+        substeps.insert_before("a", substep("pre")).unwrap();
+        substeps.insert_after("c", substep("post")).unwrap();
+        substeps.replace("b", substep("b2")).unwrap();
+        substeps.move_after("pre", "c").unwrap();
+        substeps.move_before("post", "a").unwrap();
+        substeps.remove("c").unwrap();
+        assert_eq!(substeps.names(), ["post", "a", "b2", "pre"]);
+
+        substeps.wrap("a", "puts start", "puts end").unwrap();
+        assert_eq!(
+            substeps.get("a").unwrap().command,
+            "puts start\na\nputs end"
+        );
+
+        let found = substeps.find(&Regex::new("^p").unwrap());
+        assert_eq!(found.len(), 2);
+    }
+
+    #[test]
+    fn rejects_unknown_and_duplicate_names() {
+        let mut substeps = list();
+        assert_eq!(
+            substeps.replace("syn_opt", substep("x")).unwrap_err(),
+            SubstepError::Unknown("syn_opt".into())
+        );
+        assert_eq!(
+            substeps.insert_after("a", substep("b")),
+            Err(SubstepError::Duplicate("b".into()))
+        );
+        assert_eq!(
+            substeps.move_before("a", "missing"),
+            Err(SubstepError::Unknown("missing".into()))
+        );
+        assert_eq!(
+            substeps.move_before("a", "a"),
+            Err(SubstepError::MoveOntoItself("a".into()))
+        );
+        assert_eq!(substeps.names(), ["a", "b", "c"]);
+        substeps.move_before("c", "b").unwrap();
+        substeps.move_after("a", "b").unwrap();
+        assert_eq!(substeps.names(), ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn slices_between_checkpoint_and_endpoint() {
+        let substeps = list();
+        let names = |v: Vec<Substep>| v.into_iter().map(|s| s.name).collect::<Vec<_>>();
+        assert_eq!(names(substeps.slice(Some("a"), None).unwrap()), ["b", "c"]);
+        assert_eq!(names(substeps.slice(None, Some("b")).unwrap()), ["a", "b"]);
+        assert!(substeps.slice(Some("b"), Some("b")).unwrap().is_empty());
+        assert_eq!(
+            substeps.slice(Some("c"), Some("a")).unwrap_err(),
+            SubstepError::EndBeforeStart {
+                start_after: "c".into(),
+                end_at: "a".into()
+            }
+        );
+    }
+}
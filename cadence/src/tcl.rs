@@ -0,0 +1,561 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use rust_decimal::Decimal;
+
+fn is_bare(word: &str) -> bool {
+    !word.is_empty()
+        && !word.starts_with('#')
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./:+,=@%*!?~^<>".contains(c))
+}
+
+fn can_brace(word: &str) -> bool {
+    let mut depth = 0i32;
+    let mut escaped = false;
+    for c in word.chars() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth < 0 {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    depth == 0 && !escaped
+}
+
+/// Quotes `word` so that TCL parses it back as exactly one word with the same value
+pub fn quote(word: &str) -> String {
+    if is_bare(word) {
+        return word.to_string();
+    }
+    if word.is_empty() || can_brace(word) {
+        return format!("{{{word}}}");
+    }
+    let mut quoted = String::with_capacity(word.len() + 8);
+    for c in word.chars() {
+        match c {
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\\' | '"' | '$' | '[' | ']' | '{' | '}' | ';' | ' ' | '#' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            _ => quoted.push(c),
+        }
+    }
+    quoted
+}
+
+/// A single word of a TCL command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Word {
+    /// A value that is quoted on output
+    Literal(String),
+    /// TCL emitted verbatim, such as `$var` or `-flag`
+    Raw(String),
+    /// A list of words, emitted as `[list ...]`
+    List(Vec<Word>),
+    /// Command substitution, emitted as `[...]`
+    Subst(Box<Command>),
+}
+
+impl Word {
+    pub fn raw(tcl: impl Into<String>) -> Self {
+        Word::Raw(tcl.into())
+    }
+
+    pub fn var(name: &str) -> Self {
+        Word::Raw(format!("${name}"))
+    }
+}
+
+/// Builds a `[list ...]` word from `items`
+pub fn list<W: Into<Word>>(items: impl IntoIterator<Item = W>) -> Word {
+    Word::List(items.into_iter().map(Into::into).collect())
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Word::Literal(s) => write!(f, "{}", quote(s)),
+            Word::Raw(s) => write!(f, "{s}"),
+            Word::List(items) => {
+                write!(f, "[list")?;
+                for item in items {
+                    write!(f, " {item}")?;
+                }
+                write!(f, "]")
+            }
+            Word::Subst(command) => write!(f, "[{command}]"),
+        }
+    }
+}
+
+impl From<&str> for Word {
+    fn from(s: &str) -> Self {
+        Word::Literal(s.to_string())
+    }
+}
+
+impl From<String> for Word {
+    fn from(s: String) -> Self {
+        Word::Literal(s)
+    }
+}
+
+impl From<&String> for Word {
+    fn from(s: &String) -> Self {
+        Word::Literal(s.clone())
+    }
+}
+
+impl From<&Path> for Word {
+    fn from(p: &Path) -> Self {
+        Word::Literal(p.display().to_string())
+    }
+}
+
+impl From<&PathBuf> for Word {
+    fn from(p: &PathBuf) -> Self {
+        Word::Literal(p.display().to_string())
+    }
+}
+
+impl From<PathBuf> for Word {
+    fn from(p: PathBuf) -> Self {
+        Word::Literal(p.display().to_string())
+    }
+}
+
+impl From<bool> for Word {
+    fn from(b: bool) -> Self {
+        Word::Raw(b.to_string())
+    }
+}
+
+impl From<i64> for Word {
+    fn from(n: i64) -> Self {
+        Word::Raw(n.to_string())
+    }
+}
+
+impl From<f64> for Word {
+    fn from(n: f64) -> Self {
+        Word::Raw(n.to_string())
+    }
+}
+
+impl From<Decimal> for Word {
+    fn from(n: Decimal) -> Self {
+        Word::Raw(n.to_string())
+    }
+}
+
+impl From<Command> for Word {
+    fn from(command: Command) -> Self {
+        Word::Subst(Box::new(command))
+    }
+}
+
+/// A TCL command built word by word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    pub name: String,
+    pub words: Vec<Word>,
+}
+
+impl Command {
+    pub fn new(name: impl Into<String>) -> Self {
+        Command {
+            name: name.into(),
+            words: Vec::new(),
+        }
+    }
+
+    /// Appends a quoted argument
+    pub fn arg(mut self, word: impl Into<Word>) -> Self {
+        self.words.push(word.into());
+        self
+    }
+
+    /// Appends several quoted arguments
+    pub fn args<W: Into<Word>>(mut self, words: impl IntoIterator<Item = W>) -> Self {
+        self.words.extend(words.into_iter().map(Into::into));
+        self
+    }
+
+    /// Appends a `-flag` without a value
+    pub fn flag(mut self, flag: &str) -> Self {
+        self.words.push(Word::raw(format!("-{flag}")));
+        self
+    }
+
+    /// Appends `-flag value`
+    pub fn opt(self, flag: &str, value: impl Into<Word>) -> Self {
+        self.flag(flag).arg(value)
+    }
+
+    /// Appends TCL verbatim
+    pub fn raw(mut self, tcl: impl Into<String>) -> Self {
+        self.words.push(Word::raw(tcl));
+        self
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for word in &self.words {
+            write!(f, " {word}")?;
+        }
+        Ok(())
+    }
+}
+
+/// `set_db <attribute> <value>`
+pub fn set_db(attribute: &str, value: impl Into<Word>) -> Command {
+    Command::new("set_db").raw(attribute).arg(value)
+}
+
+/// A TCL script assembled from commands, comments and verbatim blocks
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    lines: Vec<String>,
+}
+
+impl Script {
+    pub fn new() -> Self {
+        Script::default()
+    }
+
+    pub fn command(&mut self, command: Command) -> &mut Self {
+        self.lines.push(command.to_string());
+        self
+    }
+
+    pub fn set_db(&mut self, attribute: &str, value: impl Into<Word>) -> &mut Self {
+        self.command(set_db(attribute, value))
+    }
+
+    pub fn comment(&mut self, text: &str) -> &mut Self {
+        for line in text.lines() {
+            self.lines.push(format!("# {line}"));
+        }
+        self
+    }
+
+    /// Appends TCL verbatim, for blocks that are not generated from data
+    pub fn raw(&mut self, tcl: &str) -> &mut Self {
+        self.lines.push(tcl.trim_end().to_string());
+        self
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Script> for String {
+    fn from(script: Script) -> Self {
+        script.to_string()
+    }
+}
+
+/// A lexical problem found by [`check_syntax`]; `line` is 1-based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TclSyntaxError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TclSyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TclSyntaxError {}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Lexer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error<T>(&self, line: usize, message: String) -> Result<T, TclSyntaxError> {
+        Err(TclSyntaxError { line, message })
+    }
+
+    /// Consumes one character, keeping track of line numbers
+    fn bump(&mut self) {
+        if self.peek() == Some('\n') {
+            self.line += 1;
+        }
+        self.pos += 1;
+    }
+
+    /// Consumes a backslash and the character it escapes
+    fn escape(&mut self) {
+        self.bump();
+        if self.peek().is_some() {
+            self.bump();
+        }
+    }
+
+    fn at_word_end(&self, in_bracket: bool) -> bool {
+        match self.peek() {
+            None | Some(' ' | '\t' | '\n' | '\r' | ';') => true,
+            Some(']') => in_bracket,
+            _ => false,
+        }
+    }
+
+    /// Parses commands until the end of input, or until the `]` closing a command
+    /// substitution opened on `open_line`
+    fn script(&mut self, open_line: Option<usize>) -> Result<(), TclSyntaxError> {
+        loop {
+            while let Some(' ' | '\t' | '\n' | '\r' | ';') = self.peek() {
+                self.bump();
+            }
+            match self.peek() {
+                None => {
+                    return match open_line {
+                        Some(line) => self.error(line, "unclosed '[' command substitution".into()),
+                        None => Ok(()),
+                    };
+                }
+                Some(']') if open_line.is_some() => {
+                    self.bump();
+                    return Ok(());
+                }
+                Some('#') => {
+                    while let Some(c) = self.peek() {
+                        match c {
+                            '\\' => self.escape(),
+                            '\n' => break,
+                            _ => self.bump(),
+                        }
+                    }
+                }
+                Some(_) => self.command(open_line.is_some())?,
+            }
+        }
+    }
+
+    fn command(&mut self, in_bracket: bool) -> Result<(), TclSyntaxError> {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r') => self.bump(),
+                Some('\\') if self.chars.get(self.pos + 1) == Some(&'\n') => self.escape(),
+                None | Some('\n' | ';') => return Ok(()),
+                Some(']') if in_bracket => return Ok(()),
+                Some(_) => self.word(in_bracket)?,
+            }
+        }
+    }
+
+    fn word(&mut self, in_bracket: bool) -> Result<(), TclSyntaxError> {
+        let start_line = self.line;
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                let mut depth = 1;
+                while depth > 0 {
+                    match self.peek() {
+                        None => return self.error(start_line, "unclosed '{'".into()),
+                        Some('\\') => self.escape(),
+                        Some('{') => {
+                            depth += 1;
+                            self.bump();
+                        }
+                        Some('}') => {
+                            depth -= 1;
+                            self.bump();
+                        }
+                        Some(_) => self.bump(),
+                    }
+                }
+                if !self.at_word_end(in_bracket) {
+                    return self.error(self.line, "extra characters after close-brace".into());
+                }
+            }
+            Some('"') => {
+                self.bump();
+                loop {
+                    match self.peek() {
+                        None => return self.error(start_line, "unclosed '\"'".into()),
+                        Some('\\') => self.escape(),
+                        Some('[') => {
+                            let line = self.line;
+                            self.bump();
+                            self.script(Some(line))?;
+                        }
+                        Some('"') => {
+                            self.bump();
+                            break;
+                        }
+                        Some(_) => self.bump(),
+                    }
+                }
+                if !self.at_word_end(in_bracket) {
+                    return self.error(self.line, "extra characters after close-quote".into());
+                }
+            }
+            Some('}') => return self.error(start_line, "unmatched '}'".into()),
+            _ => {
+                while !self.at_word_end(in_bracket) {
+                    match self.peek() {
+                        Some('\\') => self.escape(),
+                        Some('[') => {
+                            let line = self.line;
+                            self.bump();
+                            self.script(Some(line))?;
+                        }
+                        Some('$') if self.chars.get(self.pos + 1) == Some(&'{') => {
+                            while !matches!(self.peek(), None | Some('}')) {
+                                self.bump();
+                            }
+                            if self.peek().is_none() {
+                                return self.error(start_line, "unclosed '${'".into());
+                            }
+                            self.bump();
+                        }
+                        _ => self.bump(),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that `script` is lexically well formed TCL: braces, brackets and quotes are
+/// balanced and every word is properly terminated
+pub fn check_syntax(script: &str) -> Result<(), TclSyntaxError> {
+    Lexer {
+        chars: script.chars().collect(),
+        pos: 0,
+        line: 1,
+    }
+    .script(None)
+}
+
+/// Checks that `script` is well formed TCL that can also be embedded in a braced word, as in
+/// `catch {…}`: within braces TCL counts every brace not escaped by a backslash, including
+/// those in comments and quoted strings
+pub fn check_braced_syntax(script: &str) -> Result<(), TclSyntaxError> {
+    check_syntax(script)?;
+    // lines of the braces not closed yet
+    let mut open = Vec::new();
+    let mut line = 1;
+    let mut chars = script.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // the escaped character is skipped either way
+            '\\' if chars.next() == Some('\n') => line += 1,
+            '\n' => line += 1,
+            '{' => open.push(line),
+            '}' if open.pop().is_none() => {
+                return Err(TclSyntaxError {
+                    line,
+                    message: "unmatched '}' once wrapped in braces".into(),
+                });
+            }
+            _ => {}
+        }
+    }
+    match open.last() {
+        Some(&line) => Err(TclSyntaxError {
+            line,
+            message: "unclosed '{' once wrapped in braces".into(),
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_words_that_tcl_would_split_or_substitute() {
+        assert_eq!(quote("/pdk/lib/tt.lib"), "/pdk/lib/tt.lib");
+        assert_eq!(quote(""), "{}");
+        assert_eq!(quote("/my pdk/tt.lib"), "{/my pdk/tt.lib}");
+        assert_eq!(quote("a[1]"), "{a[1]}");
+        assert_eq!(quote("$HOME"), "{$HOME}");
+        assert_eq!(quote("#x"), "{#x}");
+        assert_eq!(quote("a}b"), "a\\}b");
+        assert_eq!(quote("a{ b"), "a\\{\\ b");
+    }
+
+    #[test]
+    fn renders_commands_and_scripts() {
+        let read = Command::new("read_physical").opt(
+            "lef",
+            list([Path::new("/a b/x.lef"), Path::new("/c/y.lef")]),
+        );
+        assert_eq!(
+            read.to_string(),
+            "read_physical -lef [list {/a b/x.lef} /c/y.lef]"
+        );
+
+        let mut script = Script::new();
+        script
+            .comment("library setup")
+            .set_db("design_process_node", 130i64)
+            .command(Command::new("get_db").arg(Command::new("get_db").raw("designs")));
+        assert_eq!(
+            script.to_string(),
+            "# library setup\nset_db design_process_node 130\nget_db [get_db designs]\n"
+        );
+    }
+
+    #[test]
+    fn reports_unbalanced_tcl_with_line_numbers() {
+        assert_eq!(
+            check_syntax("set x {a {b} c}\nputs \"[lindex $x 0]\"\n# [ignored\n"),
+            Ok(())
+        );
+        assert_eq!(
+            check_syntax("set x 1\nif {$x} {\n  puts hi\n")
+                .unwrap_err()
+                .line,
+            2
+        );
+        assert_eq!(
+            check_syntax("puts \"a\nb\nputs x\n").unwrap_err(),
+            TclSyntaxError {
+                line: 1,
+                message: "unclosed '\"'".into()
+            }
+        );
+        assert_eq!(check_syntax("set x [get_db designs\n").unwrap_err().line, 1);
+        assert_eq!(check_syntax("puts a\n}\n").unwrap_err().line, 2);
+        assert_eq!(
+            check_syntax("puts {a}b\n").unwrap_err().message,
+            "extra characters after close-brace"
+        );
+    }
+}
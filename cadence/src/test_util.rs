@@ -0,0 +1,23 @@
+use crate::innovus::HardMacroConstraint;
+
+/// An instance `name` of `master` at (`x`, `y`) with halos of 2 microns and a met1-met3 stackup
+pub(crate) fn macro_at(
+    master: &str,
+    name: &str,
+    x: f64,
+    y: f64,
+    orientation: &str,
+) -> HardMacroConstraint {
+    HardMacroConstraint {
+        x,
+        y,
+        stackup: vec!["met1".into(), "met2".into(), "met3".into()],
+        route_halo_size: 2.0,
+        place_halo_size: 2.0,
+        top_layer: "met3".into(),
+        orientation: orientation.into(),
+        create_physical: false,
+        master: master.into(),
+        name: name.into(),
+    }
+}
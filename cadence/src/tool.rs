@@ -0,0 +1,485 @@
+use std::fmt::Debug;
+use std::fmt::Write as FmtWrite;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fs, io};
+
+use crate::checkpoint::{
+    CheckpointInfo, PrunePolicy, latest_checkpoint, list_checkpoints, prune_checkpoints,
+    validate_checkpoint,
+};
+use crate::log::{LogPolicy, parse_log, run_logged, substep_marker_tcl};
+use crate::substep::{SubstepError, SubstepList};
+use crate::tcl::{TclSyntaxError, check_braced_syntax, check_syntax, quote};
+use crate::{Checkpoint, Substep, format_paths, missing_outputs, read_error_log, trap_tcl_errors};
+use fs::File;
+use rivet::Step;
+
+/// Describes how to script and launch a Cadence tool
+#[derive(Debug, Clone)]
+pub struct ToolDescriptor {
+    /// Executable launched in the work dir, e.g. `genus`
+    pub binary: String,
+    /// Name of the generated script, e.g. `syn.tcl`
+    pub script_name: String,
+    /// Name of the captured tool log, e.g. `syn.log`
+    pub log_name: String,
+    /// Flag that passes the script to the tool, e.g. `-f`
+    pub script_flag: String,
+    /// Extra command line arguments
+    pub args: Vec<String>,
+    /// TCL emitted at the top of the script before any checkpoint is read
+    pub preamble: Vec<String>,
+    /// Command used to save a checkpoint, followed by the checkpoint path
+    pub write_db: String,
+    /// Command used to restore a checkpoint, followed by the checkpoint path
+    pub read_db: String,
+    /// Command that ends the script
+    pub exit: String,
+}
+
+/// Outcome of the last run of a tool step, as found in its work dir
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunStatus {
+    NotRun,
+    /// The run failed, in `substep` if the failure was trapped in one
+    Failed {
+        substep: Option<String>,
+        message: String,
+    },
+    Completed,
+}
+
+/// Shared core of the Cadence tool steps: substeps, checkpoints, script generation,
+/// tool invocation and post-run checks
+#[derive(Debug, Clone)]
+pub struct CadenceToolStep {
+    pub tool: ToolDescriptor,
+    pub work_dir: PathBuf,
+    pub module: String,
+    pub substeps: SubstepList,
+    pub pinned: bool,
+    pub start_checkpoint: Option<Checkpoint>,
+    pub endpoint: Option<String>,
+    pub resume: bool,
+    pub dependencies: Vec<Arc<dyn Step>>,
+    pub expected_outputs: Vec<PathBuf>,
+    pub log_policy: LogPolicy,
+}
+
+impl CadenceToolStep {
+    pub fn new(
+        tool: ToolDescriptor,
+        work_dir: impl Into<PathBuf>,
+        module: impl Into<String>,
+        substeps: Vec<Substep>,
+        pinned: bool,
+        deps: Vec<Arc<dyn Step>>,
+    ) -> Self {
+        CadenceToolStep {
+            tool,
+            work_dir: work_dir.into(),
+            module: module.into(),
+            substeps: substeps.into(),
+            pinned,
+            start_checkpoint: None,
+            endpoint: None,
+            resume: false,
+            dependencies: deps,
+            expected_outputs: Vec::new(),
+            log_policy: LogPolicy::default(),
+        }
+    }
+
+    pub fn script_path(&self) -> PathBuf {
+        self.work_dir.join(&self.tool.script_name)
+    }
+
+    pub fn error_log_path(&self) -> PathBuf {
+        self.work_dir.join("rivet_error.log")
+    }
+
+    pub fn log_path(&self) -> PathBuf {
+        self.work_dir.join(&self.tool.log_name)
+    }
+
+    /// File recording the exit code of the last tool run, written once the tool exits
+    pub fn exit_status_path(&self) -> PathBuf {
+        self.work_dir.join("rivet_exit_status")
+    }
+
+    /// Reads the outcome of the last run from its log, error log, exit code and expected
+    /// outputs
+    pub fn run_status(&self) -> RunStatus {
+        if !self.log_path().exists() {
+            return RunStatus::NotRun;
+        }
+        if let Some((substep, message)) = read_error_log(&self.error_log_path()) {
+            return RunStatus::Failed {
+                substep: Some(substep),
+                message,
+            };
+        }
+        let failed = |message: String| RunStatus::Failed {
+            substep: None,
+            message,
+        };
+        match fs::read_to_string(self.exit_status_path()) {
+            Err(_) => return failed(format!("{} did not exit", self.tool.binary)),
+            Ok(code) if code.trim() != "0" => {
+                return failed(format!(
+                    "{} exited with status {}",
+                    self.tool.binary,
+                    code.trim()
+                ));
+            }
+            Ok(_) => {}
+        }
+        let missing = missing_outputs(&self.expected_outputs);
+        if !missing.is_empty() {
+            return failed(format!("missing outputs:\n{}", format_paths(&missing)));
+        }
+        RunStatus::Completed
+    }
+
+    /// Wall time of the last run, from the script being written to the log last being
+    /// appended to
+    pub fn runtime(&self) -> Option<Duration> {
+        let modified = |path: PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+        let start = modified(self.script_path())?;
+        modified(self.log_path())?.duration_since(start).ok()
+    }
+
+    /// Generates the tool script running `substeps`, optionally after reading `start_checkpoint`
+    fn make_tcl_file(
+        &self,
+        start_checkpoint: Option<&Checkpoint>,
+        substeps: Vec<Substep>,
+    ) -> io::Result<()> {
+        let script_name = &self.tool.script_name;
+        fs::create_dir_all(&self.work_dir)?;
+        let mut tcl_file = File::create(self.script_path())
+            .unwrap_or_else(|_| panic!("failed to create {script_name} file"));
+
+        let error_log = self.error_log_path();
+        File::create(&error_log).expect("failed to create rivet_error.log file");
+        if let Err(e) = fs::remove_file(self.exit_status_path())
+            && e.kind() != io::ErrorKind::NotFound
+        {
+            return Err(e);
+        }
+        for line in &self.tool.preamble {
+            writeln!(tcl_file, "{line}")?;
+        }
+
+        if let Some(checkpoint) = start_checkpoint {
+            writeln!(
+                tcl_file,
+                "{}",
+                trap_tcl_errors(
+                    &format!("read_db_{}", checkpoint.name),
+                    &self.read_db_tcl(checkpoint),
+                    &error_log
+                )
+            )?;
+        }
+
+        for step in substeps.into_iter() {
+            println!("\n--> Parsing step: {}\n", step.name);
+            writeln!(tcl_file, "{}", substep_marker_tcl(&step.name))?;
+            writeln!(
+                tcl_file,
+                "{}",
+                trap_tcl_errors(&step.name, &self.substep_tcl(&step), &error_log)
+            )?;
+        }
+        writeln!(tcl_file, "{}", self.tool.exit)?;
+
+        println!("\nFinished creating tcl file\n");
+        Ok(())
+    }
+
+    fn read_db_tcl(&self, checkpoint: &Checkpoint) -> String {
+        format!(
+            "{} {}",
+            self.tool.read_db,
+            quote(&checkpoint.path.display().to_string())
+        )
+    }
+
+    /// The TCL of `step`, followed by writing its checkpoint if it has one
+    fn substep_tcl(&self, step: &Substep) -> String {
+        let mut command = step.command.clone();
+        if step.checkpoint {
+            let checkpoint_file = self.work_dir.join(format!("post_{}", step.name));
+            writeln!(
+                command,
+                "\n{} {}",
+                self.tool.write_db,
+                quote(&checkpoint_file.display().to_string())
+            )
+            .unwrap();
+        }
+        command
+    }
+
+    /// Lexically checks the TCL of each substep, including that it stays balanced once
+    /// wrapped in the braces of its error trap, so that malformed TCL is reported before the
+    /// tool is launched, naming the substep and the line within it
+    pub fn check_tcl(
+        &self,
+        start_checkpoint: Option<&Checkpoint>,
+        substeps: &[Substep],
+    ) -> Result<(), String> {
+        let report = |name: &str, e: TclSyntaxError| {
+            format!("substep '{name}' line {}: {}", e.line, e.message)
+        };
+        let preamble = self.tool.preamble.join("\n");
+        check_syntax(&preamble).map_err(|e| report("<preamble>", e))?;
+        let read_db =
+            start_checkpoint.map(|c| (format!("read_db_{}", c.name), self.read_db_tcl(c)));
+        let trapped = read_db.into_iter().chain(
+            substeps
+                .iter()
+                .map(|s| (s.name.clone(), self.substep_tcl(s))),
+        );
+        for (name, tcl) in trapped {
+            check_braced_syntax(&tcl).map_err(|e| report(&name, e))?;
+        }
+        Ok(())
+    }
+
+    /// Inserts a custom command as a substep in the flow
+    pub fn add_hook(
+        &mut self,
+        name: &str,
+        tcl: &str,
+        after_substep: &str,
+        checkpointed: bool,
+    ) -> Result<(), SubstepError> {
+        self.substeps.insert_after(
+            after_substep,
+            Substep {
+                name: name.to_string(),
+                command: tcl.to_string(),
+                checkpoint: checkpointed,
+            },
+        )
+    }
+
+    /// Replaces a specfic substep in the flow with a new command
+    pub fn replace_hook(
+        &mut self,
+        new_substep_name: &str,
+        tcl: &str,
+        replaced_substep_name: &str,
+        checkpointed: bool,
+    ) -> Result<(), SubstepError> {
+        self.substeps
+            .replace(
+                replaced_substep_name,
+                Substep {
+                    name: new_substep_name.to_string(),
+                    command: tcl.to_string(),
+                    checkpoint: checkpointed,
+                },
+            )
+            .map(|_| ())
+    }
+
+    /// Deletes a specfic substep in the flow
+    pub fn delete_hook(&mut self, deleted_substep_name: &str) -> Result<(), SubstepError> {
+        self.substeps.remove(deleted_substep_name).map(|_| ())
+    }
+
+    /// Assigns the starting checkpoint of the flow
+    pub fn add_checkpoint(&mut self, name: &str, checkpoint_path: PathBuf) {
+        self.start_checkpoint = Some(Checkpoint {
+            name: name.to_string(),
+            path: checkpoint_path,
+        });
+    }
+
+    pub fn add_endpoint(&mut self, name: &str) {
+        self.endpoint = Some(name.to_string());
+    }
+
+    /// Restarts the flow from the latest valid checkpoint in the work dir when no
+    /// starting checkpoint was assigned explicitly
+    pub fn enable_resume(&mut self) {
+        self.resume = true;
+    }
+
+    /// Returns the checkpoint the flow will start from
+    pub fn effective_start_checkpoint(&self) -> Option<Checkpoint> {
+        if self.start_checkpoint.is_some() || !self.resume {
+            return self.start_checkpoint.clone();
+        }
+        latest_checkpoint(&self.work_dir, &self.substeps)
+    }
+
+    /// Lists the checkpoints written to the work dir, oldest first
+    pub fn checkpoints(&self) -> io::Result<Vec<CheckpointInfo>> {
+        list_checkpoints(&self.work_dir)
+    }
+
+    /// Deletes checkpoints not retained by `policy`
+    pub fn prune_checkpoints(&self, policy: &PrunePolicy) -> io::Result<Vec<PathBuf>> {
+        prune_checkpoints(&self.work_dir, policy)
+    }
+
+    /// Sets which log messages fail the step even if the tool exits successfully
+    pub fn set_log_policy(&mut self, policy: LogPolicy) {
+        self.log_policy = policy;
+    }
+
+    /// Declares a file or directory the flow must produce
+    pub fn add_expected_output(&mut self, path: impl Into<PathBuf>) {
+        self.expected_outputs.push(path.into());
+    }
+}
+
+impl Step for CadenceToolStep {
+    fn execute(&self) {
+        let script_name = &self.tool.script_name;
+        let start_checkpoint = self.effective_start_checkpoint();
+        if let Some(checkpoint) = &start_checkpoint {
+            if self.start_checkpoint.is_none() {
+                println!(
+                    "\nResuming {} from checkpoint {}\n",
+                    self.module, checkpoint.name
+                );
+            }
+            if let Err(e) = validate_checkpoint(checkpoint, &self.substeps) {
+                panic!("Invalid starting checkpoint for {script_name}: {e}");
+            }
+        }
+        let substeps = self
+            .substeps
+            .slice(
+                start_checkpoint.as_ref().map(|c| c.name.as_str()),
+                self.endpoint.as_deref(),
+            )
+            .unwrap_or_else(|e| panic!("Invalid endpoint for {script_name}: {e}"));
+
+        if let Err(e) = self.check_tcl(start_checkpoint.as_ref(), &substeps) {
+            panic!("Malformed TCL, not launching {}: {e}", self.tool.binary);
+        }
+        self.make_tcl_file(start_checkpoint.as_ref(), substeps)
+            .unwrap_or_else(|_| panic!("Failed to create {script_name}"));
+
+        let status = run_logged(
+            Command::new(&self.tool.binary)
+                .arg(&self.tool.script_flag)
+                .arg(self.script_path())
+                .args(&self.tool.args)
+                .current_dir(self.work_dir.clone()),
+            &self.log_path(),
+        )
+        .unwrap_or_else(|_| panic!("Failed to execute {script_name}"));
+        let code = status
+            .code()
+            .map_or_else(|| "killed".to_owned(), |c| c.to_string());
+        fs::write(self.exit_status_path(), code).expect("Failed to record the tool exit status");
+
+        if !status.success() {
+            if let Some((substep, message)) = read_error_log(&self.error_log_path()) {
+                panic!("{script_name} failed in substep '{substep}': {message}");
+            }
+            eprintln!("Failed to execute {script_name}");
+            panic!("Stopped flow");
+        }
+
+        let log = fs::read_to_string(self.log_path())
+            .unwrap_or_else(|_| panic!("Failed to read {}", self.tool.log_name));
+        let summary = parse_log(&log);
+        println!("\n{script_name} log summary: {summary}");
+        let violations = self.log_policy.violations(&summary);
+        if !violations.is_empty() {
+            let listed: Vec<String> = violations
+                .iter()
+                .map(|m| {
+                    format!(
+                        "  {} ({}) in {}: {}",
+                        m.severity,
+                        m.id,
+                        m.substep.as_deref().unwrap_or("<preamble>"),
+                        m.text
+                    )
+                })
+                .collect();
+            panic!(
+                "{script_name} log contains messages that fail the flow:\n{}",
+                listed.join("\n")
+            );
+        }
+
+        let missing = missing_outputs(&self.expected_outputs);
+        if !missing.is_empty() {
+            panic!(
+                "{script_name} exited successfully but did not produce expected outputs:\n{}",
+                format_paths(&missing)
+            );
+        }
+    }
+
+    fn deps(&self) -> Vec<Arc<dyn Step>> {
+        self.dependencies.clone()
+    }
+
+    fn pinned(&self) -> bool {
+        self.pinned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::innovus::innovus_tool;
+
+    #[test]
+    fn check_tcl_names_the_malformed_substep() {
+        let ok = |name: &str| Substep {
+            name: name.into(),
+            command: "set_db design_process_node 130\nputs {done}".into(),
+            checkpoint: true,
+        };
+        let mut step = CadenceToolStep::new(
+            innovus_tool(false),
+            "/tmp/par",
+            "top",
+            vec![ok("init"), ok("place")],
+            false,
+            vec![],
+        );
+        assert_eq!(step.check_tcl(None, &step.substeps), Ok(()));
+
+        for (hook, message) in [
+            (
+                "puts ok\nforeach x {a b {\n  puts $x\n}",
+                "line 2: unclosed '{'",
+            ),
+            ("puts ok\nputs \"hello", "line 2: unclosed '\"'"),
+            (
+                "puts ok\nputs [get_db foo",
+                "line 2: unclosed '[' command substitution",
+            ),
+            ("puts ok\n}", "line 2: unmatched '}'"),
+            (
+                "puts ok\n# }\nputs done",
+                "line 2: unmatched '}' once wrapped in braces",
+            ),
+            ("puts \"{\"", "line 1: unclosed '{' once wrapped in braces"),
+        ] {
+            step.add_hook("hook", hook, "init", false).unwrap();
+            assert_eq!(
+                step.check_tcl(None, &step.substeps),
+                Err(format!("substep 'hook' {message}"))
+            );
+            step.delete_hook("hook").unwrap();
+        }
+    }
+}
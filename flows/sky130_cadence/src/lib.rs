@@ -1,19 +1,39 @@
+pub mod report;
+
+use crate::report::write_html_report;
+use cadence::budget::{BudgetPolicy, budget_child, connected_clock_ports};
+use cadence::floorplan::{FloorplanErrors, FloorplanProblem, lef_macro_sizes, validate_floorplan};
 use cadence::genus::{
     DesignFiles as GenusDesignFiles, GenusStep, add_tieoffs, dont_avoid_lib_cells, elaborate,
     power_intent, set_default_options, syn_generic, syn_init_design, syn_map,
     syn_read_design_files, syn_write_design,
 };
 use cadence::innovus::{
-    DesignFiles as InnovusDesignFiles, Floorplan, HardMacroConstraint, InnovusStep, Layer,
-    PinAssignment, TopLevelConstraint, add_fillers, floorplan_design, innovus_settings, opt_design,
-    par_init_design, par_read_design_files, place_opt_design, place_pins, power_straps,
-    route_design, set_default_process, write_ilm, write_regs,
+    CtsSpec, DesignFiles as InnovusDesignFiles, Floorplan, HardMacroConstraint, InnovusStep,
+    PinPlacement, PinPlanError, PinSelector, PinSpread, Side, TopLevelConstraint, add_fillers,
+    clock_tree, floorplan_design, innovus_settings, opt_design, par_init_design,
+    par_read_design_files, par_write_reports, place_opt_design, place_pins, place_tap_cells,
+    power_straps, route_design, set_default_process, write_ilm, write_regs,
+};
+use cadence::pegasus::{PegasusStep, read_drc_violations};
+use cadence::pin_alignment::{
+    AlignmentError, AlignmentSpec, BlockOutline, NetEnd, ParentNet, align_block_pins,
 };
-use cadence::{MmmcConfig, MmmcCorner, SubmoduleInfo, Substep};
+use cadence::power_grid::{GridTarget, PowerGrid};
+use cadence::qor::QorError;
+use cadence::regression::{ModuleQor, RunManifest};
+use cadence::sdc::{Clock, ClockRelation, Constraints, PortGroup, SdcError};
+use cadence::tcl::{Command, Script, list};
+use cadence::{MmmcConfig, SubmoduleInfo, Substep};
 use indoc::formatdoc;
 use rivet::bash::BashStep;
 use rivet::{Dag, NamedNode, Step, StepRef, execute, hierarchical};
-use sky130::{setup_techlef, sky130_connect_nets};
+use sky130::corners::{CellLibrary, CornerSet, sky130_mmmc};
+use sky130::{
+    setup_techlef, sky130_connect_nets, sky130_floorplan_rules, sky130_pin_alignment,
+    sky130_power_grid, sky130_sized_power_grid, sky130_tap_cells,
+};
+use std::fmt;
 use std::fs;
 use std::io::Write;
 use std::os::unix::fs::PermissionsExt;
@@ -23,6 +43,7 @@ use std::sync::Arc;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
+#[derive(Clone)]
 pub struct ModuleInfo {
     pub module_name: String,
     pub pin_info: FlatPinInfo,
@@ -30,7 +51,17 @@ pub struct ModuleInfo {
     pub srams: Vec<Sram22>,
     pub placement_constraints: Floorplan,
     pub floorplan_commands: String,
-    pub sdc: String,
+    pub sdc: Constraints,
+    /// Pin placement of the block; derived from its parent by [`align_hierarchy_pins`] when
+    /// empty, or all pins go on the bottom side
+    pub pin_plan: Vec<PinPlacement>,
+    /// Supply current and IR drop the power grid is sized for; the nominal grid is used when
+    /// `None`. [`GridTarget::from_power`] derives one from the power report of a previous run.
+    pub grid_target: Option<GridTarget>,
+    /// Ports of the block
+    pub ports: Vec<String>,
+    /// Connections of the hard macros of the block to each other and to its ports
+    pub nets: Vec<ParentNet>,
 }
 
 #[derive(Clone, Debug)]
@@ -155,76 +186,103 @@ fn sky130_par_read_design_files(files: InnovusDesignFiles, srams: &[Sram22]) ->
     })
 }
 
-fn sky130_scl_cadence_par_write_design(
-    pdk_root: &Path,
+/// Writes the final database, netlists, GDS (merged with `pdk_gds` and the SRAM macros), SDF
+/// and SPEF of `module` into `work_dir`
+fn sky130_cadence_par_write_design(
+    pdk_gds: PathBuf,
     work_dir: &Path,
     module: &str,
     srams: &[Sram22],
-    corners: Vec<MmmcCorner>,
+    mmmc: &MmmcConfig,
 ) -> Substep {
-    let par_rundir = work_dir.display().to_string();
-    let module = module.to_owned();
-    let setup = corners
-        .iter()
-        .find(|p| p.corner_type == "setup")
-        .unwrap()
-        .name
-        .clone();
-    let hold = corners
-        .iter()
-        .find(|p| p.corner_type == "hold")
-        .unwrap()
-        .name
-        .clone();
-    let typical = corners
-        .iter()
-        .find(|p| p.corner_type == "extra")
-        .unwrap()
-        .name
-        .clone();
+    let out = |suffix: &str| work_dir.join(format!("{module}{suffix}"));
+    let (setup, hold, typical) = (mmmc.primary_setup(), mmmc.primary_hold(), mmmc.dynamic());
 
     let sram_work_dir = work_dir.parent().unwrap().join("sram");
-    let pdk_gds = pdk_root
-        .join("sky130/sky130_cds/sky130_scl_9T_0.0.5/gds/sky130_scl_9T.gds")
-        .display()
-        .to_string();
     let mut merge_gds = vec![pdk_gds];
-    merge_gds.extend(
-        srams
-            .iter()
-            .map(|s| s.gds(&sram_work_dir).display().to_string()),
-    );
-    let merge_str = merge_gds.join(" ");
+    merge_gds.extend(srams.iter().map(|s| s.gds(&sram_work_dir)));
 
     let lefpin_map = concat!(
         env!("CARGO_MANIFEST_DIR"),
         "/../../pdks/sky130/src/sky130_lefpin.map"
     );
+    let mut script = Script::new();
+    script
+        .set_db("timing_enable_simultaneous_setup_hold_mode", true)
+        .command(
+            Command::new("write_db")
+                .arg(format!("{module}_FINAL"))
+                .flag("def")
+                .flag("verilog"),
+        )
+        .set_db("timing_enable_simultaneous_setup_hold_mode", false)
+        .set_db("write_stream_virtual_connection", false)
+        .command(
+            Command::new("write_netlist")
+                .arg(out(".lvs.v"))
+                .flag("top_module_first")
+                .opt("top_module", module)
+                .flag("exclude_leaf_cells")
+                .flag("phys")
+                .flag("flat")
+                .opt("exclude_insts_of_cells", ""),
+        )
+        .command(
+            Command::new("write_netlist")
+                .arg(out(".sim.v"))
+                .flag("top_module_first")
+                .opt("top_module", module)
+                .flag("exclude_leaf_cells")
+                .opt("exclude_insts_of_cells", ""),
+        )
+        .command(
+            Command::new("write_stream")
+                .opt("mode", "ALL")
+                .opt("format", "stream")
+                .opt("map_file", lefpin_map)
+                .flag("uniquify_cell_names")
+                .opt("merge", list(merge_gds))
+                .arg(out(".gds")),
+        )
+        .command(
+            Command::new("write_sdf")
+                .opt("max_view", setup.name())
+                .opt("min_view", hold.name())
+                .opt("typical_view", typical.name())
+                .arg(out(".par.sdf")),
+        )
+        .set_db("extract_rc_coupled", true)
+        .command(Command::new("extract_rc"));
+    for view in [setup, hold, typical] {
+        script.command(
+            Command::new("write_parasitics")
+                .opt("spef_file", out(&format!(".{}.par.spef", view.corner.name)))
+                .opt("rc_corner", view.corner.rc_corner_name()),
+        );
+    }
+    script
+        .command(Command::new("write_db").arg("post_write_design"))
+        .raw("ln -sfn post_write_design latest");
+
     Substep {
         checkpoint: true,
-        command: formatdoc!(
-            r#"
-            set_db timing_enable_simultaneous_setup_hold_mode true
-            write_db {module}_FINAL -def -verilog
-            set_db timing_enable_simultaneous_setup_hold_mode false
-            set_db write_stream_virtual_connection false
-            write_netlist {par_rundir}/{module}.lvs.v -top_module_first -top_module {module} -exclude_leaf_cells -phys -flat -exclude_insts_of_cells {{}}
-            write_netlist {par_rundir}/{module}.sim.v -top_module_first -top_module {module} -exclude_leaf_cells -exclude_insts_of_cells {{}}
-            write_stream -mode ALL -format stream -map_file {lefpin_map} -uniquify_cell_names -merge {{ {merge_str} }} {par_rundir}/{module}.gds
-            write_sdf -max_view {setup}.setup_view -min_view {hold}.hold_view -typical_view {typical}.extra_view {par_rundir}/{module}.par.sdf
-            set_db extract_rc_coupled true
-            extract_rc
-            write_parasitics -spef_file {par_rundir}/{module}.{setup}.par.spef -rc_corner {setup}.setup_rc
-            write_parasitics -spef_file {par_rundir}/{module}.{hold}.par.spef -rc_corner {hold}.hold_rc
-            write_parasitics -spef_file {par_rundir}/{module}.{typical}.par.spef -rc_corner {typical}.extra_rc
-            write_db post_write_design
-            ln -sfn post_write_design latest
-            "#
-        ),
+        command: script.to_string(),
         name: "write_design".into(),
     }
 }
 
+fn sky130_scl_cadence_par_write_design(
+    pdk_root: &Path,
+    work_dir: &Path,
+    module: &str,
+    srams: &[Sram22],
+    mmmc: &MmmcConfig,
+) -> Substep {
+    let pdk_gds = pdk_root.join("sky130/sky130_cds/sky130_scl_9T_0.0.5/gds/sky130_scl_9T.gds");
+    sky130_cadence_par_write_design(pdk_gds, work_dir, module, srams, mmmc)
+}
+
+#[derive(Clone)]
 pub enum FlatPinInfo {
     None,
     PinSyn(PathBuf),
@@ -235,15 +293,76 @@ pub struct Sky130FlatFlow {
     pub module: String,
     pub syn: StepRef<GenusStep>,
     pub par: StepRef<InnovusStep>,
+    /// Pegasus DRC of the GDS written by place and route
+    pub drc: StepRef<PegasusStep>,
+    /// Pegasus LVS of the GDS written by place and route
+    pub lvs: StepRef<PegasusStep>,
     pub submodules: Vec<SubmoduleInfo>,
 }
 
+/// A Pegasus `func` run on the GDS written by `par`, in its work dir
+fn sky130_signoff(par: &StepRef<InnovusStep>, func: &str) -> StepRef<PegasusStep> {
+    let (work_dir, module) = {
+        let par = par.get();
+        (par.work_dir.clone(), par.module.clone())
+    };
+    StepRef::new(PegasusStep::new(
+        work_dir,
+        func.into(),
+        module,
+        false,
+        vec![Arc::new(par.clone()) as Arc<dyn Step>],
+    ))
+}
+
+/// Runs place and route of every module of `flow`, then the DRC and LVS of each module,
+/// children first, so that the report and the QoR manifest cover sign-off
+pub fn execute_with_signoff(flow: &Dag<Sky130FlatFlow>) {
+    fn signoff(flow: &Dag<Sky130FlatFlow>) {
+        for child in &flow.directed_edges {
+            signoff(child);
+        }
+        // Place and route already ran, so only the sign-off steps themselves are executed
+        flow.node.drc.execute();
+        flow.node.lvs.execute();
+    }
+    execute(flow.node.par.clone());
+    signoff(flow);
+}
+
 impl NamedNode for Sky130FlatFlow {
     fn name(&self) -> String {
         self.module.clone()
     }
 }
 
+/// Collects the place and route QoR and DRC violations of every module of `flow`, to compare
+/// against a baseline run with [`cadence::regression::compare`]
+pub fn qor_manifest(flow: &Dag<Sky130FlatFlow>) -> Result<RunManifest, QorError> {
+    fn collect(flow: &Dag<Sky130FlatFlow>, manifest: &mut RunManifest) -> Result<(), QorError> {
+        for child in &flow.directed_edges {
+            collect(child, manifest)?;
+        }
+        if manifest.get(&flow.node.module).is_some() {
+            return Ok(());
+        }
+        let par = flow.node.par.get();
+        let qor = match par.qor.get() {
+            Some(qor) => qor.clone(),
+            None => par.read_qor()?,
+        };
+        let drc = flow.node.drc.get();
+        let violations = read_drc_violations(&drc.work_dir, &drc.module)?;
+        manifest
+            .modules
+            .push(ModuleQor::from_qor(&flow.node.module, &qor, violations));
+        Ok(())
+    }
+    let mut manifest = RunManifest::default();
+    collect(flow, &mut manifest)?;
+    Ok(manifest)
+}
+
 pub struct SclSynConfig<'a> {
     pub pdk_root: &'a Path,
     pub work_dir: &'a PathBuf,
@@ -254,7 +373,7 @@ pub struct SclSynConfig<'a> {
     pub dep_info: &'a [(&'a ModuleInfo, &'a Sky130FlatFlow)],
     pub submodules: Vec<SubmoduleInfo>,
     pub pin_info: &'a FlatPinInfo,
-    pub sdc: &'a str,
+    pub sdc: &'a Constraints,
 }
 
 pub struct SclParConfig<'a> {
@@ -267,54 +386,20 @@ pub struct SclParConfig<'a> {
     pub submodules: Vec<SubmoduleInfo>,
     pub pin_info: &'a FlatPinInfo,
     pub syn_step: StepRef<GenusStep>,
-    pub sdc: &'a str,
+    pub sdc: &'a Constraints,
+    /// Pin placement of the block; all pins go on the bottom side when empty
+    pub pin_plan: &'a [PinPlacement],
+    pub grid_target: Option<&'a GridTarget>,
 }
 
 pub fn sky130_scl_cadence_syn(config: SclSynConfig<'_>) -> GenusStep {
     let SclSynConfig { pdk_root, work_dir, module, verilog_paths, srams, sram_work_dir, dep_info, submodules, pin_info, sdc } = config;
-    let ss_100c_1v60 = MmmcCorner {
-        name: "ss_100c_1v60".to_string(),
-        corner_type: "setup".to_string(),
-        libs: vec![
-            pdk_root.join("sky130/sky130_cds/sky130_scl_9T_0.0.5/lib/sky130_ss_1.62_125_nldm.lib"),
-        ],
-        temperature: dec!(100.0),
-    };
-    let ff_n40c_1v95 = MmmcCorner {
-        name: "ff_n40c_1v95".to_string(),
-        corner_type: "hold".to_string(),
-        libs: vec![
-            pdk_root.join("sky130/sky130_cds/sky130_scl_9T_0.0.5/lib/sky130_ff_1.98_0_nldm.lib"),
-        ],
-        temperature: dec!(-40.0),
-    };
-
-    let tt_025c_1v80 = MmmcCorner {
-        name: "tt_025c_1v80".to_string(),
-        corner_type: "extra".to_string(),
-        libs: vec![
-            pdk_root.join("sky130/sky130_cds/sky130_scl_9T_0.0.5/lib/sky130_tt_1.8_25_nldm.lib"),
-        ],
-        temperature: dec!(25.0),
-    };
-
-    let syn_con = MmmcConfig {
-        sdc_files: vec![work_dir.join("clock_pin_constraints.sdc")],
-
-        corners: vec![
-            ss_100c_1v60.clone(),
-            ff_n40c_1v95.clone(),
-            tt_025c_1v80.clone(),
-        ],
-
-        setup: vec![ss_100c_1v60.clone()],
-
-        hold: vec![ff_n40c_1v95.clone(), tt_025c_1v80.clone()],
-
-        dynamic: tt_025c_1v80.clone(),
-
-        leakage: tt_025c_1v80.clone(),
-    };
+    let syn_con = sky130_mmmc(
+        CellLibrary::Scl9T,
+        pdk_root,
+        CornerSet::Signoff,
+        vec![work_dir.join("clock_pin_constraints.sdc")],
+    );
     fs::create_dir_all(work_dir.join("checkpoints/")).expect("Failed to create directory");
 
     let tlef = setup_techlef(
@@ -368,14 +453,14 @@ pub fn sky130_scl_cadence_syn(config: SclSynConfig<'_>) -> GenusStep {
                 GenusDesignFiles {
                     work_dir,
                     verilog_paths,
-                    mmmc_conf: syn_con,
+                    mmmc_conf: syn_con.clone(),
                     tlef: &tlef,
                     pdk_lef: &pdk_root
                         .join("sky130/sky130_cds/sky130_scl_9T_0.0.5/lef/sky130_scl_9T.lef"),
                     submodules: Some(submodules.clone()),
                     is_hierarchical,
                     hard_macros: &[],
-                    sdc_content: sdc,
+                    sdc_content: &sdc.to_string(),
                 },
                 srams,
             ),
@@ -385,15 +470,25 @@ pub fn sky130_scl_cadence_syn(config: SclSynConfig<'_>) -> GenusStep {
             syn_generic(),
             syn_map(),
             add_tieoffs("sky130_fd_sc_hd__conb_1", "sky130_fd_sc_hd__conb_1"),
-            syn_write_design(module, ss_100c_1v60.clone(), is_hierarchical),
+            syn_write_design(
+                module,
+                syn_con.primary_setup().corner.clone(),
+                is_hierarchical,
+            ),
         ],
         matches!(pin_info, FlatPinInfo::PinSyn(_)),
         deps,
     )
 }
 
-pub fn sky130_scl_cadence_par(config: SclParConfig<'_>) -> InnovusStep {
-    let SclParConfig { pdk_root, work_dir, module, constraints, netlist, srams, submodules, pin_info, syn_step, sdc } = config;
+pub fn sky130_scl_cadence_par(config: SclParConfig<'_>) -> Result<InnovusStep, ModulePinPlanError> {
+    let SclParConfig { pdk_root, work_dir, module, constraints, netlist, srams, submodules, pin_info, syn_step, sdc, pin_plan, grid_target } = config;
+    let pins = place_pins("5", "1", module, &pin_plan_or_default(pin_plan)).map_err(|error| {
+        ModulePinPlanError {
+            module: module.clone(),
+            error,
+        }
+    })?;
     let filler_cells = vec![
         "FILL0".into(),
         "FILL1".into(),
@@ -404,87 +499,16 @@ pub fn sky130_scl_cadence_par(config: SclParConfig<'_>) -> InnovusStep {
         "FILL36".into(),
     ];
 
-    let assignment = PinAssignment {
-        pins: "*".into(),
-        module: module.into(),
-        patterns: "-spread_type range".into(),
-        layer: "-layer {met4}".into(),
-        side: "-side bottom".into(),
-        start: "-start {30 0}".into(),
-        end: "-end {0 0}".into(),
-        assign: "".into(),
-        width: "".into(),
-        depth: "".into(),
-    };
-
-    let layers = vec![
-        Layer {
-            top: "met1".into(),
-            bot: "met1".into(),
-            spacing: dec!(4.000),
-            trim_antenna: false,
-            add_stripes_command: r#"add_stripes -nets {VDD VSS} -layer met1 -direction horizontal -start_offset -.2 -width .4 -spacing 3.74 -set_to_set_distance 8.28 -start_from bottom -switch_layer_over_obs false -max_same_layer_jog_length 2 -pad_core_ring_top_layer_limit met5 -pad_core_ring_bottom_layer_limit met1 -block_ring_top_layer_limit met5 -block_ring_bottom_layer_limit met1 -use_wire_group 0 -snap_wire_center_to_grid none"#.to_string(),
-        },
-
-        Layer {
-            top: "met4".to_string(),
-            bot: "met1".to_string(),
-            spacing: dec!(2.000),
-            trim_antenna: true,
-            add_stripes_command: r#"add_stripes -create_pins 0 -block_ring_bottom_layer_limit met4 -block_ring_top_layer_limit met1 -direction vertical -layer met4 -nets {VSS VDD} -pad_core_ring_bottom_layer_limit met1 -set_to_set_distance 75.90 -spacing 3.66 -switch_layer_over_obs 0 -width 1.86 -area [get_db designs .core_bbox] -start [expr [lindex [lindex [get_db designs .core_bbox] 0] 0] + 7.35]"#.to_string(),
-        },
-        Layer {
-            top: "met5".to_string(),
-            bot: "met4".to_string(),
-            spacing: dec!(2.000),
-            trim_antenna: true,
-            add_stripes_command: r#"add_stripes -create_pins 1 -block_ring_bottom_layer_limit met5 -block_ring_top_layer_limit met4 -direction horizontal -layer met5 -nets {VSS VDD} -pad_core_ring_bottom_layer_limit met4 -set_to_set_distance 225.40 -spacing 17.68 -switch_layer_over_obs 0 -width 1.64 -area [get_db designs .core_bbox] -start [expr [lindex [lindex [get_db designs .core_bbox] 0] 1] + 5.62]"#.to_string(),
-        }
-    ];
-
-    let ss_100c_1v60 = MmmcCorner {
-        name: "ss_100c_1v60".to_string(),
-        corner_type: "setup".to_string(),
-        libs: vec![
-            pdk_root.join("sky130/sky130_cds/sky130_scl_9T_0.0.5/lib/sky130_ss_1.62_125_nldm.lib"),
-        ],
-        temperature: dec!(100.0),
-    };
-    let ff_n40c_1v95 = MmmcCorner {
-        name: "ff_n40c_1v95".to_string(),
-        corner_type: "hold".to_string(),
-        libs: vec![
-            pdk_root.join("sky130/sky130_cds/sky130_scl_9T_0.0.5/lib/sky130_ff_1.98_0_nldm.lib"),
-        ],
-        temperature: dec!(-40.0),
-    };
-
-    let tt_025c_1v80 = MmmcCorner {
-        name: "tt_025c_1v80".to_string(),
-        corner_type: "extra".to_string(),
-        libs: vec![
-            pdk_root.join("sky130/sky130_cds/sky130_scl_9T_0.0.5/lib/sky130_tt_1.8_25_nldm.lib"),
-        ],
-        temperature: dec!(25.0),
-    };
+    let (core_width, core_height) = constraints.top.core_size();
+    let power_grid =
+        sky130_cadence_power_grid(CellLibrary::Scl9T, core_width, core_height, grid_target);
 
-    let par_con = MmmcConfig {
-        sdc_files: vec![work_dir.join("clock_pin_constraints.sdc")],
-
-        corners: vec![
-            ss_100c_1v60.clone(),
-            ff_n40c_1v95.clone(),
-            tt_025c_1v80.clone(),
-        ],
-
-        setup: vec![ss_100c_1v60.clone()],
-
-        hold: vec![ff_n40c_1v95.clone(), tt_025c_1v80.clone()],
-
-        dynamic: tt_025c_1v80.clone(),
-
-        leakage: tt_025c_1v80.clone(),
-    };
+    let par_con = sky130_mmmc(
+        CellLibrary::Scl9T,
+        pdk_root,
+        CornerSet::Signoff,
+        vec![work_dir.join("clock_pin_constraints.sdc")],
+    );
 
     fs::create_dir_all(work_dir.join("checkpoints/")).expect("Failed to create directory");
 
@@ -495,7 +519,7 @@ pub fn sky130_scl_cadence_par(config: SclParConfig<'_>) -> InnovusStep {
 
     let par_constraints = constraints.clone();
 
-    InnovusStep::new(
+    Ok(InnovusStep::new(
         work_dir,
         module,
         vec![
@@ -505,13 +529,13 @@ pub fn sky130_scl_cadence_par(config: SclParConfig<'_>) -> InnovusStep {
                     work_dir,
                     module,
                     netlist_path: netlist,
-                    mmmc_conf: par_con,
+                    mmmc_conf: par_con.clone(),
                     tlef: &tlef,
                     pdk_lef: &pdk_root
                         .join("sky130/sky130_cds/sky130_scl_9T_0.0.5/lef/sky130_scl_9T.lef"),
                     submodules: Some(submodules),
                     hard_macros: &[],
-                    sdc_content: sdc,
+                    sdc_content: &sdc.to_string(),
                 },
                 srams,
             ),
@@ -525,40 +549,76 @@ pub fn sky130_scl_cadence_par(config: SclParConfig<'_>) -> InnovusStep {
                 "unithd",
             ),
             sky130_connect_nets(),
-            power_straps(layers.clone()),
-            place_pins("5", "1", vec![assignment]),
+            place_tap_cells(&sky130_tap_cells(CellLibrary::Scl9T)),
+            power_straps(&power_grid),
+            pins,
             place_opt_design(None),
+            clock_tree(&CtsSpec::default()),
             add_fillers(filler_cells),
             route_design(),
             opt_design(),
+            par_write_reports(),
             write_regs(),
             sky130_connect_nets(),
-            sky130_scl_cadence_par_write_design(
-                pdk_root,
-                work_dir,
-                module,
-                srams,
-                vec![
-                    ss_100c_1v60.clone(),
-                    ff_n40c_1v95.clone(),
-                    tt_025c_1v80.clone(),
-                ],
-            ),
+            sky130_scl_cadence_par_write_design(pdk_root, work_dir, module, srams, &par_con),
             write_ilm(
                 work_dir,
                 module,
-                &layers[0],
-                vec![
-                    ss_100c_1v60.clone(),
-                    ff_n40c_1v95.clone(),
-                    tt_025c_1v80.clone(),
-                ],
+                power_grid.top_layer().expect("power grid has stripes"),
+                &par_con,
             ),
         ],
         matches!(pin_info, FlatPinInfo::PinPar(_)),
         vec![Arc::new(syn_step) as Arc<dyn Step>],
         false,
-    )
+    ))
+}
+
+/// A pin plan of a module that [`place_pins`] rejects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModulePinPlanError {
+    pub module: String,
+    pub error: PinPlanError,
+}
+
+impl fmt::Display for ModulePinPlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pin plan for {}: {}", self.module, self.error)
+    }
+}
+
+impl std::error::Error for ModulePinPlanError {}
+
+/// Returns `plan`, or all pins spread along the bottom side on met4 if it is empty
+fn pin_plan_or_default(plan: &[PinPlacement]) -> Vec<PinPlacement> {
+    if !plan.is_empty() {
+        return plan.to_vec();
+    }
+    vec![PinPlacement {
+        pins: PinSelector::All,
+        side: Side::Bottom,
+        layers: vec!["met4".into()],
+        spread: PinSpread::Range {
+            start: (dec!(30), dec!(0)),
+            end: (dec!(0), dec!(0)),
+        },
+        width: None,
+        depth: None,
+    }]
+}
+
+/// The power grid of a block, sized for `target` when one is given
+fn sky130_cadence_power_grid(
+    library: CellLibrary,
+    core_width: Decimal,
+    core_height: Decimal,
+    target: Option<&GridTarget>,
+) -> PowerGrid {
+    match target {
+        Some(target) => sky130_sized_power_grid(library, core_width, core_height, target)
+            .unwrap_or_else(|e| panic!("Failed to size the power grid: {e}")),
+        None => sky130_power_grid(library, core_width, core_height),
+    }
 }
 
 pub fn sky130_innovus_settings() -> Substep {
@@ -596,9 +656,6 @@ pub fn sky130_innovus_settings() -> Substep {
             # Clock attributes  [get_db -category cts]
             ##########################################################
             #-------------------------------------------------------------------------------
-            set_db cts_target_skew 0.03
-            set_db cts_max_fanout 10
-            #set_db cts_target_max_transition_time .3
             set_db opt_setup_target_slack 0.10
             set_db opt_hold_target_slack 0.10
 
@@ -670,6 +727,161 @@ pub fn sky130_cadence_power_spec(module: &str, voltage: Decimal) -> String {
     }
 }
 
+/// Gives every child module without constraints default ones budgeted from its parent's,
+/// top down, so that modules with hand-written constraints keep them; a child shared with
+/// another part of the hierarchy is copied before it is updated. Unless the policy names
+/// them, the clock ports of a child are found by following the nets of its parent to the
+/// instances of the child.
+pub fn budget_hierarchy(
+    hierarchy: &mut Dag<ModuleInfo>,
+    policy: &impl Fn(&ModuleInfo) -> BudgetPolicy,
+) -> Result<(), ModuleBudgetError> {
+    let parent = &hierarchy.node;
+    for edge in &mut hierarchy.directed_edges {
+        let child = Arc::make_mut(edge);
+        if child.node.sdc.is_empty() {
+            let mut child_policy = policy(&child.node);
+            let instances: Vec<&str> = parent
+                .placement_constraints
+                .hard_macros
+                .iter()
+                .filter(|m| m.master == child.node.module_name)
+                .map(|m| m.name.as_str())
+                .collect();
+            if child_policy.clock_ports.is_empty()
+                && !instances.is_empty()
+                && !parent.nets.is_empty()
+            {
+                child_policy.clock_ports =
+                    connected_clock_ports(&parent.sdc, &parent.nets, &instances);
+            }
+            child.node.sdc =
+                budget_child(&parent.sdc, &child_policy, &child.node.ports).map_err(|errors| {
+                    ModuleBudgetError {
+                        module: child.node.module_name.clone(),
+                        errors,
+                    }
+                })?;
+        }
+        budget_hierarchy(child, policy)?;
+    }
+    Ok(())
+}
+
+/// Constraints budgeted for a module that do not match its ports or its parent's clocks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleBudgetError {
+    pub module: String,
+    pub errors: Vec<SdcError>,
+}
+
+impl fmt::Display for ModuleBudgetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid budgeted constraints for {}:", self.module)?;
+        for error in &self.errors {
+            write!(f, "\n  {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ModuleBudgetError {}
+
+/// Gives every child module without a pin plan one facing the blocks it connects to in its
+/// parent, top down, so that the pins of each child are placed after its parent's floorplan;
+/// a child shared with another part of the hierarchy is copied before it is updated
+pub fn align_hierarchy_pins(
+    hierarchy: &mut Dag<ModuleInfo>,
+    spec: &AlignmentSpec,
+) -> Result<(), AlignmentError> {
+    let parent = &hierarchy.node;
+    let blocks: Vec<BlockOutline> = hierarchy
+        .directed_edges
+        .iter()
+        .map(|child| {
+            BlockOutline::new(
+                &child.node.module_name,
+                &child.node.placement_constraints.top,
+                child.node.ports.clone(),
+            )
+        })
+        .collect();
+    let mut plans = Vec::new();
+    for child in &hierarchy.directed_edges {
+        let instantiated = parent
+            .placement_constraints
+            .hard_macros
+            .iter()
+            .any(|m| m.master == child.node.module_name);
+        plans.push(
+            if child.node.pin_plan.is_empty() && instantiated && !parent.nets.is_empty() {
+                Some(align_block_pins(
+                    &parent.placement_constraints,
+                    &parent.nets,
+                    &blocks,
+                    &child.node.module_name,
+                    spec,
+                )?)
+            } else {
+                None
+            },
+        );
+    }
+    for (edge, plan) in hierarchy.directed_edges.iter_mut().zip(plans) {
+        let child = Arc::make_mut(edge);
+        if let Some(plan) = plan {
+            child.node.pin_plan = plan;
+        }
+        align_hierarchy_pins(child, spec)?;
+    }
+    Ok(())
+}
+
+/// Checks the floorplan of `module`, with its child blocks and SRAMs as hard macros, so that
+/// every problem is reported before any tool runs
+fn check_floorplan(
+    module: &ModuleInfo,
+    dep_info: &[(&ModuleInfo, &Sky130FlatFlow)],
+    sram_work_dir: &Path,
+) {
+    let mut rules = sky130_floorplan_rules();
+    rules.macro_sizes = dep_info
+        .iter()
+        .map(|(child, _)| {
+            (
+                child.module_name.clone(),
+                child.placement_constraints.top.die_size(),
+            )
+        })
+        .collect();
+    // SRAMs are only sized once the compiler has written their LEF
+    let mut pending = Vec::new();
+    for sram in &module.srams {
+        match fs::read_to_string(sram.lef(sram_work_dir)) {
+            Ok(lef) => rules.macro_sizes.extend(lef_macro_sizes(&lef)),
+            Err(_) => pending.push(sram.name()),
+        }
+    }
+    if let Err(FloorplanErrors(problems)) =
+        validate_floorplan(&module.placement_constraints, &rules)
+    {
+        let problems: Vec<FloorplanProblem> = problems
+            .into_iter()
+            .filter(|p| {
+                !matches!(p, FloorplanProblem::UnknownMacroSize { master, .. }
+                    if pending.contains(master))
+            })
+            .collect();
+        if !problems.is_empty() {
+            panic!(
+                "invalid floorplan for {}:\n{}",
+                module.module_name,
+                FloorplanErrors(problems)
+            );
+        }
+    }
+}
+
 fn sky130_scl_cadence_flat_flow(
     pdk_root: &Path,
     work_dir: &Path,
@@ -677,6 +889,7 @@ fn sky130_scl_cadence_flat_flow(
     dep_info: &[(&ModuleInfo, &Sky130FlatFlow)],
 ) -> Sky130FlatFlow {
     let sram_work_dir = work_dir.join("sram");
+    check_floorplan(module, dep_info, &sram_work_dir);
 
     let mut all_submodules: Vec<SubmoduleInfo> = Vec::new();
     for (child_module, child_flow) in dep_info {
@@ -695,7 +908,7 @@ fn sky130_scl_cadence_flat_flow(
     }
 
     let syn_work_dir = work_dir.join("syn-rundir");
-    let syn = sky130_scl_cadence_syn(SclSynConfig {
+    let mut syn = sky130_scl_cadence_syn(SclSynConfig {
         pdk_root,
         work_dir: &syn_work_dir,
         module: &module.module_name,
@@ -707,16 +920,17 @@ fn sky130_scl_cadence_flat_flow(
         pin_info: &module.pin_info,
         sdc: &module.sdc,
     });
-    let syn_pointer = StepRef::new(syn);
     let par_work_dir = work_dir.join("par-rundir");
     let output_netlist_path = if !dep_info.is_empty() {
         syn_work_dir.join(format!("{}_noilm.mapped.v", module.module_name))
     } else {
         syn_work_dir.join(format!("{}.mapped.v", module.module_name))
     };
+    syn.add_expected_output(&output_netlist_path);
+    let syn_pointer = StepRef::new(syn);
 
     let final_constraints = module.placement_constraints.clone();
-    let par = sky130_scl_cadence_par(SclParConfig {
+    let mut par = sky130_scl_cadence_par(SclParConfig {
         pdk_root,
         work_dir: &par_work_dir,
         module: &module.module_name,
@@ -727,11 +941,20 @@ fn sky130_scl_cadence_flat_flow(
         pin_info: &module.pin_info,
         syn_step: syn_pointer.clone(),
         sdc: &module.sdc,
-    });
+        pin_plan: &module.pin_plan,
+        grid_target: module.grid_target.as_ref(),
+    })
+    .unwrap_or_else(|e| panic!("{e}"));
+    let (gds, ilm, lef) = (par.gds_path(), par.ilm_path(), par.lef_path());
+    par.add_expected_output(gds);
+    par.add_expected_output(ilm);
+    par.add_expected_output(lef);
     let par_pointer = StepRef::new(par);
     Sky130FlatFlow {
         module: module.module_name.to_string(),
         syn: syn_pointer,
+        drc: sky130_signoff(&par_pointer, "drc"),
+        lvs: sky130_signoff(&par_pointer, "lvs"),
         par: par_pointer,
         submodules: all_submodules.clone(),
     }
@@ -740,8 +963,12 @@ fn sky130_scl_cadence_flat_flow(
 pub fn sky130_scl_cadence_reference_flow(
     pdk_root: PathBuf,
     work_dir: PathBuf,
-    hierarchy: Dag<ModuleInfo>,
+    mut hierarchy: Dag<ModuleInfo>,
 ) -> Dag<Sky130FlatFlow> {
+    budget_hierarchy(&mut hierarchy, &|_| BudgetPolicy::default())
+        .expect("failed to budget child constraints");
+    align_hierarchy_pins(&mut hierarchy, &sky130_pin_alignment())
+        .expect("failed to align child pins");
     hierarchical(&hierarchy, &|block: &ModuleInfo,
                                sub_blocks: Vec<(
         &ModuleInfo,
@@ -762,69 +989,10 @@ fn sky130_os_cadence_par_write_design(
     work_dir: &Path,
     module: &str,
     srams: &[Sram22],
-    corners: Vec<MmmcCorner>,
+    mmmc: &MmmcConfig,
 ) -> Substep {
-    let par_rundir = work_dir.display().to_string();
-    let module = module.to_owned();
-    let setup = corners
-        .iter()
-        .find(|p| p.corner_type == "setup")
-        .unwrap()
-        .name
-        .clone();
-    let hold = corners
-        .iter()
-        .find(|p| p.corner_type == "hold")
-        .unwrap()
-        .name
-        .clone();
-    let typical = corners
-        .iter()
-        .find(|p| p.corner_type == "extra")
-        .unwrap()
-        .name
-        .clone();
-
-    let sram_work_dir = work_dir.parent().unwrap().join("sram");
-    let pdk_gds = pdk_root
-        .join("libs.ref/sky130_fd_sc_hd/gds/sky130_fd_sc_hd.gds")
-        .display()
-        .to_string();
-    let mut merge_gds = vec![pdk_gds];
-    merge_gds.extend(
-        srams
-            .iter()
-            .map(|s| s.gds(&sram_work_dir).display().to_string()),
-    );
-    let merge_str = merge_gds.join(" ");
-
-    let lefpin_map = concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/../../pdks/sky130/src/sky130_lefpin.map"
-    );
-    Substep {
-        checkpoint: true,
-        command: formatdoc!(
-            r#"
-            set_db timing_enable_simultaneous_setup_hold_mode true
-            write_db {module}_FINAL -def -verilog
-            set_db timing_enable_simultaneous_setup_hold_mode false
-            set_db write_stream_virtual_connection false
-            write_netlist {par_rundir}/{module}.lvs.v -top_module_first -top_module {module} -exclude_leaf_cells -phys -flat -exclude_insts_of_cells {{}}
-            write_netlist {par_rundir}/{module}.sim.v -top_module_first -top_module {module} -exclude_leaf_cells -exclude_insts_of_cells {{}}
-            write_stream -mode ALL -format stream -map_file {lefpin_map} -uniquify_cell_names -merge {{ {merge_str} }} {par_rundir}/{module}.gds
-            write_sdf -max_view {setup}.setup_view -min_view {hold}.hold_view -typical_view {typical}.extra_view {par_rundir}/{module}.par.sdf
-            set_db extract_rc_coupled true
-            extract_rc
-            write_parasitics -spef_file {par_rundir}/{module}.{setup}.par.spef -rc_corner {setup}.setup_rc
-            write_parasitics -spef_file {par_rundir}/{module}.{hold}.par.spef -rc_corner {hold}.hold_rc
-            write_parasitics -spef_file {par_rundir}/{module}.{typical}.par.spef -rc_corner {typical}.extra_rc
-            write_db post_write_design
-            ln -sfn post_write_design latest
-            "#
-        ),
-        name: "write_design".into(),
-    }
+    let pdk_gds = pdk_root.join("libs.ref/sky130_fd_sc_hd/gds/sky130_fd_sc_hd.gds");
+    sky130_cadence_par_write_design(pdk_gds, work_dir, module, srams, mmmc)
 }
 
 pub struct OsSynConfig<'a> {
@@ -837,7 +1005,7 @@ pub struct OsSynConfig<'a> {
     pub dep_info: &'a [(&'a ModuleInfo, &'a Sky130FlatFlow)],
     pub submodules: Vec<SubmoduleInfo>,
     pub pin_info: &'a FlatPinInfo,
-    pub sdc: &'a str,
+    pub sdc: &'a Constraints,
 }
 
 pub struct OsParConfig<'a> {
@@ -850,45 +1018,20 @@ pub struct OsParConfig<'a> {
     pub submodules: Vec<SubmoduleInfo>,
     pub pin_info: &'a FlatPinInfo,
     pub syn_step: StepRef<GenusStep>,
-    pub sdc: &'a str,
+    pub sdc: &'a Constraints,
+    /// Pin placement of the block; all pins go on the bottom side when empty
+    pub pin_plan: &'a [PinPlacement],
+    pub grid_target: Option<&'a GridTarget>,
 }
 
 pub fn sky130_os_cadence_syn(config: OsSynConfig<'_>) -> GenusStep {
     let OsSynConfig { pdk_root, work_dir, module, verilog_paths, srams, sram_work_dir, dep_info, submodules, pin_info, sdc } = config;
-    let ss_100c_1v60 = MmmcCorner {
-        name: "ss_100c_1v60".to_string(),
-        corner_type: "setup".to_string(),
-        libs: vec![pdk_root.join("libs.ref/sky130_fd_sc_hd/lib/sky130_fd_sc_hd__ss_100C_1v60.lib")],
-        temperature: dec!(100.0),
-    };
-    let ff_n40c_1v95 = MmmcCorner {
-        name: "ff_n40c_1v95".to_string(),
-        corner_type: "hold".to_string(),
-        libs: vec![
-            pdk_root
-                .join("libs.ref/sky130_fd_sc_hd/lib/sky130_fd_sc_hd__ff_n40C_1v95_ccsnoise.lib"),
-        ],
-        temperature: dec!(-40.0),
-    };
-    let tt_025c_1v80 = MmmcCorner {
-        name: "tt_025c_1v80".to_string(),
-        corner_type: "extra".to_string(),
-        libs: vec![pdk_root.join("libs.ref/sky130_fd_sc_hd/lib/sky130_fd_sc_hd__tt_025C_1v80.lib")],
-        temperature: dec!(25.0),
-    };
-
-    let syn_con = MmmcConfig {
-        sdc_files: vec![work_dir.join("clock_pin_constraints.sdc")],
-        corners: vec![
-            ss_100c_1v60.clone(),
-            ff_n40c_1v95.clone(),
-            tt_025c_1v80.clone(),
-        ],
-        setup: vec![ss_100c_1v60.clone()],
-        hold: vec![ff_n40c_1v95.clone(), tt_025c_1v80.clone()],
-        dynamic: tt_025c_1v80.clone(),
-        leakage: tt_025c_1v80.clone(),
-    };
+    let syn_con = sky130_mmmc(
+        CellLibrary::FdScHd,
+        pdk_root,
+        CornerSet::Signoff,
+        vec![work_dir.join("clock_pin_constraints.sdc")],
+    );
     fs::create_dir_all(work_dir.join("checkpoints/")).expect("Failed to create directory");
 
     let tlef = setup_techlef(
@@ -941,13 +1084,13 @@ pub fn sky130_os_cadence_syn(config: OsSynConfig<'_>) -> GenusStep {
                 GenusDesignFiles {
                     work_dir,
                     verilog_paths,
-                    mmmc_conf: syn_con,
+                    mmmc_conf: syn_con.clone(),
                     tlef: &tlef,
                     pdk_lef: &pdk_root.join("libs.ref/sky130_fd_sc_hd/lef/sky130_fd_sc_hd.lef"),
                     submodules: Some(submodules.clone()),
                     is_hierarchical,
                     hard_macros: &[],
-                    sdc_content: sdc,
+                    sdc_content: &sdc.to_string(),
                 },
                 srams,
             ),
@@ -957,15 +1100,25 @@ pub fn sky130_os_cadence_syn(config: OsSynConfig<'_>) -> GenusStep {
             syn_generic(),
             syn_map(),
             add_tieoffs("sky130_fd_sc_hd__conb_1", "sky130_fd_sc_hd__conb_1"),
-            syn_write_design(module, ss_100c_1v60.clone(), is_hierarchical),
+            syn_write_design(
+                module,
+                syn_con.primary_setup().corner.clone(),
+                is_hierarchical,
+            ),
         ],
         matches!(pin_info, FlatPinInfo::PinSyn(_)),
         deps,
     )
 }
 
-pub fn sky130_os_cadence_par(config: OsParConfig<'_>) -> InnovusStep {
-    let OsParConfig { pdk_root, work_dir, module, constraints, netlist, srams, submodules, pin_info, syn_step, sdc } = config;
+pub fn sky130_os_cadence_par(config: OsParConfig<'_>) -> Result<InnovusStep, ModulePinPlanError> {
+    let OsParConfig { pdk_root, work_dir, module, constraints, netlist, srams, submodules, pin_info, syn_step, sdc, pin_plan, grid_target } = config;
+    let pins = place_pins("5", "1", module, &pin_plan_or_default(pin_plan)).map_err(|error| {
+        ModulePinPlanError {
+            module: module.clone(),
+            error,
+        }
+    })?;
     let filler_cells = vec![
         "sky130_fd_sc_hd__fill_1".into(),
         "sky130_fd_sc_hd__fill_2".into(),
@@ -973,70 +1126,16 @@ pub fn sky130_os_cadence_par(config: OsParConfig<'_>) -> InnovusStep {
         "sky130_fd_sc_hd__fill_8".into(),
     ];
 
-    let assignment = PinAssignment {
-        pins: "*".into(),
-        module: module.into(),
-        patterns: "-spread_type range".into(),
-        layer: "-layer {met4}".into(),
-        side: "-side bottom".into(),
-        start: "-start {30 0}".into(),
-        end: "-end {0 0}".into(),
-        assign: "".into(),
-        width: "".into(),
-        depth: "".into(),
-    };
-
-    let layers = vec![
-        Layer {
-            top: "met4".to_string(),
-            bot: "met1".to_string(),
-            spacing: dec!(2.000),
-            trim_antenna: true,
-            add_stripes_command: r#"add_stripes -create_pins 0 -block_ring_bottom_layer_limit met4 -block_ring_top_layer_limit met1 -direction vertical -layer met4 -nets {VSS VDD} -pad_core_ring_bottom_layer_limit met1 -set_to_set_distance 75.90 -spacing 3.66 -switch_layer_over_obs 0 -width 1.86 -area [get_db designs .core_bbox] -start [expr [lindex [lindex [get_db designs .core_bbox] 0] 0] + 7.35]"#.to_string(),
-        },
-        Layer {
-            top: "met5".to_string(),
-            bot: "met4".to_string(),
-            spacing: dec!(2.000),
-            trim_antenna: true,
-            add_stripes_command: r#"add_stripes -create_pins 1 -block_ring_bottom_layer_limit met5 -block_ring_top_layer_limit met4 -direction horizontal -layer met5 -nets {VSS VDD} -pad_core_ring_bottom_layer_limit met4 -set_to_set_distance 225.40 -spacing 17.68 -switch_layer_over_obs 0 -width 1.64 -area [get_db designs .core_bbox] -start [expr [lindex [lindex [get_db designs .core_bbox] 0] 1] + 5.62]"#.to_string(),
-        },
-    ];
-
-    let ss_100c_1v60 = MmmcCorner {
-        name: "ss_100c_1v60".to_string(),
-        corner_type: "setup".to_string(),
-        libs: vec![pdk_root.join("libs.ref/sky130_fd_sc_hd/lib/sky130_fd_sc_hd__ss_100C_1v60.lib")],
-        temperature: dec!(100.0),
-    };
-    let ff_n40c_1v95 = MmmcCorner {
-        name: "ff_n40c_1v95".to_string(),
-        corner_type: "hold".to_string(),
-        libs: vec![
-            pdk_root
-                .join("libs.ref/sky130_fd_sc_hd/lib/sky130_fd_sc_hd__ff_n40C_1v95_ccsnoise.lib"),
-        ],
-        temperature: dec!(-40.0),
-    };
-    let tt_025c_1v80 = MmmcCorner {
-        name: "tt_025c_1v80".to_string(),
-        corner_type: "extra".to_string(),
-        libs: vec![pdk_root.join("libs.ref/sky130_fd_sc_hd/lib/sky130_fd_sc_hd__tt_025C_1v80.lib")],
-        temperature: dec!(25.0),
-    };
+    let (core_width, core_height) = constraints.top.core_size();
+    let power_grid =
+        sky130_cadence_power_grid(CellLibrary::FdScHd, core_width, core_height, grid_target);
 
-    let par_con = MmmcConfig {
-        sdc_files: vec![work_dir.join("clock_pin_constraints.sdc")],
-        corners: vec![
-            ss_100c_1v60.clone(),
-            ff_n40c_1v95.clone(),
-            tt_025c_1v80.clone(),
-        ],
-        setup: vec![ss_100c_1v60.clone()],
-        hold: vec![ff_n40c_1v95.clone(), tt_025c_1v80.clone()],
-        dynamic: tt_025c_1v80.clone(),
-        leakage: tt_025c_1v80.clone(),
-    };
+    let par_con = sky130_mmmc(
+        CellLibrary::FdScHd,
+        pdk_root,
+        CornerSet::Signoff,
+        vec![work_dir.join("clock_pin_constraints.sdc")],
+    );
 
     fs::create_dir_all(work_dir.join("checkpoints/")).expect("Failed to create directory");
 
@@ -1047,7 +1146,7 @@ pub fn sky130_os_cadence_par(config: OsParConfig<'_>) -> InnovusStep {
 
     let par_constraints = constraints.clone();
 
-    InnovusStep::new(
+    Ok(InnovusStep::new(
         work_dir,
         module,
         vec![
@@ -1057,12 +1156,12 @@ pub fn sky130_os_cadence_par(config: OsParConfig<'_>) -> InnovusStep {
                     work_dir,
                     module,
                     netlist_path: netlist,
-                    mmmc_conf: par_con,
+                    mmmc_conf: par_con.clone(),
                     tlef: &tlef,
                     pdk_lef: &pdk_root.join("libs.ref/sky130_fd_sc_hd/lef/sky130_fd_sc_hd.lef"),
                     submodules: Some(submodules),
                     hard_macros: &[],
-                    sdc_content: sdc,
+                    sdc_content: &sdc.to_string(),
                 },
                 srams,
             ),
@@ -1077,40 +1176,29 @@ pub fn sky130_os_cadence_par(config: OsParConfig<'_>) -> InnovusStep {
                 "unithd",
             ),
             sky130_connect_nets(),
-            power_straps(layers.clone()),
-            place_pins("5", "1", vec![assignment]),
+            place_tap_cells(&sky130_tap_cells(CellLibrary::FdScHd)),
+            power_straps(&power_grid),
+            pins,
             place_opt_design(None),
+            clock_tree(&CtsSpec::default()),
             add_fillers(filler_cells),
             route_design(),
             opt_design(),
+            par_write_reports(),
             write_regs(),
             sky130_connect_nets(),
-            sky130_os_cadence_par_write_design(
-                pdk_root,
-                work_dir,
-                module,
-                srams,
-                vec![
-                    ss_100c_1v60.clone(),
-                    ff_n40c_1v95.clone(),
-                    tt_025c_1v80.clone(),
-                ],
-            ),
+            sky130_os_cadence_par_write_design(pdk_root, work_dir, module, srams, &par_con),
             write_ilm(
                 work_dir,
                 module,
-                &layers[0],
-                vec![
-                    ss_100c_1v60.clone(),
-                    ff_n40c_1v95.clone(),
-                    tt_025c_1v80.clone(),
-                ],
+                power_grid.top_layer().expect("power grid has stripes"),
+                &par_con,
             ),
         ],
         matches!(pin_info, FlatPinInfo::PinPar(_)),
         vec![Arc::new(syn_step) as Arc<dyn Step>],
         false,
-    )
+    ))
 }
 
 fn sky130_os_cadence_flat_flow(
@@ -1120,6 +1208,7 @@ fn sky130_os_cadence_flat_flow(
     dep_info: &[(&ModuleInfo, &Sky130FlatFlow)],
 ) -> Sky130FlatFlow {
     let sram_work_dir = work_dir.join("sram");
+    check_floorplan(module, dep_info, &sram_work_dir);
 
     let mut all_submodules: Vec<SubmoduleInfo> = Vec::new();
     for (child_module, child_flow) in dep_info {
@@ -1137,7 +1226,7 @@ fn sky130_os_cadence_flat_flow(
     }
 
     let syn_work_dir = work_dir.join("syn-rundir");
-    let syn = sky130_os_cadence_syn(OsSynConfig {
+    let mut syn = sky130_os_cadence_syn(OsSynConfig {
         pdk_root,
         work_dir: &syn_work_dir,
         module: &module.module_name,
@@ -1149,16 +1238,17 @@ fn sky130_os_cadence_flat_flow(
         pin_info: &module.pin_info,
         sdc: &module.sdc,
     });
-    let syn_pointer = StepRef::new(syn);
     let par_work_dir = work_dir.join("par-rundir");
     let output_netlist_path = if !dep_info.is_empty() {
         syn_work_dir.join(format!("{}_noilm.mapped.v", module.module_name))
     } else {
         syn_work_dir.join(format!("{}.mapped.v", module.module_name))
     };
+    syn.add_expected_output(&output_netlist_path);
+    let syn_pointer = StepRef::new(syn);
 
     let final_constraints = module.placement_constraints.clone();
-    let par = sky130_os_cadence_par(OsParConfig {
+    let mut par = sky130_os_cadence_par(OsParConfig {
         pdk_root,
         work_dir: &par_work_dir,
         module: &module.module_name,
@@ -1169,11 +1259,20 @@ fn sky130_os_cadence_flat_flow(
         pin_info: &module.pin_info,
         syn_step: syn_pointer.clone(),
         sdc: &module.sdc,
-    });
+        pin_plan: &module.pin_plan,
+        grid_target: module.grid_target.as_ref(),
+    })
+    .unwrap_or_else(|e| panic!("{e}"));
+    let (gds, ilm, lef) = (par.gds_path(), par.ilm_path(), par.lef_path());
+    par.add_expected_output(gds);
+    par.add_expected_output(ilm);
+    par.add_expected_output(lef);
     let par_pointer = StepRef::new(par);
     Sky130FlatFlow {
         module: module.module_name.to_string(),
         syn: syn_pointer,
+        drc: sky130_signoff(&par_pointer, "drc"),
+        lvs: sky130_signoff(&par_pointer, "lvs"),
         par: par_pointer,
         submodules: all_submodules,
     }
@@ -1182,8 +1281,12 @@ fn sky130_os_cadence_flat_flow(
 pub fn sky130_os_cadence_reference_flow(
     pdk_root: PathBuf,
     work_dir: PathBuf,
-    hierarchy: Dag<ModuleInfo>,
+    mut hierarchy: Dag<ModuleInfo>,
 ) -> Dag<Sky130FlatFlow> {
+    budget_hierarchy(&mut hierarchy, &|_| BudgetPolicy::default())
+        .expect("failed to budget child constraints");
+    align_hierarchy_pins(&mut hierarchy, &sky130_pin_alignment())
+        .expect("failed to align child pins");
     hierarchical(&hierarchy, &|block: &ModuleInfo,
                                sub_blocks: Vec<(
         &ModuleInfo,
@@ -1228,19 +1331,24 @@ pub fn decoder_flow() -> anyhow::Result<()> {
                     obstructs: vec![],
                 },
                 floorplan_commands: String::new(),
-                sdc: cadence::sdc(),
+                sdc: Constraints::single_clock("clk", dec!(2.0)),
+                pin_plan: vec![],
+                grid_target: None,
+                ports: vec![],
+                nets: vec![],
             },
             directed_edges: vec![],
         },
     );
 
-    execute(flow.node.par);
+    execute_with_signoff(&flow);
     Ok(())
 }
 
 pub fn hierarchical_flow() -> anyhow::Result<()> {
     let pdk_root = PathBuf::from(std::env::var("SKY130PDK_OS_INSTALL_PATH")?);
     let work_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("build/hierarchical");
+    let report_path = work_dir.join("report.html");
 
     let stackup = || -> Vec<String> {
         vec![
@@ -1253,6 +1361,67 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
         ]
     };
 
+    let ports = |names: &[&str]| -> Vec<String> { names.iter().map(|&n| n.into()).collect() };
+    let pin = |instance: &str, port: &str| NetEnd::Instance {
+        instance: instance.into(),
+        port: port.into(),
+    };
+
+    // Operand bits enter on the left, sums leave on the right and the carry chain runs
+    // fa_1, fa_2, fa_3, fa_4
+    let mut adder_ports = ports(&["cin", "cout"]);
+    let mut adder_nets = vec![
+        ParentNet::new(
+            "cin",
+            vec![NetEnd::Boundary(Side::Bottom), pin("fa_1", "cin")],
+        ),
+        ParentNet::new(
+            "cout",
+            vec![pin("fa_4", "cout"), NetEnd::Boundary(Side::Top)],
+        ),
+    ];
+    for bit in 0..4 {
+        let fa = format!("fa_{}", bit + 1);
+        for (port, side) in [("a", Side::Left), ("b", Side::Left), ("sum", Side::Right)] {
+            let name = format!("{port}[{bit}]");
+            adder_ports.push(name.clone());
+            adder_nets.push(ParentNet::new(
+                name,
+                vec![NetEnd::Boundary(side), pin(&fa, port)],
+            ));
+        }
+        if bit > 0 {
+            adder_nets.push(ParentNet::new(
+                format!("carry[{bit}]"),
+                vec![pin(&format!("fa_{bit}"), "cout"), pin(&fa, "cin")],
+            ));
+        }
+    }
+    // The carries of both half adders meet at the OR gate in the middle of the full adder
+    let or_gate = NetEnd::Point((dec!(50), dec!(50)));
+    let full_adder_nets = vec![
+        ParentNet::new("a", vec![NetEnd::Boundary(Side::Left), pin("ha1", "a")]),
+        ParentNet::new("b", vec![NetEnd::Boundary(Side::Left), pin("ha1", "b")]),
+        ParentNet::new("sum1", vec![pin("ha1", "sum"), pin("ha2", "a")]),
+        ParentNet::new("cin", vec![NetEnd::Boundary(Side::Bottom), pin("ha2", "b")]),
+        ParentNet::new(
+            "sum",
+            vec![pin("ha2", "sum"), NetEnd::Boundary(Side::Right)],
+        ),
+        ParentNet::new("carry1", vec![pin("ha1", "carry"), or_gate.clone()]),
+        ParentNet::new("carry2", vec![pin("ha2", "carry"), or_gate]),
+    ];
+
+    // The adder is combinational, so a virtual clock times its I/O and is budgeted down to
+    // the full and half adders
+    let mut adder_sdc = Constraints::new();
+    adder_sdc
+        .clock(Clock::virtual_clock("vclk", dec!(10)).uncertainty(dec!(0.05)))
+        .clock_group(ClockRelation::Asynchronous, vec![vec!["vclk".into()]])
+        .load(Decimal::ONE, PortGroup::AllOutputs)
+        .input_delay("vclk", Decimal::ZERO, PortGroup::AllInputs)
+        .output_delay("vclk", Decimal::ZERO, PortGroup::AllOutputs);
+
     let mut flow = sky130_scl_cadence_reference_flow(
         pdk_root,
         work_dir,
@@ -1276,8 +1445,8 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
                     },
                     hard_macros: vec![
                         HardMacroConstraint {
-                            x: 10.0,
-                            y: 10.0,
+                            x: 10.12,
+                            y: 10.88,
                             orientation: "r0".into(),
                             top_layer: "met3".into(),
                             stackup: stackup(),
@@ -1288,8 +1457,8 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
                             master: "fulladder".into(),
                         },
                         HardMacroConstraint {
-                            x: 10.0,
-                            y: 150.0,
+                            x: 10.12,
+                            y: 152.32,
                             orientation: "r0".into(),
                             top_layer: "met3".into(),
                             stackup: stackup(),
@@ -1300,8 +1469,8 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
                             master: "fulladder".into(),
                         },
                         HardMacroConstraint {
-                            x: 150.0,
-                            y: 10.0,
+                            x: 150.42,
+                            y: 10.88,
                             orientation: "r0".into(),
                             top_layer: "met3".into(),
                             stackup: stackup(),
@@ -1312,8 +1481,8 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
                             master: "fulladder".into(),
                         },
                         HardMacroConstraint {
-                            x: 150.0,
-                            y: 150.0,
+                            x: 150.42,
+                            y: 152.32,
                             orientation: "r0".into(),
                             top_layer: "met3".into(),
                             stackup: stackup(),
@@ -1327,7 +1496,11 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
                     obstructs: vec![],
                 },
                 floorplan_commands: String::new(),
-                sdc: String::new(),
+                sdc: adder_sdc,
+                pin_plan: vec![],
+                grid_target: None,
+                ports: adder_ports,
+                nets: adder_nets,
             },
             directed_edges: vec![Arc::new(Dag {
                 node: ModuleInfo {
@@ -1349,8 +1522,8 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
                         },
                         hard_macros: vec![
                             HardMacroConstraint {
-                                x: 10.0,
-                                y: 10.0,
+                                x: 10.12,
+                                y: 10.88,
                                 orientation: "r0".into(),
                                 top_layer: "met3".into(),
                                 stackup: stackup(),
@@ -1361,8 +1534,8 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
                                 master: "halfadder".into(),
                             },
                             HardMacroConstraint {
-                                x: 50.0,
-                                y: 10.0,
+                                x: 50.14,
+                                y: 10.88,
                                 orientation: "r0".into(),
                                 top_layer: "met3".into(),
                                 stackup: stackup(),
@@ -1376,7 +1549,11 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
                         obstructs: vec![],
                     },
                     floorplan_commands: String::new(),
-                    sdc: String::new(),
+                    sdc: Constraints::new(),
+                    pin_plan: vec![],
+                    grid_target: None,
+                    ports: ports(&["a", "b", "cin", "sum", "cout"]),
+                    nets: full_adder_nets,
                 },
                 directed_edges: vec![Arc::new(Dag {
                     node: ModuleInfo {
@@ -1400,7 +1577,11 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
                             obstructs: vec![],
                         },
                         floorplan_commands: String::new(),
-                        sdc: String::new(),
+                        sdc: Constraints::new(),
+                        pin_plan: vec![],
+                        grid_target: None,
+                        ports: ports(&["a", "b", "sum", "carry"]),
+                        nets: vec![],
                     },
                     directed_edges: vec![],
                 })],
@@ -1412,9 +1593,10 @@ pub fn hierarchical_flow() -> anyhow::Result<()> {
         .unwrap()
         .syn
         .get()
-        .replace_hook("syn_opt", "syn_opt", "syn_map", false);
+        .replace_hook("syn_opt", "syn_opt", "syn_map", false)?;
 
-    execute(flow.node.par);
+    execute_with_signoff(&flow);
+    write_html_report(&flow, &report_path)?;
     Ok(())
 }
 
@@ -1431,4 +1613,71 @@ mod tests {
     fn hierarchical_vlsi() -> anyhow::Result<()> {
         hierarchical_flow()
     }
+
+    fn module(name: &str, size: f64, ports: &[&str]) -> ModuleInfo {
+        ModuleInfo {
+            module_name: name.into(),
+            pin_info: FlatPinInfo::None,
+            verilog: vec![],
+            srams: vec![],
+            placement_constraints: Floorplan {
+                top: TopLevelConstraint {
+                    width: size,
+                    height: size,
+                    left: 0.0,
+                    bottom: 0.0,
+                    right: 0.0,
+                    top: 0.0,
+                },
+                hard_macros: vec![],
+                obstructs: vec![],
+            },
+            floorplan_commands: String::new(),
+            sdc: Constraints::new(),
+            pin_plan: vec![],
+            grid_target: None,
+            ports: ports.iter().map(|&p| p.into()).collect(),
+            nets: vec![],
+        }
+    }
+
+    #[test]
+    fn aligns_pins_of_shared_children() {
+        let mut top = module("top", 100.0, &["a"]);
+        top.placement_constraints
+            .hard_macros
+            .push(HardMacroConstraint {
+                x: 10.12,
+                y: 10.88,
+                orientation: "r0".into(),
+                top_layer: "met3".into(),
+                stackup: vec![],
+                route_halo_size: 2.0,
+                place_halo_size: 1.2,
+                create_physical: false,
+                name: "u_leaf".into(),
+                master: "leaf".into(),
+            });
+        top.nets.push(ParentNet::new(
+            "a",
+            vec![
+                NetEnd::Boundary(Side::Left),
+                NetEnd::Instance {
+                    instance: "u_leaf".into(),
+                    port: "a".into(),
+                },
+            ],
+        ));
+        let leaf = Arc::new(Dag {
+            node: module("leaf", 20.0, &["a"]),
+            directed_edges: vec![],
+        });
+        let mut hierarchy = Dag {
+            node: top,
+            directed_edges: vec![leaf.clone()],
+        };
+        align_hierarchy_pins(&mut hierarchy, &sky130_pin_alignment()).unwrap();
+        assert!(leaf.node.pin_plan.is_empty());
+        assert!(!hierarchy.directed_edges[0].node.pin_plan.is_empty());
+    }
 }
@@ -0,0 +1,303 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use cadence::pegasus::read_outcome;
+use cadence::qor::{Qor, Slack};
+use cadence::tool::{CadenceToolStep, RunStatus};
+use rivet::Dag;
+
+use crate::Sky130FlatFlow;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin: 0.5em 0 1.5em; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: left; }
+th { background: #f0f0f0; }
+.passed { color: #080; } .failed { color: #c00; } .pending { color: #888; }
+section { border-top: 2px solid #ddd; margin-top: 2em; }
+";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn link(path: &Path, label: &str) -> String {
+    if path.exists() {
+        format!(
+            "<a href=\"file://{}\">{}</a>",
+            escape(&path.display().to_string()),
+            escape(label)
+        )
+    } else {
+        format!("<span class=\"pending\">{}</span>", escape(label))
+    }
+}
+
+fn status_cell(status: &RunStatus) -> String {
+    match status {
+        RunStatus::NotRun => "<td class=\"pending\">not run</td>".into(),
+        RunStatus::Completed => "<td class=\"passed\">completed</td>".into(),
+        RunStatus::Failed {
+            substep: Some(substep),
+            message,
+        } => format!(
+            "<td class=\"failed\">failed in {}: {}</td>",
+            escape(substep),
+            escape(message)
+        ),
+        RunStatus::Failed {
+            substep: None,
+            message,
+        } => format!("<td class=\"failed\">failed: {}</td>", escape(message)),
+    }
+}
+
+fn outcome_cell(outcome: Option<bool>) -> &'static str {
+    match outcome {
+        None => "<td class=\"pending\">not run</td>",
+        Some(true) => "<td class=\"passed\">passed</td>",
+        Some(false) => "<td class=\"failed\">failed</td>",
+    }
+}
+
+fn slack_cells(slack: Option<&Slack>) -> String {
+    match slack {
+        Some(s) => format!(
+            "<td>{}</td><td>{}</td><td>{}</td>",
+            s.wns, s.tns, s.violating_paths
+        ),
+        None => "<td>-</td><td>-</td><td>-</td>".into(),
+    }
+}
+
+fn tree(flow: &Dag<Sky130FlatFlow>, html: &mut String) {
+    let module = escape(&flow.node.module);
+    let _ = write!(html, "<li><a href=\"#{module}\">{module}</a>");
+    if !flow.directed_edges.is_empty() {
+        html.push_str("<ul>");
+        for child in &flow.directed_edges {
+            tree(child, html);
+        }
+        html.push_str("</ul>");
+    }
+    html.push_str("</li>");
+}
+
+fn step_row(html: &mut String, name: &str, step: &CadenceToolStep) {
+    let runtime = step
+        .runtime()
+        .map_or_else(|| "-".to_owned(), |d| format!("{}s", d.as_secs()));
+    let _ = write!(
+        html,
+        "<tr><td>{name}</td>{}<td>{runtime}</td><td>{} {} {}</td></tr>",
+        status_cell(&step.run_status()),
+        link(&step.log_path(), &step.tool.log_name),
+        link(&step.script_path(), &step.tool.script_name),
+        link(&step.work_dir.join("reports"), "reports"),
+    );
+}
+
+fn qor_tables(html: &mut String, name: &str, qor: &Qor) {
+    let _ = write!(html, "<h3>{name} QoR</h3>");
+    if !qor.timing.is_empty() {
+        html.push_str(
+            "<table><tr><th>view</th><th>setup WNS</th><th>setup TNS</th><th>setup violations</th>\
+             <th>hold WNS</th><th>hold TNS</th><th>hold violations</th></tr>",
+        );
+        for view in &qor.timing {
+            let _ = write!(
+                html,
+                "<tr><td>{}</td>{}{}</tr>",
+                escape(&view.view),
+                slack_cells(view.setup.as_ref()),
+                slack_cells(view.hold.as_ref())
+            );
+        }
+        html.push_str("</table>");
+    }
+    html.push_str(
+        "<table><tr><th>area</th><th>cells</th><th>leakage (W)</th><th>dynamic (W)</th>\
+         <th>total power (W)</th></tr><tr>",
+    );
+    let top = qor.area.first();
+    let _ = write!(
+        html,
+        "<td>{}</td><td>{}</td>",
+        top.map_or_else(|| "-".into(), |a| a.total_area.to_string()),
+        top.map_or_else(|| "-".into(), |a| a.cell_count.to_string()),
+    );
+    match &qor.power {
+        Some(p) => {
+            let _ = write!(
+                html,
+                "<td>{}</td><td>{}</td><td>{}</td>",
+                p.leakage,
+                p.dynamic(),
+                p.total
+            );
+        }
+        None => html.push_str("<td>-</td><td>-</td><td>-</td>"),
+    }
+    html.push_str("</tr></table>");
+}
+
+fn module_section(flow: &Sky130FlatFlow, html: &mut String) {
+    let syn = flow.syn.get();
+    let par = flow.par.get();
+    let module = escape(&flow.module);
+    let _ = write!(html, "<section id=\"{module}\"><h2>{module}</h2>");
+
+    html.push_str("<table><tr><th>step</th><th>status</th><th>runtime</th><th>files</th></tr>");
+    step_row(html, "synthesis", &syn);
+    step_row(html, "place and route", &par);
+    html.push_str("</table>");
+
+    html.push_str("<table><tr><th>DRC</th><th>LVS</th></tr><tr>");
+    for signoff in [&flow.drc, &flow.lvs] {
+        let signoff = signoff.get();
+        html.push_str(outcome_cell(read_outcome(&signoff.work_dir, &signoff.func)));
+    }
+    html.push_str("</tr></table>");
+
+    if let Some(qor) = syn.qor.get().cloned().or_else(|| syn.read_qor().ok()) {
+        qor_tables(html, "Synthesis", &qor);
+    }
+    if let Some(qor) = par.qor.get().cloned().or_else(|| par.read_qor().ok()) {
+        qor_tables(html, "Place and route", &qor);
+    }
+    html.push_str("</section>");
+}
+
+fn sections(flow: &Dag<Sky130FlatFlow>, seen: &mut HashSet<String>, html: &mut String) {
+    if !seen.insert(flow.node.module.clone()) {
+        return;
+    }
+    module_section(&flow.node, html);
+    for child in &flow.directed_edges {
+        sections(child, seen, html);
+    }
+}
+
+/// Renders a self-contained HTML page summarizing every block of a hierarchical build: the
+/// hierarchy, step status and runtimes, QoR, sign-off outcomes and links to logs and reports
+pub fn html_report(flow: &Dag<Sky130FlatFlow>) -> String {
+    let top = escape(&flow.node.module);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{top} build report</title>\
+         <style>{STYLE}</style></head><body><h1>{top} build report</h1><h2>Hierarchy</h2><ul>"
+    );
+    tree(flow, &mut html);
+    html.push_str("</ul>");
+    sections(flow, &mut HashSet::new(), &mut html);
+    html.push_str("</body></html>\n");
+    html
+}
+
+pub fn write_html_report(flow: &Dag<Sky130FlatFlow>, path: &Path) -> io::Result<()> {
+    fs::write(path, html_report(flow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cadence::genus::GenusStep;
+    use cadence::innovus::InnovusStep;
+    use cadence::pegasus::{PegasusStep, outcome_path};
+    use rivet::StepRef;
+    use std::sync::Arc;
+
+    fn flow(
+        work_dir: &Path,
+        module: &str,
+        children: Vec<Dag<Sky130FlatFlow>>,
+    ) -> Dag<Sky130FlatFlow> {
+        let dir = work_dir.join(module);
+        let par = StepRef::new(InnovusStep::new(
+            dir.join("par"),
+            module,
+            vec![],
+            false,
+            vec![],
+            false,
+        ));
+        let signoff = |func: &str| {
+            StepRef::new(PegasusStep::new(
+                dir.join("par"),
+                func.into(),
+                module.into(),
+                false,
+                vec![],
+            ))
+        };
+        Dag {
+            node: Sky130FlatFlow {
+                module: module.into(),
+                syn: StepRef::new(GenusStep::new(
+                    dir.join("syn"),
+                    module,
+                    vec![],
+                    false,
+                    vec![],
+                )),
+                drc: signoff("drc"),
+                lvs: signoff("lvs"),
+                par,
+                submodules: vec![],
+            },
+            directed_edges: children.into_iter().map(Arc::new).collect(),
+        }
+    }
+
+    #[test]
+    fn reports_status_qor_and_signoff() {
+        let work_dir = std::env::temp_dir().join(format!("rivet-report-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&work_dir);
+        let top = flow(&work_dir, "top", vec![flow(&work_dir, "sub", vec![])]);
+
+        // top: synthesis completed with QoR, place and route failed, DRC passed
+        let syn = work_dir.join("top/syn");
+        fs::create_dir_all(syn.join("reports")).unwrap();
+        fs::write(syn.join("syn.log"), "").unwrap();
+        fs::write(syn.join("rivet_exit_status"), "0").unwrap();
+        fs::write(
+            syn.join("reports/final_power.rpt"),
+            "Power Unit: mW\n  Subtotal  0.001  0.2  0.1  0.301 100.00%\n",
+        )
+        .unwrap();
+        let par = work_dir.join("top/par");
+        fs::create_dir_all(&par).unwrap();
+        fs::write(par.join("par.log"), "").unwrap();
+        fs::write(
+            par.join("rivet_error.log"),
+            "route_design\nnet <a> & <b> shorted\n",
+        )
+        .unwrap();
+        fs::write(outcome_path(&par, "drc"), "passed").unwrap();
+        // sub: killed before it recorded an exit status
+        let sub = work_dir.join("sub/syn");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join("syn.log"), "").unwrap();
+
+        let html = html_report(&top);
+        fs::remove_dir_all(&work_dir).unwrap();
+        assert!(html.contains(
+            "<li><a href=\"#top\">top</a><ul><li><a href=\"#sub\">sub</a></li></ul></li>"
+        ));
+        assert!(html.contains("<td class=\"passed\">completed</td>"));
+        assert!(html.contains(
+            "<td class=\"failed\">failed in route_design: net &lt;a&gt; &amp; &lt;b&gt; shorted</td>"
+        ));
+        assert!(html.contains("<td class=\"failed\">failed: genus did not exit</td>"));
+        assert!(
+            html.contains("<td class=\"passed\">passed</td><td class=\"pending\">not run</td>")
+        );
+        assert!(html.contains("<h3>Synthesis QoR</h3>"));
+        assert!(html.contains("<td>0.000001</td><td>0.0003</td><td>0.000301</td>"));
+    }
+}
@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+
+use cadence::{CornerRole, MmmcConfig, MmmcCorner};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// Standard cell libraries shipped with the sky130 PDKs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellLibrary {
+    /// Cadence `sky130_scl_9T` library from the sky130_cds PDK
+    Scl9T,
+    /// Open source `sky130_fd_sc_hd` library
+    FdScHd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Process {
+    Slow,
+    Typical,
+    Fast,
+}
+
+/// A process, voltage and temperature corner at which a sky130 cell library is characterized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PvtCorner {
+    pub name: &'static str,
+    pub library: CellLibrary,
+    pub process: Process,
+    pub voltage: Decimal,
+    pub temperature: Decimal,
+    /// Liberty file characterized at this corner, relative to the PDK root
+    pub lib: &'static str,
+}
+
+impl PvtCorner {
+    /// Liberty file of the corner in the PDK installed at `pdk_root`
+    pub fn lib_path(&self, pdk_root: &Path) -> PathBuf {
+        pdk_root.join(self.lib)
+    }
+
+    pub fn mmmc_corner(&self, role: CornerRole, pdk_root: &Path) -> MmmcCorner {
+        MmmcCorner::new(
+            self.name,
+            role,
+            vec![self.lib_path(pdk_root)],
+            self.temperature,
+        )
+    }
+}
+
+/// The corners `library` is characterized at, with the voltage and temperature of its
+/// liberty files
+pub fn pvt_corners(library: CellLibrary) -> Vec<PvtCorner> {
+    let corner = |name, process, voltage, temperature, lib| PvtCorner {
+        name,
+        library,
+        process,
+        voltage,
+        temperature,
+        lib,
+    };
+    match library {
+        CellLibrary::Scl9T => vec![
+            corner(
+                "ss_125c_1v62",
+                Process::Slow,
+                dec!(1.62),
+                dec!(125.0),
+                "sky130/sky130_cds/sky130_scl_9T_0.0.5/lib/sky130_ss_1.62_125_nldm.lib",
+            ),
+            corner(
+                "ff_0c_1v98",
+                Process::Fast,
+                dec!(1.98),
+                dec!(0.0),
+                "sky130/sky130_cds/sky130_scl_9T_0.0.5/lib/sky130_ff_1.98_0_nldm.lib",
+            ),
+            corner(
+                "tt_025c_1v80",
+                Process::Typical,
+                dec!(1.80),
+                dec!(25.0),
+                "sky130/sky130_cds/sky130_scl_9T_0.0.5/lib/sky130_tt_1.8_25_nldm.lib",
+            ),
+        ],
+        CellLibrary::FdScHd => vec![
+            corner(
+                "ss_100c_1v60",
+                Process::Slow,
+                dec!(1.60),
+                dec!(100.0),
+                "libs.ref/sky130_fd_sc_hd/lib/sky130_fd_sc_hd__ss_100C_1v60.lib",
+            ),
+            corner(
+                "ff_n40c_1v95",
+                Process::Fast,
+                dec!(1.95),
+                dec!(-40.0),
+                "libs.ref/sky130_fd_sc_hd/lib/sky130_fd_sc_hd__ff_n40C_1v95_ccsnoise.lib",
+            ),
+            corner(
+                "tt_025c_1v80",
+                Process::Typical,
+                dec!(1.80),
+                dec!(25.0),
+                "libs.ref/sky130_fd_sc_hd/lib/sky130_fd_sc_hd__tt_025C_1v80.lib",
+            ),
+        ],
+    }
+}
+
+fn pvt_corner(library: CellLibrary, process: Process) -> PvtCorner {
+    pvt_corners(library)
+        .into_iter()
+        .find(|c| c.process == process)
+        .expect("every process has a corner")
+}
+
+/// Which corners a flow analyzes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CornerSet {
+    /// Setup at the slow corner, hold at the fast and typical corners, power at typical
+    Signoff,
+    /// Every view at the typical corner, for quick turnaround
+    FastIteration,
+}
+
+/// Builds the MMMC configuration analyzing `set` with `library`, constrained by `sdc_files`
+pub fn sky130_mmmc(
+    library: CellLibrary,
+    pdk_root: &Path,
+    set: CornerSet,
+    sdc_files: Vec<PathBuf>,
+) -> MmmcConfig {
+    let corner = |process| pvt_corner(library, process);
+    let config = match set {
+        CornerSet::Signoff => {
+            let slow = corner(Process::Slow).mmmc_corner(CornerRole::Setup, pdk_root);
+            let fast = corner(Process::Fast).mmmc_corner(CornerRole::Hold, pdk_root);
+            let typical = corner(Process::Typical).mmmc_corner(CornerRole::Extra, pdk_root);
+            MmmcConfig::new(
+                sdc_files,
+                vec![slow.clone(), fast.clone(), typical.clone()],
+                &[&slow.name],
+                &[&fast.name, &typical.name],
+                &typical.name,
+                &typical.name,
+            )
+        }
+        CornerSet::FastIteration => {
+            let typical = corner(Process::Typical).mmmc_corner(CornerRole::Setup, pdk_root);
+            MmmcConfig::new(
+                sdc_files,
+                vec![typical.clone()],
+                &[&typical.name],
+                &[&typical.name],
+                &typical.name,
+                &typical.name,
+            )
+        }
+    };
+    config.expect("sky130 corner sets reference their own corners")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corner_sets_build_valid_configs() {
+        let root = Path::new("/pdk");
+        let signoff = sky130_mmmc(CellLibrary::Scl9T, root, CornerSet::Signoff, vec![]);
+        assert_eq!(signoff.primary_setup().corner.name, "ss_125c_1v62");
+        assert_eq!(signoff.hold().len(), 2);
+        assert_eq!(
+            signoff.primary_setup().corner.libs,
+            vec![root.join("sky130/sky130_cds/sky130_scl_9T_0.0.5/lib/sky130_ss_1.62_125_nldm.lib")]
+        );
+
+        let hd = sky130_mmmc(CellLibrary::FdScHd, root, CornerSet::Signoff, vec![]);
+        assert_eq!(hd.primary_setup().corner.name, "ss_100c_1v60");
+
+        let fast = sky130_mmmc(CellLibrary::FdScHd, root, CornerSet::FastIteration, vec![]);
+        assert_eq!(fast.corners().len(), 1);
+        assert_eq!(fast.primary_hold().corner.name, "tt_025c_1v80");
+    }
+
+    #[test]
+    fn corners_match_their_liberty_files() {
+        let pvt = |library, process| {
+            let corner = pvt_corner(library, process);
+            (corner.voltage, corner.temperature)
+        };
+        assert_eq!(
+            pvt(CellLibrary::Scl9T, Process::Slow),
+            (dec!(1.62), dec!(125))
+        );
+        assert_eq!(
+            pvt(CellLibrary::Scl9T, Process::Fast),
+            (dec!(1.98), dec!(0))
+        );
+        assert_eq!(
+            pvt(CellLibrary::FdScHd, Process::Slow),
+            (dec!(1.60), dec!(100))
+        );
+        assert_eq!(
+            pvt(CellLibrary::FdScHd, Process::Fast),
+            (dec!(1.95), dec!(-40))
+        );
+    }
+}
@@ -1,12 +1,24 @@
+pub mod corners;
+
 use std::{
+    collections::HashMap,
     fs,
     fs::File,
     io::{BufRead, BufReader, BufWriter, Write},
     path::{Path, PathBuf},
 };
 
+use cadence::floorplan::FloorplanRules;
+use cadence::innovus::{EndcapCells, Side, TapCellSpec};
+use cadence::pin_alignment::AlignmentSpec;
+use cadence::power_grid::{
+    Direction, Followpins, GridTarget, MetalLayer, PowerGrid, SizingError, Stripes,
+};
 use cadence::Substep;
+use corners::CellLibrary;
 use indoc::formatdoc;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
 
 pub fn sky130_connect_nets() -> Substep {
     Substep {
@@ -31,6 +43,165 @@ pub fn sky130_connect_nets() -> Substep {
     }
 }
 
+/// Well tap and endcap cells of `library`, with taps close enough to meet the sky130 latchup
+/// rules
+pub fn sky130_tap_cells(library: CellLibrary) -> TapCellSpec {
+    match library {
+        CellLibrary::Scl9T => TapCellSpec {
+            tap_cell: "FILLTIE".into(),
+            pitch: dec!(20.0),
+            checkerboard: true,
+            endcaps: None,
+            macro_halo: None,
+        },
+        CellLibrary::FdScHd => TapCellSpec {
+            tap_cell: "sky130_fd_sc_hd__tapvpwrvgnd_1".into(),
+            pitch: dec!(13.0),
+            checkerboard: false,
+            endcaps: Some(EndcapCells {
+                left: "sky130_fd_sc_hd__decap_3".into(),
+                right: "sky130_fd_sc_hd__decap_3".into(),
+            }),
+            macro_halo: None,
+        },
+    }
+}
+
+/// Grids and layers of sky130 floorplans placed on the `unithd` site
+pub fn sky130_floorplan_rules() -> FloorplanRules {
+    FloorplanRules {
+        site_width: dec!(0.46),
+        row_height: dec!(2.72),
+        manufacturing_grid: dec!(0.005),
+        layers: [
+            "li1", "mcon", "met1", "via", "met2", "via2", "met3", "via3", "met4", "via4", "met5",
+        ]
+        .map(String::from)
+        .to_vec(),
+        macro_sizes: HashMap::new(),
+    }
+}
+
+/// Block pins on met3 along the left and right sides and on met4 along the top and bottom,
+/// one met4 track apart
+pub fn sky130_pin_alignment() -> AlignmentSpec {
+    AlignmentSpec {
+        horizontal_layers: vec!["met3".into()],
+        vertical_layers: vec!["met4".into()],
+        pitch: dec!(0.92),
+        margin: dec!(2.0),
+        unconnected_side: Side::Bottom,
+    }
+}
+
+/// Adjusts `nominal` so that a whole number of stripe sets spans `span`, never spacing them
+/// further apart than `nominal` nor closer than `min_pitch`
+fn fit_pitch(nominal: Decimal, span: Decimal, min_pitch: Decimal) -> Decimal {
+    let sets = (span / nominal).ceil().max(Decimal::ONE);
+    (span / sets).round_dp(2).max(min_pitch)
+}
+
+/// Power grid of a `core_width` by `core_height` block: rails on met1, vertical met4 stripes
+/// and horizontal met5 stripes carrying the block power pins
+pub fn sky130_power_grid(
+    library: CellLibrary,
+    core_width: Decimal,
+    core_height: Decimal,
+) -> PowerGrid {
+    let rail_width = match library {
+        CellLibrary::Scl9T => dec!(0.40),
+        CellLibrary::FdScHd => dec!(0.48),
+    };
+    let mut grid = PowerGrid::new(&["VSS", "VDD"]);
+    grid.followpins = Some(Followpins {
+        layer: "met1".into(),
+        width: rail_width,
+    });
+    let (met4_offset, met5_offset) = (dec!(7.35), dec!(5.62));
+    let nets = Decimal::from(grid.nets.len() as u64);
+    // Narrowest pitch that keeps a set of stripes clear of the next one
+    let min_pitch = |layer: &str, width: Decimal, spacing: Decimal| {
+        let min_spacing = sky130_metal_layer(layer)
+            .expect("sky130 stripes are on metal layers")
+            .min_spacing;
+        nets * width + (nets - Decimal::ONE) * spacing + min_spacing
+    };
+    let (met4_width, met4_spacing) = (dec!(1.86), dec!(3.66));
+    let (met5_width, met5_spacing) = (dec!(1.64), dec!(17.68));
+    grid.stripes = vec![
+        Stripes {
+            layer: "met4".into(),
+            direction: Direction::Vertical,
+            width: met4_width,
+            spacing: met4_spacing,
+            pitch: fit_pitch(
+                dec!(75.90),
+                core_width - met4_offset * Decimal::TWO,
+                min_pitch("met4", met4_width, met4_spacing),
+            ),
+            offset: met4_offset,
+            via_bottom_layer: "met1".into(),
+            spacing_from_block: dec!(2.000),
+            trim_antenna: true,
+            create_pins: false,
+        },
+        Stripes {
+            layer: "met5".into(),
+            direction: Direction::Horizontal,
+            width: met5_width,
+            spacing: met5_spacing,
+            pitch: fit_pitch(
+                dec!(225.40),
+                core_height - met5_offset * Decimal::TWO,
+                min_pitch("met5", met5_width, met5_spacing),
+            ),
+            offset: met5_offset,
+            via_bottom_layer: "met4".into(),
+            spacing_from_block: dec!(2.000),
+            trim_antenna: true,
+            create_pins: true,
+        },
+    ];
+    grid
+}
+
+/// Width, spacing and resistance rules of the sky130 metal layers
+pub fn sky130_metal_layer(name: &str) -> Option<MetalLayer> {
+    let (min_width, min_spacing, sheet_resistance) = match name {
+        "met1" | "met2" => (dec!(0.14), dec!(0.14), dec!(0.125)),
+        "met3" | "met4" => (dec!(0.30), dec!(0.30), dec!(0.047)),
+        "met5" => (dec!(1.60), dec!(1.60), dec!(0.0285)),
+        _ => return None,
+    };
+    Some(MetalLayer {
+        name: name.into(),
+        min_width,
+        max_width: None,
+        min_spacing,
+        sheet_resistance,
+    })
+}
+
+/// [`sky130_power_grid`] with stripes sized to meet `target`
+pub fn sky130_sized_power_grid(
+    library: CellLibrary,
+    core_width: Decimal,
+    core_height: Decimal,
+    target: &GridTarget,
+) -> Result<PowerGrid, SizingError> {
+    let mut grid = sky130_power_grid(library, core_width, core_height);
+    let nets = grid.nets.len();
+    for stripes in &mut grid.stripes {
+        let layer = sky130_metal_layer(&stripes.layer).expect("sky130 stripes are on metal layers");
+        let (length, span) = match stripes.direction {
+            Direction::Vertical => (core_height, core_width),
+            Direction::Horizontal => (core_width, core_height),
+        };
+        stripes.size_for(&layer, length, span, nets, target)?;
+    }
+    Ok(grid)
+}
+
 pub fn setup_techlef(working_directory: &Path, lef_file: &PathBuf) -> PathBuf {
     let cache_dir = working_directory.join("tech-sky130-cache");
     fs::create_dir_all(&cache_dir).expect("failed to create directory");
@@ -81,3 +252,21 @@ pub fn setup_techlef(working_directory: &Path, lef_file: &PathBuf) -> PathBuf {
     }
     tlef_path
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stripe_sets_never_overlap_or_thin_out() {
+        // 30 um blocks are narrower than one nominal pitch
+        let grid = sky130_power_grid(CellLibrary::FdScHd, dec!(30), dec!(30));
+        assert_eq!(grid.stripes[0].pitch, dec!(15.30));
+        assert_eq!(grid.stripes[1].pitch, dec!(22.56));
+
+        // Just over one nominal pitch gets two sets rather than one stretched set
+        let grid = sky130_power_grid(CellLibrary::FdScHd, dec!(100), dec!(500));
+        assert_eq!(grid.stripes[0].pitch, dec!(42.65));
+        assert_eq!(grid.stripes[1].pitch, dec!(162.92));
+    }
+}
@@ -4,7 +4,7 @@ use std::fmt::Debug;
 use std::sync::{Arc, Mutex, MutexGuard};
 pub mod bash;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Dag<F> {
     pub node: F,
     pub directed_edges: Vec<Arc<Dag<F>>>,